@@ -16,6 +16,7 @@ use std::mem::size_of;
 
 // The data for the benchmark, consiting of 8x64b=512b which is a cache line on most architectures
 #[derive(Column, Debug, Default, Clone)]
+#[column(project = "id")]
 struct Data {
     id: usize,
     val: f64,
@@ -40,6 +41,24 @@ fn data_column(b: &mut test::Bencher) {
     })
 }
 
+/// Perform assign operation on column type through a projection, input=output
+#[bench]
+fn data_column_projection(b: &mut test::Bencher) {
+    let size = 1 << 20;
+    let mut a = Vec::with_capacity(size);
+    for i in 0..size {
+        a.push(Data { id: i, val: 15., ..Data::default()});
+    }
+    let mut dc = <Data as Column>::with_capacity(size);
+    dc.extend(a);
+    b.bytes = (size_of::<usize>() * size) as u64;
+    b.iter(|| {
+        for e in dc.iter_id_mut() {
+            *e.id *= 2;
+        }
+    })
+}
+
 /// Perform add/assign operation on row type with three inputs, one output
 #[bench]
 fn data_column_add_assign(bench: &mut test::Bencher) {
@@ -125,7 +144,7 @@ fn data_bitmap_column_add_assign(bench: &mut test::Bencher) {
     cb.extend(b);
     test::black_box(r.first().unwrap().dummy);
     cr.extend(r);
-    let mut bitmap_container: FilteredCollection<_> = FilteredCollection::new(&ca, ca.len());
+    let mut bitmap_container: FilteredCollection<_> = FilteredCollection::new(&ca);
     // Retain every second element
     bitmap_container.retain(|d| d.id & 1 == 1);
     // We touch three values but the bitmap only exposes every second element
@@ -152,7 +171,7 @@ fn data_bitmap_vec_add_assign(bench: &mut test::Bencher) {
         r.push(Data { id: 0, val: 1., ..Data::default()});
     }
     test::black_box(r.first().unwrap().dummy);
-    let mut bitmap_container: FilteredCollection<_> = FilteredCollection::new(&a, a.len());
+    let mut bitmap_container: FilteredCollection<_> = FilteredCollection::new(&a);
     // Retain every second element
     bitmap_container.retain(|d| d.id & 1 == 1);
     // We touch three values but the bitmap only exposes every second element
@@ -166,6 +185,20 @@ fn data_bitmap_vec_add_assign(bench: &mut test::Bencher) {
     })
 }
 
+/// Sum a sparse selection of a column, exposing one element in 1024
+#[bench]
+fn data_bitmap_sparse_sum(bench: &mut test::Bencher) {
+    let size = 1 << 20;
+    let mut a = <Data as Column>::with_capacity(size);
+    a.extend((0..size).map(|i| Data { id: i, val: i as f64, ..Data::default()}));
+    let mut bitmap_container: FilteredCollection<_> = FilteredCollection::new(&a);
+    bitmap_container.retain(|d| d.id & 1023 == 0);
+    bench.bytes = (size_of::<f64>() * size / 1024) as u64;
+    bench.iter(|| {
+        test::black_box(bitmap_container.iter().map(|e| *e.val).sum::<f64>())
+    })
+}
+
 /// Perform assign operation on column type, input=output
 #[bench]
 fn data_row_to_column(b: &mut test::Bencher) {
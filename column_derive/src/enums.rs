@@ -0,0 +1,1067 @@
+// Copyright 2017 columnar-rs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+//! Derive a `Column` representation for an enum.
+//!
+//! An enum is stored as a tagged union: a discriminant column records the
+//! variant of every element, and each variant stores its fields densely in
+//! its own columns. An offset column maps every element to its position in
+//! the columns of its variant.
+//!
+//! The columns of a variant store its elements in order, which the iterators
+//! rely on. Moving an element across elements of other variants, e.g. when
+//! swapping elements of different variants, only moves the entries of the
+//! affected variants and updates the offsets in between.
+
+use quote;
+use syn;
+use syn::Ident;
+
+use ::{CLONE_LIFETIME, COLUMN_LIFETIME, ColumnTypes, ContainerOptions, build_column_trait_impls, build_derived_mutation_impl, build_from_iter_impl, build_gather_impl, column_attributes, lifetime_generics};
+
+/// A field of a variant together with the name of the column storing it.
+struct VariantField<'a> {
+    /// The column and binding name of this field, e.g. `v0_x` or `v1_0`. The
+    /// names are prefixed with the discriminant rather than the variant name,
+    /// which could collide, e.g. for `A { b_c }` and `AB { c }`.
+    column: Ident,
+    field: &'a syn::Field,
+}
+
+/// A variant of the enum and the columns of its fields.
+struct VariantColumns<'a> {
+    /// The discriminant value stored for this variant
+    discriminant: u8,
+    variant: &'a syn::Variant,
+    fields: Vec<VariantField<'a>>,
+}
+
+pub struct EnumData<'a> {
+    type_ref: Ident,
+    type_ref_mut: Ident,
+    type_container: Ident,
+    type_iter: Ident,
+    type_iter_mut: Ident,
+    type_into_iter: Ident,
+
+    ast: &'a syn::MacroInput,
+    variants: Vec<VariantColumns<'a>>,
+
+    lt_generics: syn::Generics,
+    /// The generics of the reference types, which only borrow the column if
+    /// any variant has fields
+    ref_generics: syn::Generics,
+}
+
+impl<'a> EnumData<'a> {
+
+    pub fn new(ast: &'a syn::MacroInput, variants: &'a [syn::Variant]) -> Self {
+        assert!(!variants.is_empty(), "At least one variant required");
+        assert!(variants.len() <= 256, "At most 256 variants supported");
+
+        let variants: Vec<_> = variants.iter().enumerate().map(|(discriminant, variant)| {
+            let fields = variant.data.fields().iter().enumerate().map(|(index, field)| {
+                assert!(column_attributes(&field.attrs).is_empty(), "Field attributes are not supported on enum variants");
+                let column = match field.ident {
+                    Some(ref ident) => Ident::from(format!("v{}_{}", discriminant, ident)),
+                    None => Ident::from(format!("v{}_{}", discriminant, index)),
+                };
+                VariantField { column, field }
+            }).collect();
+            VariantColumns { discriminant: discriminant as u8, variant, fields }
+        }).collect();
+
+        let options = ContainerOptions::new(ast);
+        assert!(options.projections.is_empty(), "Projections are not supported on enums");
+        let base_name = options.name;
+        let lt_generics = lifetime_generics(&ast.generics);
+        let ref_generics = if variants.iter().any(|v| !v.fields.is_empty()) { lt_generics.clone() } else { ast.generics.clone() };
+        Self {
+            type_ref: Ident::from(format!("{}Ref", base_name)),
+            type_ref_mut: Ident::from(format!("{}RefMut", base_name)),
+            type_container: Ident::from(format!("{}Column", base_name)),
+            type_iter: Ident::from(format!("{}ColumnIterator", base_name)),
+            type_iter_mut: Ident::from(format!("{}ColumnIteratorMut", base_name)),
+            type_into_iter: Ident::from(format!("{}ColumnIntoIterator", base_name)),
+            ast,
+            variants,
+            lt_generics,
+            ref_generics,
+        }
+    }
+
+    pub fn column_enum(&self) -> quote::Tokens {
+        let ref_tokens = self.build_ref_type(&self.type_ref, syn::Mutability::Immutable);
+        let ref_mut_tokens = self.build_ref_type(&self.type_ref_mut, syn::Mutability::Mutable);
+        let column_tokens = self.build_column_type();
+        let column_iterator_tokens = self.build_column_iterator_type(&self.type_iter, "::std::slice::Iter");
+        let column_iterator_mut_tokens = self.build_column_iterator_type(&self.type_iter_mut, "::std::slice::IterMut");
+        let column_into_iterator_tokens = self.build_column_into_iterator_type();
+        let container_impl = self.build_container_impl();
+        let extend_impl = self.build_extend_impl();
+        let into_iter_impl = self.build_into_iter_impl(false);
+        let into_iter_mut_impl = self.build_into_iter_impl(true);
+        let into_iter_owned_impl = self.build_into_iter_owned_impl();
+        let materialize_impl = self.build_materialize_impl();
+        let len_impl = self.build_len_impl();
+        let (impl_generics, ty_generics, where_clause) = self.ast.generics.split_for_impl();
+        let from_iter_impl = build_from_iter_impl(&self.ast.ident, &self.type_container, &impl_generics, &ty_generics, where_clause);
+        let ref_impl = self.build_ref_impl(&self.type_ref);
+        let ref_mut_impl = self.build_ref_impl(&self.type_ref_mut);
+        let column_iter_impl = self.build_column_iter_impl_iter(&self.type_iter, &self.type_ref, &self.lt_generics, &self.ref_generics, false);
+        let column_iter_mut_impl = self.build_column_iter_impl_iter(&self.type_iter_mut, &self.type_ref_mut, &self.lt_generics, &self.ref_generics, false);
+        let column_into_iter_impl = self.build_column_iter_impl_iter(&self.type_into_iter, &self.ast.ident, &self.ast.generics, &self.ast.generics, true);
+        quote! {
+
+            #ref_tokens
+
+            #ref_mut_tokens
+
+            #column_tokens
+
+            #column_iterator_tokens
+
+            #column_iterator_mut_tokens
+
+            #column_into_iterator_tokens
+
+            #container_impl
+
+            #extend_impl
+
+            #into_iter_impl
+
+            #into_iter_mut_impl
+
+            #into_iter_owned_impl
+
+            #from_iter_impl
+
+            #materialize_impl
+
+            #len_impl
+
+            #ref_impl
+
+            #ref_mut_impl
+
+            #column_iter_impl
+
+            #column_iter_mut_impl
+
+            #column_into_iter_impl
+        }
+    }
+
+    /// All columns of all variants, in declaration order
+    fn columns(&self) -> Vec<&VariantField<'a>> {
+        self.variants.iter().flat_map(|v| v.fields.iter()).collect()
+    }
+
+    /// The names of the fields of the column iterators
+    fn iter_names(&self) -> Vec<Ident> {
+        self.columns().iter().map(|c| Ident::new(format!("iter_{}", c.column))).collect()
+    }
+
+    /// A where clause bounding the type of every column by `Clone`
+    fn clone_bounds(&self) -> quote::Tokens {
+        let bounds = self.clone_predicates();
+        quote! { where #(#bounds),* }
+    }
+
+    /// The predicates of `clone_bounds`, see `CLONE_LIFETIME`
+    fn clone_predicates(&self) -> Vec<quote::Tokens> {
+        let lifetime = syn::Lifetime::new(CLONE_LIFETIME);
+        self.columns().iter().map(|c| {
+            let ref ty = c.field.ty;
+            quote! { for<#lifetime> #ty: Clone }
+        }).collect()
+    }
+
+    /// Build a pattern or constructor for a variant of type `name`. Each field
+    /// is represented by the tokens returned by `value`.
+    fn variant_expr<F>(&self, name: &Ident, variant: &VariantColumns, value: F) -> quote::Tokens
+        where F: Fn(&VariantField) -> quote::Tokens,
+    {
+        let ref ident = variant.variant.ident;
+        match variant.variant.data {
+            syn::VariantData::Struct(_) => {
+                let names: Vec<_> = variant.fields.iter().map(|f| f.field.ident.clone().unwrap()).collect();
+                let values: Vec<_> = variant.fields.iter().map(&value).collect();
+                quote! { #name::#ident { #(#names: #values),* } }
+            },
+            syn::VariantData::Tuple(_) => {
+                let values: Vec<_> = variant.fields.iter().map(&value).collect();
+                quote! { #name::#ident ( #(#values),* ) }
+            },
+            syn::VariantData::Unit => quote! { #name::#ident },
+        }
+    }
+
+    /// An array expression telling for each discriminant whether its variant
+    /// has fields. The offsets of variants without fields are always zero.
+    fn has_fields(&self) -> quote::Tokens {
+        let has_fields: Vec<_> = self.variants.iter().map(|v| !v.fields.is_empty()).collect();
+        quote! { [#(#has_fields),*] }
+    }
+
+    /// Build match arms on the discriminant that run the statement returned by
+    /// `statement` on every column of the variant. Variants without fields
+    /// have no arms.
+    fn column_arms<F>(&self, statement: F) -> Vec<quote::Tokens>
+        where F: Fn(&Ident) -> quote::Tokens,
+    {
+        self.variants.iter().filter(|v| !v.fields.is_empty()).map(|v| {
+            let discriminant = v.discriminant;
+            let statements: Vec<_> = v.fields.iter().map(|f| statement(&f.column)).collect();
+            quote! { #discriminant => { #(#statements)* }, }
+        }).collect()
+    }
+
+    fn build_ref_type(&self, name: &Ident, mutability: syn::Mutability) -> quote::Tokens {
+        let lifetime = syn::Lifetime::new(COLUMN_LIFETIME);
+
+        // Add same lifetime and mutability to the field refs
+        let variants: Vec<_> = self.variants.iter().map(|v| {
+            let mut variant = v.variant.clone();
+            variant.discriminant = None;
+            for f in variant.data.fields_mut() {
+                f.ty = syn::Ty::Rptr(Some(lifetime.clone()), Box::new(syn::MutTy { ty: f.ty.clone(), mutability }));
+            }
+            variant
+        }).collect();
+        let (_impl_generics, ty_generics, where_clause) = self.ref_generics.split_for_impl();
+        let ref vis = self.ast.vis;
+        quote! {
+            #[derive(Debug)]
+            #[allow(dead_code)]
+            #vis enum #name #ty_generics #where_clause {
+                #(#variants),*
+            }
+        }
+    }
+
+    fn build_column_type(&self) -> quote::Tokens {
+        let ref name = self.type_container;
+
+        let columns = self.columns();
+        let names: Vec<_> = columns.iter().map(|c| &c.column).collect();
+        let types: Vec<_> = columns.iter().map(|c| &c.field.ty).collect();
+        let (_impl_generics, ty_generics, where_clause) = self.ast.generics.split_for_impl();
+        let ref vis = self.ast.vis;
+        quote! {
+            #[derive(Debug)]
+            #[allow(dead_code)]
+            #vis struct #name #ty_generics #where_clause {
+                discriminant: Vec<u8>,
+                offset: Vec<usize>,
+                #(#names: Vec<#types>),*
+            }
+        }
+    }
+
+    fn build_column_iterator_type(&self, name: &Ident, iter_type_name: &str) -> quote::Tokens {
+        let iters = self.iter_names();
+        let types: Vec<_> = self.columns().iter().map(|c| &c.field.ty).collect();
+        let iter_type = ::std::iter::repeat(Ident::new(iter_type_name));
+        let lifetime = ::std::iter::repeat(Ident::from(COLUMN_LIFETIME));
+
+        let (_impl_generics, ty_generics, where_clause) = self.lt_generics.split_for_impl();
+        let ref vis = self.ast.vis;
+        quote! {
+            #[derive(Debug)]
+            #[allow(dead_code)]
+            #vis struct #name #ty_generics #where_clause {
+                iter_discriminant: ::std::slice::Iter<'column, u8>,
+                #(#iters: #iter_type<#lifetime, #types>),*
+            }
+        }
+    }
+
+    fn build_column_into_iterator_type(&self) -> quote::Tokens {
+        let ref name = self.type_into_iter;
+        let iters = self.iter_names();
+        let types: Vec<_> = self.columns().iter().map(|c| &c.field.ty).collect();
+
+        let (_impl_generics, ty_generics, where_clause) = self.ast.generics.split_for_impl();
+        let ref vis = self.ast.vis;
+        quote! {
+            #[derive(Debug)]
+            #[allow(dead_code)]
+            #vis struct #name #ty_generics #where_clause {
+                iter_discriminant: ::std::vec::IntoIter<u8>,
+                #(#iters: ::std::vec::IntoIter<#types>),*
+            }
+        }
+    }
+
+    fn build_container_impl(&self) -> quote::Tokens {
+        let ref type_container = self.type_container;
+        let ref type_column = self.ast.ident;
+        let ref type_ref = self.type_ref;
+        let ref type_ref_mut = self.type_ref_mut;
+        let ref type_iter = self.type_iter;
+        let ref type_iter_mut = self.type_iter_mut;
+
+        let (_impl_generics, ty_generics, _where_clause) = self.ast.generics.split_for_impl();
+        let (lt_impl_generics, _lt_ty_generics, lt_where_clause) = self.lt_generics.split_for_impl();
+
+        let names: &Vec<_> = &self.columns().into_iter().map(|c| c.column.clone()).collect();
+        let iters: &Vec<_> = &self.iter_names();
+
+        let push = self.build_column_push_impl();
+        let index = self.build_column_index_impl(false);
+        let index_mut = self.build_column_index_impl(true);
+        let get = self.build_column_get_impl();
+        let insert = self.build_column_insert_impl();
+        let remove = self.build_column_remove_impl();
+        let mutation = self.build_column_mutation_impl();
+        let derived_mutation = build_derived_mutation_impl(type_column, &ty_generics);
+        let gather = build_gather_impl(type_column, &ty_generics, &self.clone_bounds());
+
+        let new = quote! {
+            fn new() -> Self::Output {
+                #type_container {
+                    discriminant: Vec::new(),
+                    offset: Vec::new(),
+                    #(#names: Vec::new()),*
+                }
+            }
+        };
+        // Only the discriminant and offset columns hold an entry for every
+        // element, the columns of the variants grow on push
+        let with_capacity = quote! {
+            fn with_capacity(capacity: usize) -> Self::Output {
+                #type_container {
+                    discriminant: Vec::with_capacity(capacity),
+                    offset: Vec::with_capacity(capacity),
+                    #(#names: Vec::new()),*
+                }
+            }
+        };
+        let types = ColumnTypes {
+            container: type_container,
+            reference: type_ref,
+            reference_mut: type_ref_mut,
+            iter: type_iter,
+            iter_mut: type_iter_mut,
+            ref_generics: &self.ref_generics,
+        };
+        let column_trait_impls = build_column_trait_impls(type_column, &types, &self.ast.generics, &self.clone_predicates(), &new, &with_capacity);
+
+        quote! {
+            #[allow(dead_code)]
+            impl#lt_impl_generics #type_container #ty_generics #lt_where_clause {
+
+                fn iter(&self) -> #type_iter #ty_generics {
+                    #type_iter {
+                        iter_discriminant: self.discriminant.iter(),
+                        #(#iters: self.#names.iter()),*
+                    }
+                }
+
+                fn iter_mut(&mut self) -> #type_iter_mut #ty_generics {
+                    #type_iter_mut {
+                        iter_discriminant: self.discriminant.iter(),
+                        #(#iters: self.#names.iter_mut()),*
+                    }
+                }
+
+                fn len(&self) -> usize {
+                    self.discriminant.len()
+                }
+
+                fn is_empty(&self) -> bool {
+                    self.discriminant.is_empty()
+                }
+
+                fn clear(&mut self) {
+                    self.discriminant.clear();
+                    self.offset.clear();
+                    #(self.#names.clear();)*
+                }
+
+                fn reserve(&mut self, additional: usize) {
+                    self.discriminant.reserve(additional);
+                    self.offset.reserve(additional);
+                }
+
+                fn capacity(&self) -> usize {
+                    self.discriminant.capacity().min(self.offset.capacity())
+                }
+
+                #push
+                #index
+                #index_mut
+                #get
+                #insert
+                #remove
+                #mutation
+                #derived_mutation
+                #gather
+            }
+
+            #column_trait_impls
+        }
+    }
+
+    fn build_column_push_impl(&self) -> quote::Tokens {
+        let ref type_column = self.ast.ident;
+        let (_impl_generics, ty_generics, _where_clause) = self.ast.generics.split_for_impl();
+
+        let arms: Vec<_> = self.variants.iter().map(|v| {
+            let pattern = self.variant_expr(type_column, v, |f| {
+                let ref column = f.column;
+                quote! { #column }
+            });
+            let discriminant = v.discriminant;
+            let offset = match v.fields.first() {
+                Some(f) => {
+                    let ref column = f.column;
+                    quote! { self.#column.len() }
+                },
+                None => quote! { 0 },
+            };
+            let columns: Vec<_> = v.fields.iter().map(|f| f.column.clone()).collect();
+            let values = columns.clone();
+            quote! {
+                #pattern => {
+                    self.discriminant.push(#discriminant);
+                    self.offset.push(#offset);
+                    #(self.#columns.push(#values);)*
+                }
+            }
+        }).collect();
+
+        quote! {
+            fn push(&mut self, element: #type_column #ty_generics) {
+                match element {
+                    #(#arms)*
+                }
+            }
+        }
+    }
+
+    fn build_column_index_impl(&self, mutable: bool) -> quote::Tokens {
+        let (_impl_generics, ty_generics, _where_clause) = self.ast.generics.split_for_impl();
+
+        let (fn_name, type_name, modifier, bounds) = if mutable {
+            (Ident::new("index_mut"), &self.type_ref_mut, Ident::new("mut"), quote! {})
+        } else {
+            (Ident::new("index"), &self.ast.ident, Ident::new(""), self.clone_bounds())
+        };
+
+        let arms: Vec<_> = self.variants.iter().map(|v| {
+            let discriminant = v.discriminant;
+            let value = self.variant_expr(type_name, v, |f| {
+                let ref column = f.column;
+                if mutable { quote! { &mut self.#column[offset] } } else { quote! { Clone::clone(&self.#column[offset]) } }
+            });
+            quote! { #discriminant => #value, }
+        }).collect();
+
+        quote! {
+            fn #fn_name(& #modifier self, index: usize) -> #type_name #ty_generics #bounds {
+                let offset = self.offset[index];
+                match self.discriminant[index] {
+                    #(#arms)*
+                    _ => unreachable!(),
+                }
+            }
+        }
+    }
+
+    fn build_column_get_impl(&self) -> quote::Tokens {
+        let ref type_ref = self.type_ref;
+        let (_impl_generics, ty_generics, _where_clause) = self.ast.generics.split_for_impl();
+
+        let arms: Vec<_> = self.variants.iter().map(|v| {
+            let discriminant = v.discriminant;
+            let value = self.variant_expr(type_ref, v, |f| {
+                let ref column = f.column;
+                quote! { &self.#column[offset] }
+            });
+            quote! { #discriminant => #value, }
+        }).collect();
+
+        quote! {
+            fn get(&self, index: usize) -> Option<#type_ref #ty_generics> {
+                let offset = *self.offset.get(index)?;
+                Some(match self.discriminant[index] {
+                    #(#arms)*
+                    _ => unreachable!(),
+                })
+            }
+        }
+    }
+
+    fn build_column_insert_impl(&self) -> quote::Tokens {
+        let ref type_column = self.ast.ident;
+        let (_impl_generics, ty_generics, _where_clause) = self.ast.generics.split_for_impl();
+
+        let arms: Vec<_> = self.variants.iter().map(|v| {
+            let pattern = self.variant_expr(type_column, v, |f| {
+                let ref column = f.column;
+                quote! { #column }
+            });
+            let discriminant = v.discriminant;
+            if v.fields.is_empty() {
+                return quote! {
+                    #pattern => {
+                        self.discriminant.insert(index, #discriminant);
+                        self.offset.insert(index, 0);
+                    }
+                };
+            }
+            let columns: Vec<_> = v.fields.iter().map(|f| f.column.clone()).collect();
+            let values = columns.clone();
+            quote! {
+                #pattern => {
+                    let offset = self.discriminant[..index].iter().filter(|&&d| d == #discriminant).count();
+                    for (d, o) in self.discriminant[index..].iter().zip(&mut self.offset[index..]) {
+                        if *d == #discriminant {
+                            *o += 1;
+                        }
+                    }
+                    self.discriminant.insert(index, #discriminant);
+                    self.offset.insert(index, offset);
+                    #(self.#columns.insert(offset, #values);)*
+                }
+            }
+        }).collect();
+
+        quote! {
+            fn insert(&mut self, index: usize, element: #type_column #ty_generics) {
+                match element {
+                    #(#arms)*
+                }
+            }
+        }
+    }
+
+    fn build_column_remove_impl(&self) -> quote::Tokens {
+        let ref type_column = self.ast.ident;
+        let (_impl_generics, ty_generics, _where_clause) = self.ast.generics.split_for_impl();
+
+        // Take the fields of an element out of the columns of its variant with
+        // `method`
+        let take_arms = |method: &str| -> Vec<quote::Tokens> {
+            let method = Ident::new(method);
+            self.variants.iter().map(|v| {
+                let discriminant = v.discriminant;
+                let value = self.variant_expr(type_column, v, |f| {
+                    let ref column = f.column;
+                    quote! { self.#column.#method(offset) }
+                });
+                quote! { #discriminant => #value, }
+            }).collect()
+        };
+        let remove_arms = take_arms("remove");
+        let swap_remove_arms = take_arms("swap_remove");
+        let rotate_arms = self.column_arms(|column| quote! { self.#column[position..].rotate_right(1); });
+        let has_fields = self.has_fields();
+
+        let arms: Vec<_> = self.variants.iter().map(|v| {
+            let discriminant = v.discriminant;
+            let value = self.variant_expr(type_column, v, |f| {
+                let ref column = f.column;
+                quote! { self.#column.remove(offset) }
+            });
+            if v.fields.is_empty() {
+                return quote! { #discriminant => #value, };
+            }
+            quote! {
+                #discriminant => {
+                    for (d, o) in self.discriminant[index..].iter().zip(&mut self.offset[index..]) {
+                        if *d == #discriminant {
+                            *o -= 1;
+                        }
+                    }
+                    #value
+                },
+            }
+        }).collect();
+
+        quote! {
+            fn remove(&mut self, index: usize) -> #type_column #ty_generics {
+                let discriminant = self.discriminant.remove(index);
+                let offset = self.offset.remove(index);
+                match discriminant {
+                    #(#arms)*
+                    _ => unreachable!(),
+                }
+            }
+
+            fn swap_remove(&mut self, index: usize) -> #type_column #ty_generics {
+                assert!(index < self.len(), "swap_remove index {} is out of bounds for length {}", index, self.len());
+                let (discriminant, offset) = (self.discriminant[index], self.offset[index]);
+                let moved = self.discriminant.pop().unwrap();
+                let mut position = self.offset.pop().unwrap();
+                if discriminant == moved {
+                    // The last element is the last of its variant as well, so
+                    // it takes the place of the removed one in its columns.
+                    return match discriminant {
+                        #(#swap_remove_arms)*
+                        _ => unreachable!(),
+                    };
+                }
+                // The columns of a variant store its elements in order. The
+                // moved element goes before the elements of its variant after
+                // `index`, which move back by one, and the elements of the
+                // removed variant move forward.
+                let has_fields = #has_fields;
+                for (d, o) in self.discriminant[index + 1..].iter().zip(&mut self.offset[index + 1..]) {
+                    if !has_fields[*d as usize] {
+                        continue;
+                    }
+                    if *d == discriminant {
+                        *o -= 1;
+                    } else if *d == moved {
+                        *o += 1;
+                        position -= 1;
+                    }
+                }
+                self.discriminant[index] = moved;
+                self.offset[index] = position;
+                match moved {
+                    #(#rotate_arms)*
+                    _ => {},
+                }
+                match discriminant {
+                    #(#remove_arms)*
+                    _ => unreachable!(),
+                }
+            }
+        }
+    }
+
+    fn build_column_mutation_impl(&self) -> quote::Tokens {
+        let ref type_column = self.ast.ident;
+        let ref type_ref = self.type_ref;
+        let (_impl_generics, ty_generics, _where_clause) = self.ast.generics.split_for_impl();
+        let names: &Vec<_> = &self.columns().into_iter().map(|c| c.column.clone()).collect();
+        let variants: Vec<_> = self.variants.iter().filter(|v| !v.fields.is_empty()).collect();
+
+        let swap_arms = self.column_arms(|column| quote! { self.#column.swap(offset_a, offset_b); });
+        let rotate_left_arms = self.column_arms(|column| quote! { self.#column[offset_a..=position_a].rotate_left(1); });
+        let rotate_right_arms = self.column_arms(|column| quote! { self.#column[position_b..=offset_b].rotate_right(1); });
+        let truncate_columns: Vec<_> = self.variants.iter().flat_map(|v| {
+            let index = v.discriminant as usize;
+            v.fields.iter().map(move |f| {
+                let ref column = f.column;
+                quote! {
+                    let len = self.#column.len() - removed[#index];
+                    self.#column.truncate(len);
+                }
+            })
+        }).collect();
+        let variant_count = self.variants.len();
+        let has_fields = self.has_fields();
+        let mut split_off_columns = vec![];
+        let mut append_columns = vec![];
+        let mut retain_columns = vec![];
+        let mut retain_offsets = vec![];
+        for v in variants {
+            let discriminant = v.discriminant;
+            let columns: &Vec<_> = &v.fields.iter().map(|f| f.column.clone()).collect();
+            let columns2 = columns;
+            let ref first = columns[0];
+            split_off_columns.push(quote! {
+                {
+                    let count = self.discriminant.iter().filter(|&&d| d == #discriminant).count();
+                    for (d, o) in other.discriminant.iter().zip(&mut other.offset) {
+                        if *d == #discriminant {
+                            *o -= count;
+                        }
+                    }
+                    #(other.#columns = self.#columns2.split_off(count);)*
+                }
+            });
+            append_columns.push(quote! {
+                {
+                    let count = self.#first.len();
+                    for (d, o) in other.discriminant.iter().zip(&mut other.offset) {
+                        if *d == #discriminant {
+                            *o += count;
+                        }
+                    }
+                    #(self.#columns.append(&mut other.#columns2);)*
+                }
+            });
+            retain_columns.push(quote! {
+                {
+                    let keep: Vec<bool> = self.discriminant.iter().zip(&keep)
+                        .filter(|&(&d, _)| d == #discriminant)
+                        .map(|(_, &keep)| keep)
+                        .collect();
+                    #({
+                        let mut keep = keep.iter();
+                        self.#columns.retain(|_| *keep.next().unwrap());
+                    })*
+                }
+            });
+            retain_offsets.push(quote! {
+                {
+                    let mut offset = 0;
+                    for (d, o) in self.discriminant.iter().zip(&mut self.offset) {
+                        if *d == #discriminant {
+                            *o = offset;
+                            offset += 1;
+                        }
+                    }
+                }
+            });
+        }
+        // Drop the elements whose flag in `keep` is unset
+        let retain_keep = quote! {
+            #(#retain_columns)*
+            {
+                let mut keep = keep.iter();
+                self.discriminant.retain(|_| *keep.next().unwrap());
+            }
+            {
+                let mut keep = keep.iter();
+                self.offset.retain(|_| *keep.next().unwrap());
+            }
+            #(#retain_offsets)*
+        };
+
+        quote! {
+            fn pop(&mut self) -> Option<#type_column #ty_generics> {
+                if self.is_empty() {
+                    return None;
+                }
+                let last = self.len() - 1;
+                Some(self.remove(last))
+            }
+
+            fn truncate(&mut self, len: usize) {
+                if len >= self.len() {
+                    return;
+                }
+                let mut removed = [0usize; #variant_count];
+                for discriminant in self.discriminant.drain(len..) {
+                    removed[discriminant as usize] += 1;
+                }
+                self.offset.truncate(len);
+                #(#truncate_columns)*
+            }
+
+            fn split_off(&mut self, at: usize) -> Self {
+                let mut other = Self {
+                    discriminant: self.discriminant.split_off(at),
+                    offset: self.offset.split_off(at),
+                    #(#names: Vec::new()),*
+                };
+                #(#split_off_columns)*
+                other
+            }
+
+            fn append(&mut self, other: &mut Self) {
+                #(#append_columns)*
+                self.discriminant.append(&mut other.discriminant);
+                self.offset.append(&mut other.offset);
+            }
+
+            fn retain<F>(&mut self, f: F) where F: FnMut(#type_ref #ty_generics) -> bool {
+                let keep: Vec<bool> = self.iter().map(f).collect();
+                #retain_keep
+            }
+
+            fn swap(&mut self, a: usize, b: usize) {
+                let (a, b) = if a <= b { (a, b) } else { (b, a) };
+                let (discriminant_a, discriminant_b) = (self.discriminant[a], self.discriminant[b]);
+                let (offset_a, offset_b) = (self.offset[a], self.offset[b]);
+                if discriminant_a == discriminant_b {
+                    match discriminant_a {
+                        #(#swap_arms)*
+                        _ => {},
+                    }
+                    return;
+                }
+                // Elements of different variants change their position
+                // relative to the elements of their variant between `a` and
+                // `b`. Only these elements move in the variant's columns.
+                let (mut position_a, mut position_b) = (offset_a, offset_b);
+                let has_fields = #has_fields;
+                for (d, o) in self.discriminant[a + 1..b].iter().zip(&mut self.offset[a + 1..b]) {
+                    if !has_fields[*d as usize] {
+                        continue;
+                    }
+                    if *d == discriminant_a {
+                        *o -= 1;
+                        position_a += 1;
+                    } else if *d == discriminant_b {
+                        *o += 1;
+                        position_b -= 1;
+                    }
+                }
+                self.discriminant.swap(a, b);
+                self.offset[a] = position_b;
+                self.offset[b] = position_a;
+                match discriminant_a {
+                    #(#rotate_left_arms)*
+                    _ => {},
+                }
+                match discriminant_b {
+                    #(#rotate_right_arms)*
+                    _ => {},
+                }
+            }
+
+            fn retain_by_bitmap<S: ::column::bitmap::BitSet>(&mut self, bitmap: &S) {
+                assert_eq!(bitmap.len(), self.len(), "Bitmap length must match the column length");
+                let mut keep = vec![false; self.len()];
+                for index in bitmap.iter_ones() {
+                    keep[index] = true;
+                }
+                #retain_keep
+            }
+
+            fn shrink_to_fit(&mut self) {
+                self.discriminant.shrink_to_fit();
+                self.offset.shrink_to_fit();
+                #(self.#names.shrink_to_fit();)*
+            }
+        }
+    }
+
+    fn build_extend_impl(&self) -> quote::Tokens {
+        let ref name = self.ast.ident;
+        let ref type_container = self.type_container;
+        let (_impl_generics, ty_generics, _where_clause) = self.ast.generics.split_for_impl();
+        let (lt_impl_generics, _lt_ty_generics, lt_where_clause) = self.lt_generics.split_for_impl();
+
+        quote! {
+            impl #lt_impl_generics Extend<#name#ty_generics> for #type_container #ty_generics #lt_where_clause {
+                fn extend<T: IntoIterator<Item=#name#ty_generics>>(&mut self, iter: T) {
+                    for element in iter {
+                        self.push(element);
+                    }
+                }
+            }
+        }
+    }
+
+    fn build_into_iter_impl(&self, mutable: bool) -> quote::Tokens {
+        let ref type_container = self.type_container;
+
+        let (lt_impl_generics, lt_ty_generics, _lt_where_clause) = self.lt_generics.split_for_impl();
+        let (_ref_impl_generics, ref_ty_generics, _ref_where_clause) = self.ref_generics.split_for_impl();
+        let (_impl_generics, ty_generics, where_clause) = self.ast.generics.split_for_impl();
+
+        let (mut_modifier, item, iter, call) = if mutable {
+            (Ident::new("mut"), &self.type_ref_mut, &self.type_iter_mut, Ident::new("iter_mut"))
+        } else {
+            (Ident::new(""), &self.type_ref, &self.type_iter, Ident::new("iter"))
+        };
+        let lifetime = Ident::from(COLUMN_LIFETIME);
+        quote! {
+            impl#lt_impl_generics IntoIterator for &#lifetime #mut_modifier #type_container #ty_generics #where_clause {
+                type Item = #item#ref_ty_generics;
+                type IntoIter = #iter#lt_ty_generics;
+                fn into_iter(self) -> Self::IntoIter {
+                    self.#call()
+                }
+            }
+        }
+    }
+
+    fn build_into_iter_owned_impl(&self) -> quote::Tokens {
+        let ref name = self.ast.ident;
+        let ref type_container = self.type_container;
+        let ref type_into_iter = self.type_into_iter;
+        let (impl_generics, ty_generics, where_clause) = self.ast.generics.split_for_impl();
+
+        let names: &Vec<_> = &self.columns().into_iter().map(|c| c.column.clone()).collect();
+        let iters: &Vec<_> = &self.iter_names();
+        quote! {
+            impl #impl_generics IntoIterator for #type_container #ty_generics #where_clause {
+                type Item = #name #ty_generics;
+                type IntoIter = #type_into_iter #ty_generics;
+                fn into_iter(self) -> Self::IntoIter {
+                    #type_into_iter {
+                        iter_discriminant: self.discriminant.into_iter(),
+                        #(#iters: self.#names.into_iter()),*
+                    }
+                }
+            }
+        }
+    }
+
+    fn build_len_impl(&self) -> quote::Tokens {
+        let ref type_container = self.type_container;
+        let (impl_generics, ty_generics, where_clause) = self.ast.generics.split_for_impl();
+        quote! {
+            impl #impl_generics ::column::Len for #type_container #ty_generics #where_clause {
+                fn len(&self) -> usize {
+                    self.discriminant.len()
+                }
+
+                fn is_empty(&self) -> bool {
+                    self.discriminant.is_empty()
+                }
+            }
+        }
+    }
+
+    fn build_materialize_impl(&self) -> quote::Tokens {
+        let ref type_container = self.type_container;
+        let (impl_generics, ty_generics, where_clause) = self.ast.generics.split_for_impl();
+        let clone_predicates = self.clone_predicates();
+        let ref predicates = where_clause.predicates;
+        let names: &Vec<_> = &self.columns().into_iter().map(|c| c.column.clone()).collect();
+        let names2 = names;
+        let variants: Vec<_> = self.variants.iter()
+            .flat_map(|v| v.fields.iter().map(move |_| v.discriminant as usize))
+            .collect();
+        let variant_count = self.variants.len();
+        let has_fields = self.has_fields();
+        quote! {
+            impl #impl_generics ::column::bitmap::Materialize for #type_container #ty_generics
+                where #(#predicates,)* #(#clone_predicates),*
+            {
+                type Output = Self;
+                fn materialize<S: ::column::bitmap::BitSet>(&self, bitmap: &S) -> Self {
+                    use ::column::bitmap::BitSet;
+                    assert_eq!(bitmap.len(), self.len(), "Bitmap length must match the column length");
+                    let len = bitmap.count_ones();
+                    let mut discriminant = Vec::with_capacity(len);
+                    let mut offset = Vec::with_capacity(len);
+                    // The offsets of the selected elements of each variant
+                    let mut selected = vec![Vec::new(); #variant_count];
+                    let has_fields = #has_fields;
+                    for index in bitmap.iter_ones() {
+                        let d = self.discriminant[index];
+                        discriminant.push(d);
+                        if has_fields[d as usize] {
+                            let selected = &mut selected[d as usize];
+                            offset.push(selected.len());
+                            selected.push(self.offset[index]);
+                        } else {
+                            offset.push(0);
+                        }
+                    }
+                    Self {
+                        discriminant,
+                        offset,
+                        #(#names: selected[#variants].iter().map(|&o| Clone::clone(&self.#names2[o])).collect()),*
+                    }
+                }
+            }
+        }
+    }
+
+    fn build_ref_impl(&self, type_ref: &Ident) -> quote::Tokens {
+        let ref name = self.ast.ident;
+
+        let arms: Vec<_> = self.variants.iter().map(|v| {
+            let pattern = self.variant_expr(type_ref, v, |f| {
+                let ref column = f.column;
+                quote! { ref #column }
+            });
+            let value = self.variant_expr(name, v, |f| {
+                let ref column = f.column;
+                quote! { Clone::clone(&**#column) }
+            });
+            quote! { #pattern => #value, }
+        }).collect();
+
+        let clone_bounds = self.clone_bounds();
+        let (ref_impl_generics, ref_ty_generics, ref_where_clause) = self.ref_generics.split_for_impl();
+        let (_impl_generics, ty_generics, _where_clause) = self.ast.generics.split_for_impl();
+
+        quote! {
+            #[allow(dead_code)]
+            impl #ref_impl_generics #type_ref #ref_ty_generics #ref_where_clause {
+                fn to_owned(&self) -> #name#ty_generics #clone_bounds {
+                    match *self {
+                        #(#arms)*
+                    }
+                }
+            }
+        }
+    }
+
+    /// Implement the iterator traits for `type_iter` yielding `type_ref` with
+    /// `ref_generics`. The discriminant iterator yields owned values if `owned`
+    /// is set.
+    fn build_column_iter_impl_iter(&self, type_iter: &Ident, type_ref: &Ident, generics: &syn::Generics, ref_generics: &syn::Generics, owned: bool) -> quote::Tokens {
+        let deref = if owned { quote! {} } else { quote! { * } };
+
+        // Elements of a variant are stored in order, so the iterators of the
+        // variant's columns only advance on elements of that variant.
+        let variant_arms = |method: &str| -> Vec<quote::Tokens> {
+            self.variants.iter().map(|v| {
+                let discriminant = v.discriminant;
+                let method = Ident::new(method);
+                let value = self.variant_expr(type_ref, v, |f| {
+                    let iter = Ident::new(format!("iter_{}", f.column));
+                    quote! { self.#iter.#method()? }
+                });
+                quote! { #discriminant => #value, }
+            }).collect()
+        };
+        let arms = variant_arms("next");
+        let back_arms = variant_arms("next_back");
+
+        // Skip the elements of every variant in its columns at once
+        let skip_arms: Vec<_> = self.variants.iter().filter(|v| !v.fields.is_empty()).map(|v| {
+            let discriminant = v.discriminant;
+            let index = discriminant as usize;
+            let iters: Vec<_> = v.fields.iter().map(|f| Ident::new(format!("iter_{}", f.column))).collect();
+            quote! {
+                if skipped[#index] > 0 {
+                    let n = skipped[#index] - 1;
+                    #(self.#iters.nth(n);)*
+                }
+            }
+        }).collect();
+        let variant_count = self.variants.len();
+
+        let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+        let (_ref_impl_generics, ref_ty_generics, _ref_where_clause) = ref_generics.split_for_impl();
+
+        quote! {
+            impl #impl_generics Iterator for #type_iter #ty_generics #where_clause {
+                type Item = #type_ref #ref_ty_generics;
+
+                fn next(&mut self) -> Option<Self::Item> {
+                    Some(match #deref self.iter_discriminant.next()? {
+                        #(#arms)*
+                        _ => unreachable!(),
+                    })
+                }
+
+                fn size_hint(&self) -> (usize, Option<usize>) {
+                    self.iter_discriminant.size_hint()
+                }
+
+                fn nth(&mut self, n: usize) -> Option<Self::Item> {
+                    let mut skipped = [0usize; #variant_count];
+                    for discriminant in self.iter_discriminant.by_ref().take(n) {
+                        skipped[#deref discriminant as usize] += 1;
+                    }
+                    #(#skip_arms)*
+                    self.next()
+                }
+            }
+
+            impl #impl_generics DoubleEndedIterator for #type_iter #ty_generics #where_clause {
+                fn next_back(&mut self) -> Option<Self::Item> {
+                    Some(match #deref self.iter_discriminant.next_back()? {
+                        #(#back_arms)*
+                        _ => unreachable!(),
+                    })
+                }
+            }
+
+            impl #impl_generics ExactSizeIterator for #type_iter #ty_generics #where_clause {}
+
+            impl #impl_generics ::std::iter::FusedIterator for #type_iter #ty_generics #where_clause {}
+        }
+    }
+}
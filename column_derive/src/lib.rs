@@ -4,7 +4,7 @@
 // http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
 // http://opensource.org/licenses/MIT>, at your option. This file may not be
 // copied, modified, or distributed except according to those terms.
-//! Derive a `Column` representation for a struct.
+//! Derive a `Column` representation for a struct or an enum.
 //!
 //! # Examples
 //!
@@ -16,7 +16,7 @@
 //! # fn main() {}
 //! ```
 
-#![recursion_limit="128"]
+#![recursion_limit="256"]
 
 #![cfg(not(test))]
 
@@ -31,16 +31,29 @@ extern crate rustfmt;
 use proc_macro::TokenStream;
 use syn::Ident;
 
+mod enums;
+mod projections;
+mod slices;
+
 const COLUMN_LIFETIME: &str = "'column";
 
+/// The lifetime bound by the `Clone` predicates of the generated methods.
+/// Predicates without generics must hold where they are declared, so binding
+/// a lifetime lets a column with non-`Clone` fields derive, leaving out only
+/// the methods that clone.
+const CLONE_LIFETIME: &str = "'clone";
+
 #[doc(hidden)]
-#[proc_macro_derive(Column)]
+#[proc_macro_derive(Column, attributes(column))]
 pub fn derive_column(input: TokenStream) -> TokenStream {
     let source = input.to_string();
     let ast = syn::parse_macro_input(&source).expect("Couldn't parse source");
 
     let result = match ast.body {
-        syn::Body::Enum(_) => panic!("Enum not supported!"),
+        syn::Body::Enum(ref variants) => {
+            let enum_data = enums::EnumData::new(&ast, variants);
+            enum_data.column_enum()
+        }
         syn::Body::Struct(ref variant_data) => {
             let columar_data = ColumnData::new(&ast, &variant_data);
             columar_data.column_struct()
@@ -50,7 +63,7 @@ pub fn derive_column(input: TokenStream) -> TokenStream {
     let result_string = result.to_string();
     if cfg!(feature = "verbose") {
         match print_generated_code(&result_string, &ast, source) {
-            Err(reason) => panic!(reason),
+            Err(reason) => panic!("{}", reason),
             Ok(_) => {},
         }
     }
@@ -87,15 +100,535 @@ fn print_generated_code(result_string: &String, ast: &syn::MacroInput, source: S
 fn print_generated_code(_result_string: &String, _ast: &syn::MacroInput, _source: String) -> ::std::io::Result<()> {
     Ok(())
 }
+/// Extend `generics` by the `'column` lifetime and bound every type parameter by it.
+fn lifetime_generics(generics: &syn::Generics) -> syn::Generics {
+    let mut lt_generics = generics.clone();
+    lt_generics.lifetimes.push(syn::LifetimeDef::new(COLUMN_LIFETIME));
+
+    // Add a where X: 'lifetime to every generic parameter
+    for ty_param in &lt_generics.ty_params {
+        let mut segment = syn::PathSegment::from(ty_param.ident.clone());
+        let parameter_data = syn::AngleBracketedParameterData {
+            lifetimes: vec![],
+            types: vec![],
+            bindings: vec![],
+        };
+        segment.parameters = syn::PathParameters::AngleBracketed(parameter_data);
+
+        let where_bound = syn::WhereBoundPredicate {
+            bound_lifetimes: vec![],
+            bounded_ty: syn::Ty::Path(None, syn::Path::from(segment)),
+            bounds: vec![syn::TyParamBound::Region(syn::Lifetime::new(COLUMN_LIFETIME))],
+        };
+        lt_generics.where_clause.predicates.push(syn::WherePredicate::BoundPredicate(where_bound));
+    }
+    lt_generics
+}
+
+/// Build the type `<ty as ::column::Column>::Output`
+fn column_output_ty(ty: &syn::Ty) -> syn::Ty {
+    let path = syn::Path {
+        global: true,
+        segments: vec![syn::PathSegment::from("column"), syn::PathSegment::from("Column"), syn::PathSegment::from("Output")],
+    };
+    syn::Ty::Path(Some(syn::QSelf { ty: Box::new(ty.clone()), position: 2 }), path)
+}
+
+/// Build the type `<<ty as ::column::Column>::Output as ::column::ColumnContainer>::assoc<'column>`
+fn column_container_ty(ty: &syn::Ty, assoc: &str) -> syn::Ty {
+    let mut assoc_segment = syn::PathSegment::from(assoc);
+    assoc_segment.parameters = syn::PathParameters::AngleBracketed(syn::AngleBracketedParameterData {
+        lifetimes: vec![syn::Lifetime::new(COLUMN_LIFETIME)],
+        types: vec![],
+        bindings: vec![],
+    });
+    let path = syn::Path {
+        global: true,
+        segments: vec![syn::PathSegment::from("column"), syn::PathSegment::from("ColumnContainer"), assoc_segment],
+    };
+    syn::Ty::Path(Some(syn::QSelf { ty: Box::new(column_output_ty(ty)), position: 2 }), path)
+}
+
+/// The items of all `#[column(...)]` attributes in `attrs`
+fn column_attributes(attrs: &[syn::Attribute]) -> Vec<&syn::MetaItem> {
+    let mut items = vec![];
+    for attr in attrs {
+        match attr.value {
+            syn::MetaItem::List(ref ident, ref nested) if ident == "column" => {
+                for item in nested {
+                    match *item {
+                        syn::NestedMetaItem::MetaItem(ref item) => items.push(item),
+                        syn::NestedMetaItem::Literal(ref lit) => panic!("Unsupported column attribute: {:?}", lit),
+                    }
+                }
+            },
+            syn::MetaItem::Word(ref ident) | syn::MetaItem::NameValue(ref ident, _) if ident == "column" => {
+                panic!("Expected #[column(...)]")
+            },
+            _ => {},
+        }
+    }
+    items
+}
+
+/// Implement the iterator traits for `type_iter`, which yields `type_ref`s
+/// holding the items of the field iterators `iters` in the fields `names`. All
+/// field iterators have the same length.
+fn build_iterator_impl(type_iter: &Ident, type_ref: &Ident, generics: &syn::Generics, names: &[Ident], iters: &[Ident]) -> quote::Tokens {
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let ref first = iters[0];
+
+    quote! {
+        impl #impl_generics Iterator for #type_iter #ty_generics #where_clause {
+            type Item = #type_ref #ty_generics;
+
+            fn next(&mut self) -> Option<Self::Item> {
+                Some(Self::Item {
+                    #(#names: self.#iters.next()?),*
+                })
+            }
+
+            fn size_hint(&self) -> (usize, Option<usize>) {
+                self.#first.size_hint()
+            }
+
+            fn nth(&mut self, n: usize) -> Option<Self::Item> {
+                Some(Self::Item {
+                    #(#names: self.#iters.nth(n)?),*
+                })
+            }
+        }
+
+        impl #impl_generics DoubleEndedIterator for #type_iter #ty_generics #where_clause {
+            fn next_back(&mut self) -> Option<Self::Item> {
+                Some(Self::Item {
+                    #(#names: self.#iters.next_back()?),*
+                })
+            }
+        }
+
+        impl #impl_generics ExactSizeIterator for #type_iter #ty_generics #where_clause {}
+
+        impl #impl_generics ::std::iter::FusedIterator for #type_iter #ty_generics #where_clause {}
+    }
+}
+
+/// Implement `FromIterator` for the column `type_container` of the derived
+/// type `name`
+fn build_from_iter_impl(name: &Ident, type_container: &Ident, impl_generics: &syn::ImplGenerics,
+                        ty_generics: &syn::TyGenerics, where_clause: &syn::WhereClause) -> quote::Tokens {
+    quote! {
+        impl #impl_generics ::std::iter::FromIterator<#name #ty_generics> for #type_container #ty_generics #where_clause {
+            fn from_iter<T: IntoIterator<Item=#name #ty_generics>>(iter: T) -> Self {
+                let iter = iter.into_iter();
+                let mut column = <#name #ty_generics as ::column::Column>::with_capacity(iter.size_hint().0);
+                column.extend(iter);
+                column
+            }
+        }
+    }
+}
+
+/// The generated types of a derived column
+struct ColumnTypes<'a> {
+    container: &'a Ident,
+    reference: &'a Ident,
+    reference_mut: &'a Ident,
+    iter: &'a Ident,
+    iter_mut: &'a Ident,
+    /// The generics of `reference` and `reference_mut`, which lack the
+    /// `'column` lifetime if the references borrow nothing
+    ref_generics: &'a syn::Generics,
+}
+
+/// Implement `Column` for the derived type `name`, and `ColumnContainer` and
+/// `CloneContainer` for its column, delegating to the inherent methods of the
+/// column and its references. `new` and `with_capacity` construct the column,
+/// and `clone_predicates` bound the `CloneContainer` impl.
+fn build_column_trait_impls(name: &Ident, types: &ColumnTypes, generics: &syn::Generics, clone_predicates: &[quote::Tokens],
+                            new: &quote::Tokens, with_capacity: &quote::Tokens) -> quote::Tokens {
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let ref predicates = where_clause.predicates;
+    let lt_generics = lifetime_generics(generics);
+    let (_lt_impl_generics, lt_ty_generics, _lt_where_clause) = lt_generics.split_for_impl();
+    let lifetime = syn::Lifetime::new(COLUMN_LIFETIME);
+    let ColumnTypes { container, reference, reference_mut, iter, iter_mut, ref_generics } = *types;
+    let (_ref_impl_generics, ref_ty_generics, _ref_where_clause) = ref_generics.split_for_impl();
+
+    quote! {
+        impl #impl_generics ::column::Column for #name #ty_generics #where_clause {
+            type Output = #container #ty_generics;
+
+            #new
+
+            #with_capacity
+        }
+
+        impl #impl_generics ::column::ColumnContainer for #container #ty_generics #where_clause {
+            type Element = #name #ty_generics;
+            type Ref<#lifetime> = #reference #ref_ty_generics where Self: #lifetime;
+            type RefMut<#lifetime> = #reference_mut #ref_ty_generics where Self: #lifetime;
+            type Iter<#lifetime> = #iter #lt_ty_generics where Self: #lifetime;
+            type IterMut<#lifetime> = #iter_mut #lt_ty_generics where Self: #lifetime;
+
+            fn with_capacity(capacity: usize) -> Self {
+                <#name #ty_generics as ::column::Column>::with_capacity(capacity)
+            }
+
+            fn capacity(&self) -> usize {
+                self.capacity()
+            }
+
+            fn reserve(&mut self, additional: usize) {
+                self.reserve(additional)
+            }
+
+            fn shrink_to_fit(&mut self) {
+                self.shrink_to_fit()
+            }
+
+            fn push(&mut self, element: Self::Element) {
+                self.push(element)
+            }
+
+            fn pop(&mut self) -> Option<Self::Element> {
+                self.pop()
+            }
+
+            fn insert(&mut self, index: usize, element: Self::Element) {
+                self.insert(index, element)
+            }
+
+            fn remove(&mut self, index: usize) -> Self::Element {
+                self.remove(index)
+            }
+
+            fn swap_remove(&mut self, index: usize) -> Self::Element {
+                self.swap_remove(index)
+            }
+
+            fn swap(&mut self, a: usize, b: usize) {
+                self.swap(a, b)
+            }
+
+            fn truncate(&mut self, len: usize) {
+                self.truncate(len)
+            }
+
+            fn split_off(&mut self, at: usize) -> Self {
+                self.split_off(at)
+            }
+
+            fn append(&mut self, other: &mut Self) {
+                self.append(other)
+            }
+
+            fn clear(&mut self) {
+                self.clear()
+            }
+
+            fn get(&self, index: usize) -> Option<Self::Ref<'_>> {
+                self.get(index)
+            }
+
+            fn index_mut(&mut self, index: usize) -> Self::RefMut<'_> {
+                self.index_mut(index)
+            }
+
+            fn iter(&self) -> Self::Iter<'_> {
+                self.iter()
+            }
+
+            fn iter_mut(&mut self) -> Self::IterMut<'_> {
+                self.iter_mut()
+            }
+        }
+
+        impl #impl_generics ::column::CloneContainer for #container #ty_generics
+            where #(#predicates,)* #(#clone_predicates),*
+        {
+            fn to_owned(reference: &Self::Ref<'_>) -> Self::Element {
+                #reference::to_owned(reference)
+            }
+
+            fn to_owned_mut(reference: &Self::RefMut<'_>) -> Self::Element {
+                #reference_mut::to_owned(reference)
+            }
+
+            fn index(&self, index: usize) -> Self::Element {
+                self.index(index)
+            }
+        }
+    }
+}
+
+/// Options of the derived type, configured through `#[column(...)]` attributes
+struct ContainerOptions {
+    /// The base name of the generated types, `Data` for `DataColumn`, `DataRef`
+    /// etc. Defaults to the name of the derived type.
+    name: String,
+    /// The fields of every projection, given by their name or position
+    projections: Vec<Vec<Ident>>,
+    /// Do not generate slices, which is required for structs with nested
+    /// fields
+    no_slices: bool,
+}
+
+impl ContainerOptions {
+    fn new(ast: &syn::MacroInput) -> Self {
+        let mut options = ContainerOptions { name: ast.ident.to_string(), projections: vec![], no_slices: false };
+        for item in column_attributes(&ast.attrs) {
+            match *item {
+                syn::MetaItem::NameValue(ref ident, syn::Lit::Str(ref value, _)) if ident == "name" => options.name = value.clone(),
+                syn::MetaItem::NameValue(ref ident, syn::Lit::Str(ref value, _)) if ident == "project" => {
+                    let fields: Vec<_> = value.split(',').map(|field| field.trim()).map(|field| {
+                        match field.parse::<usize>() {
+                            Ok(index) => Ident::from(index),
+                            Err(_) => Ident::from(field),
+                        }
+                    }).collect();
+                    assert!(fields.iter().all(|field| !field.as_ref().is_empty()), "Empty field in projection: {}", value);
+                    options.projections.push(fields);
+                },
+                syn::MetaItem::Word(ref ident) if ident == "no_slices" => options.no_slices = true,
+                _ => panic!("Unsupported container attribute: {:?}", item),
+            }
+        }
+        options
+    }
+}
+
+/// The base name of the generated types, see `ContainerOptions::name`
+fn base_name(ast: &syn::MacroInput) -> String {
+    ContainerOptions::new(ast).name
+}
+
+/// Build the methods of a column type that are expressed through its `len`,
+/// `push`, `truncate`, `split_off` and `append` methods. `name` is the derived
+/// type.
+fn build_derived_mutation_impl(name: &Ident, ty_generics: &syn::TyGenerics) -> quote::Tokens {
+    quote! {
+        fn resize_with<F>(&mut self, new_len: usize, mut f: F) where F: FnMut() -> #name #ty_generics {
+            let len = self.len();
+            if new_len > len {
+                self.reserve(new_len - len);
+                for _ in len..new_len {
+                    self.push(f());
+                }
+            } else {
+                self.truncate(new_len);
+            }
+        }
+
+        /// Remove the elements in `range` and return them as a new column,
+        /// shifting the elements after the range to close the gap
+        fn split_range<R>(&mut self, range: R) -> Self where R: ::std::ops::RangeBounds<usize> {
+            use std::ops::Bound;
+            let start = match range.start_bound() {
+                Bound::Included(&start) => start,
+                Bound::Excluded(&start) => start + 1,
+                Bound::Unbounded => 0,
+            };
+            let end = match range.end_bound() {
+                Bound::Included(&end) => end + 1,
+                Bound::Excluded(&end) => end,
+                Bound::Unbounded => self.len(),
+            };
+            assert!(start <= end, "split_range start {} is greater than end {}", start, end);
+            assert!(end <= self.len(), "split_range end {} is out of bounds for length {}", end, self.len());
+            let mut tail = self.split_off(end);
+            let range = self.split_off(start);
+            self.append(&mut tail);
+            range
+        }
+
+        /// Remove the elements in `range` and return an iterator over them,
+        /// shifting the elements after the range to close the gap. Unlike
+        /// `Vec::drain`, the elements are removed even if the iterator is not
+        /// consumed.
+        fn drain<R>(&mut self, range: R) -> <Self as IntoIterator>::IntoIter where R: ::std::ops::RangeBounds<usize> {
+            self.split_range(range).into_iter()
+        }
+    }
+}
+
+/// Build `gather`, which copies the elements at the given indices into a new
+/// column, for the column of type `name`
+fn build_gather_impl(name: &Ident, ty_generics: &syn::TyGenerics, clone_bounds: &quote::Tokens) -> quote::Tokens {
+    quote! {
+        fn gather<S: IntoIterator<Item=usize>>(&self, selection: S) -> Self #clone_bounds {
+            let selection = selection.into_iter();
+            let mut column = <#name #ty_generics as ::column::Column>::with_capacity(selection.size_hint().0);
+            for index in selection {
+                column.push(self.index(index));
+            }
+            column
+        }
+    }
+}
+
+/// Options of a field, configured through `#[column(...)]` attributes
+#[derive(Default)]
+struct FieldOptions {
+    /// Store the field in the columnar representation of its type
+    nested: bool,
+    /// Do not store the field, and use its `Default` when materializing elements
+    skip: bool,
+    /// A `Vec`-like container to store the field in instead of `Vec`
+    storage: Option<syn::Path>,
+}
+
+impl FieldOptions {
+    fn new(field: &syn::Field) -> Self {
+        let mut options = Self::default();
+        for item in column_attributes(&field.attrs) {
+            match *item {
+                syn::MetaItem::Word(ref ident) if ident == "nested" => options.nested = true,
+                syn::MetaItem::Word(ref ident) if ident == "skip" => options.skip = true,
+                syn::MetaItem::NameValue(ref ident, syn::Lit::Str(ref value, _)) if ident == "storage" => {
+                    options.storage = Some(syn::parse_path(value).expect("Couldn't parse storage path"));
+                },
+                _ => panic!("Unsupported field attribute: {:?}", item),
+            }
+        }
+        let exclusive = [options.nested, options.skip, options.storage.is_some()];
+        assert!(exclusive.iter().filter(|&&set| set).count() <= 1,
+                "Field attributes nested, skip and storage are mutually exclusive");
+        options
+    }
+}
+
+/// A field of the derived type that is stored in a column
+struct ColumnField<'a> {
+    /// The name of the field in the derived type
+    name: Ident,
+    /// The name of the field in the generated types. Differs from `name` for
+    /// tuple structs with skipped fields.
+    column: Ident,
+    field: &'a syn::Field,
+    options: FieldOptions,
+}
+
+impl<'a> ColumnField<'a> {
+    /// The type of the container storing this field
+    fn storage_ty(&self) -> syn::Ty {
+        if self.options.nested {
+            return column_output_ty(&self.field.ty);
+        }
+        let mut path = self.options.storage.clone().unwrap_or_else(|| syn::Path::from("Vec"));
+        let parameter_data = syn::AngleBracketedParameterData {
+            lifetimes: vec![],
+            types: vec![self.field.ty.clone()],
+            bindings: vec![],
+        };
+        path.segments.last_mut().unwrap().parameters = syn::PathParameters::AngleBracketed(parameter_data);
+        syn::Ty::Path(None, path)
+    }
+
+    /// The type of a reference to this field, a reference to its type or the
+    /// reference type of its nested column
+    fn ref_ty(&self, mutability: syn::Mutability) -> syn::Ty {
+        if self.options.nested {
+            let assoc = match mutability {
+                syn::Mutability::Immutable => "Ref",
+                syn::Mutability::Mutable => "RefMut",
+            };
+            return column_container_ty(&self.field.ty, assoc);
+        }
+        let lifetime = syn::Lifetime::new(COLUMN_LIFETIME);
+        syn::Ty::Rptr(Some(lifetime), Box::new(syn::MutTy { ty: self.field.ty.clone(), mutability }))
+    }
+
+    /// The type of an iterator over this field's column, a slice iterator or
+    /// the iterator type of its nested column
+    fn iter_ty(&self, mutability: syn::Mutability) -> syn::Ty {
+        let (iter_type_name, nested_iter_type) = match mutability {
+            syn::Mutability::Immutable => ("::std::slice::Iter", "Iter"),
+            syn::Mutability::Mutable => ("::std::slice::IterMut", "IterMut"),
+        };
+        if self.options.nested {
+            return column_container_ty(&self.field.ty, nested_iter_type);
+        }
+        let mut segment = syn::PathSegment::from(syn::Ident::new(iter_type_name));
+        let parameter_data = syn::AngleBracketedParameterData {
+            lifetimes: vec![syn::Lifetime::new(COLUMN_LIFETIME)],
+            types: vec![self.field.ty.clone()],
+            bindings: vec![],
+        };
+        segment.parameters = syn::PathParameters::AngleBracketed(parameter_data);
+        syn::Ty::Path(None, syn::Path::from(segment))
+    }
+
+    /// The type of the owning iterator over this field's column,
+    /// `<Storage as IntoIterator>::IntoIter`
+    fn owned_iter_ty(&self) -> syn::Ty {
+        let path = syn::Path {
+            global: true,
+            segments: vec![
+                syn::PathSegment::from("std"),
+                syn::PathSegment::from("iter"),
+                syn::PathSegment::from("IntoIterator"),
+                syn::PathSegment::from("IntoIter"),
+            ],
+        };
+        syn::Ty::Path(Some(syn::QSelf { ty: Box::new(self.storage_ty()), position: 3 }), path)
+    }
+
+    /// Call `method` with `args` on this field's column in `self`. Nested
+    /// columns are called through `Len` or `ColumnContainer`, which need not
+    /// be in scope, and other columns by method call syntax.
+    fn call(&self, method: &str, args: quote::Tokens) -> quote::Tokens {
+        let ref column = self.column;
+        let method = Ident::new(method);
+        if !self.options.nested {
+            return quote! { self.#column.#method(#args) };
+        }
+        match method.as_ref() {
+            "len" | "is_empty" => quote! { ::column::Len::#method(&self.#column) },
+            "capacity" | "iter" => quote! { ::column::ColumnContainer::#method(&self.#column) },
+            _ => quote! { ::column::ColumnContainer::#method(&mut self.#column, #args) },
+        }
+    }
+
+    /// The name of this field in the column iterators
+    fn iter_name(&self) -> Ident {
+        match self.field.ident {
+            Some(ref ident) => Ident::new(format!("iter_{}", ident)),
+            None => self.column.clone(),
+        }
+    }
+
+    /// The name of the accessor of this field's column, the field's name or
+    /// `col` followed by its position for tuple structs
+    fn accessor(&self) -> Ident {
+        match self.field.ident {
+            Some(ref ident) => ident.clone(),
+            None => Ident::from(format!("col{}", self.name)),
+        }
+    }
+
+    /// The type borrowed from this field's column by its accessor, a slice of
+    /// the field's type or the nested column
+    fn accessor_ty(&self) -> syn::Ty {
+        if self.options.nested {
+            self.storage_ty()
+        } else {
+            syn::Ty::Slice(Box::new(self.field.ty.clone()))
+        }
+    }
+}
+
 struct ColumnData<'a> {
     type_ref: Ident,
     type_ref_mut: Ident,
     type_container: Ident,
     type_iter: Ident,
     type_iter_mut: Ident,
+    type_into_iter: Ident,
 
     ast: &'a syn::MacroInput,
     fields: &'a [syn::Field],
+    columns: Vec<ColumnField<'a>>,
 
     lt_generics: syn::Generics,
 }
@@ -104,46 +637,41 @@ impl<'a> ColumnData<'a> {
 
     fn new(ast: &'a syn::MacroInput, variant_data: &'a syn::VariantData) -> Self {
         let fields = match *variant_data {
-            syn::VariantData::Struct(ref fields) => fields,
-            syn::VariantData::Tuple(ref elements) => panic!("Unsupported content: {:?}", elements),
+            syn::VariantData::Struct(ref fields) | syn::VariantData::Tuple(ref fields) => fields,
             syn::VariantData::Unit => panic!("Unsupported content: Unit"),
         };
-        let type_ref: Ident = Ident::from(format!("{}Ref", ast.ident));
-        let type_ref_mut: Ident = Ident::from(format!("{}RefMut", ast.ident));
-        let type_container: Ident = Ident::from(format!("{}Column", ast.ident));
-        let type_iter: Ident = Ident::from(format!("{}ColumnIterator", ast.ident));
-        let type_iter_mut: Ident = Ident::from(format!("{}ColumnIteratorMut", ast.ident));
-
-        let mut lt_generics = ast.generics.clone();
-        lt_generics.lifetimes.push(syn::LifetimeDef::new(COLUMN_LIFETIME));
-
-
-        // Add a where X: 'lifetime to every generic parameter
-        for ty_param in &lt_generics.ty_params {
-            let mut segment = syn::PathSegment::from(ty_param.ident.clone());
-            let parameter_data = syn::AngleBracketedParameterData {
-                lifetimes: vec![],
-                types: vec![],
-                bindings: vec![],
-            };
-            segment.parameters = syn::PathParameters::AngleBracketed(parameter_data);
-
-            let where_bound = syn::WhereBoundPredicate {
-                bound_lifetimes: vec![],
-                bounded_ty: syn::Ty::Path(None, syn::Path::from(segment)),
-                bounds: vec![syn::TyParamBound::Region(syn::Lifetime::new(COLUMN_LIFETIME))],
-            };
-            lt_generics.where_clause.predicates.push(syn::WherePredicate::BoundPredicate(where_bound));
+        let base_name = base_name(ast);
+        let type_ref: Ident = Ident::from(format!("{}Ref", base_name));
+        let type_ref_mut: Ident = Ident::from(format!("{}RefMut", base_name));
+        let type_container: Ident = Ident::from(format!("{}Column", base_name));
+        let type_iter: Ident = Ident::from(format!("{}ColumnIterator", base_name));
+        let type_iter_mut: Ident = Ident::from(format!("{}ColumnIteratorMut", base_name));
+        let type_into_iter: Ident = Ident::from(format!("{}ColumnIntoIterator", base_name));
+
+        let lt_generics = lifetime_generics(&ast.generics);
+
+        let mut columns = vec![];
+        for (index, field) in fields.iter().enumerate() {
+            let options = FieldOptions::new(field);
+            if options.skip {
+                continue;
+            }
+            let name = field.ident.clone().unwrap_or_else(|| Ident::from(index));
+            let column = field.ident.clone().unwrap_or_else(|| Ident::from(columns.len()));
+            columns.push(ColumnField { name, column, field, options });
         }
+        assert!(!columns.is_empty(), "{} has no stored fields, at least one field must not be skipped", ast.ident);
 
         Self {
             ast,
             fields,
+            columns,
             type_ref,
             type_ref_mut,
             type_container,
             type_iter,
             type_iter_mut,
+            type_into_iter,
             lt_generics,
         }
     }
@@ -153,10 +681,92 @@ impl<'a> ColumnData<'a> {
     }
 
     fn get_first_field_name(&self) -> syn::Ident {
-        if let Some(name) = self.fields.first().expect("At least one field required").clone().ident {
-            name
+        self.field_names().into_iter().next().expect("At least one field required")
+    }
+
+    /// The names of the stored fields in the generated types, where tuple
+    /// fields are named by their position
+    fn field_names(&self) -> Vec<syn::Ident> {
+        self.columns.iter().map(|c| c.column.clone()).collect()
+    }
+
+    /// The names of the fields of the column iterators
+    fn iter_names(&self) -> Vec<syn::Ident> {
+        self.columns.iter().map(|c| c.iter_name()).collect()
+    }
+
+    /// Build one expression per stored field: `plain` for fields stored in a
+    /// `Vec` or custom storage, and `nested` for fields stored in a nested
+    /// column.
+    fn field_exprs<P, N>(&self, plain: P, nested: N) -> Vec<quote::Tokens>
+        where P: Fn(&ColumnField) -> quote::Tokens,
+              N: Fn(&ColumnField) -> quote::Tokens,
+    {
+        self.columns.iter().map(|c| {
+            if c.options.nested { nested(c) } else { plain(c) }
+        }).collect()
+    }
+
+    /// Build an owned instance of the derived type. Stored fields are set to
+    /// the expression returned by `value`, skipped fields to their default.
+    fn build_owned<F>(&self, value: F) -> quote::Tokens
+        where F: Fn(&ColumnField) -> quote::Tokens,
+    {
+        let ref name = self.ast.ident;
+        let mut columns = self.columns.iter().peekable();
+        let (names, values): (Vec<_>, Vec<_>) = self.fields.iter().enumerate().map(|(index, f)| {
+            let name = f.ident.clone().unwrap_or_else(|| Ident::from(index));
+            match columns.peek() {
+                Some(c) if c.name == name => (name, value(columns.next().unwrap())),
+                _ => (name, quote! { Default::default() }),
+            }
+        }).unzip();
+        quote! {
+            #name { #(#names: #values),* }
+        }
+    }
+
+    /// A where clause bounding the type of every field stored in a `Vec` or
+    /// custom storage by `Clone`, and the column of every nested field by
+    /// `CloneContainer`, or nothing if there are no stored fields.
+    fn clone_bounds(&self) -> quote::Tokens {
+        let bounds = self.clone_predicates();
+        if bounds.is_empty() {
+            return quote! {};
+        }
+        quote! { where #(#bounds),* }
+    }
+
+    /// The predicates of `clone_bounds`, see `CLONE_LIFETIME`
+    fn clone_predicates(&self) -> Vec<quote::Tokens> {
+        let lifetime = syn::Lifetime::new(CLONE_LIFETIME);
+        self.field_exprs(|c| {
+            let ref ty = c.field.ty;
+            quote! { for<#lifetime> #ty: Clone }
+        }, |c| {
+            let storage_ty = c.storage_ty();
+            quote! { for<#lifetime> #storage_ty: ::column::CloneContainer }
+        })
+    }
+
+    /// Declare a struct `name` with `fields`, using tuple struct syntax if the
+    /// derived type is a tuple struct
+    fn build_struct(&self, name: &Ident, generics: &syn::Generics, fields: Vec<syn::Field>) -> quote::Tokens {
+        let (_impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+        let ref vis = self.ast.vis;
+        let fields: Vec<_> = fields.into_iter().map(|mut f| {
+            f.attrs.retain(|attr| attr.name() != "column");
+            f
+        }).collect();
+        let body = if fields.iter().any(|f| f.ident.is_none()) {
+            quote! { (#(#fields),*) #where_clause; }
         } else {
-            syn::Ident::new("0")
+            quote! { #where_clause { #(#fields),* } }
+        };
+        quote! {
+            #[derive(Debug)]
+            #[allow(dead_code)]
+            #vis struct #name #ty_generics #body
         }
     }
 
@@ -164,17 +774,25 @@ impl<'a> ColumnData<'a> {
         let ref_tokens = self.build_ref_type();
         let ref_mut_tokens = self.build_ref_mut_type();
         let column_tokens = self.build_column_type();
-        let column_iterator_tokens = self.build_column_iterator_type(&self.type_iter, "::std::slice::Iter");
-        let column_iterator_mut_tokens = self.build_column_iterator_type(&self.type_iter_mut, "::std::slice::IterMut");
+        let column_iterator_tokens = self.build_column_iterator_type(&self.type_iter, syn::Mutability::Immutable);
+        let column_iterator_mut_tokens = self.build_column_iterator_type(&self.type_iter_mut, syn::Mutability::Mutable);
+        let column_into_iterator_tokens = self.build_column_into_iterator_type();
 
         let container_impl = self.build_container_impl();
         let extend_impl = self.build_extend_impl();
         let into_iter_impl = self.build_into_iter_impl(false);
         let into_iter_mut_impl = self.build_into_iter_impl(true);
+        let into_iter_owned_impl = self.build_into_iter_owned_impl();
+        let from_iter_impl = self.build_from_iter_impl();
+        let materialize_impl = self.build_materialize_impl();
+        let len_impl = self.build_len_impl();
+        let tuple_conversion_impl = self.build_tuple_conversion_impl();
         let ref_impl = self.build_ref_impl(&self.type_ref);
         let ref_mut_impl = self.build_ref_impl(&self.type_ref_mut);
         let column_iter_impl = self.build_column_iter_impl_iter(false);
         let column_iter_mut_impl = self.build_column_iter_impl_iter(true);
+        let slices = self.build_slices();
+        let projections = self.build_projections();
         quote! {
 
             #ref_tokens
@@ -187,6 +805,8 @@ impl<'a> ColumnData<'a> {
 
             #column_iterator_mut_tokens
 
+            #column_into_iterator_tokens
+
             #container_impl
 
             #extend_impl
@@ -195,6 +815,16 @@ impl<'a> ColumnData<'a> {
 
             #into_iter_mut_impl
 
+            #into_iter_owned_impl
+
+            #from_iter_impl
+
+            #materialize_impl
+
+            #len_impl
+
+            #tuple_conversion_impl
+
             #ref_impl
 
             #ref_mut_impl
@@ -202,105 +832,72 @@ impl<'a> ColumnData<'a> {
             #column_iter_impl
 
             #column_iter_mut_impl
+
+            #slices
+
+            #projections
         }
     }
 
     fn build_ref_type(&self) -> quote::Tokens {
-        let lifetime_a = || syn::Lifetime { ident: Ident::from(COLUMN_LIFETIME) };
         let ref name = self.type_ref;
 
         // Add same lifetime to the field refs
-        let ref_type_fields: Vec<_> = self.fields.iter().map(|f| {
-            let mut f = f.clone();
-            f.ty = syn::Ty::Rptr(Some(lifetime_a()), Box::new(syn::MutTy { ty: f.ty, mutability: syn::Mutability::Immutable}));
+        let ref_type_fields: Vec<_> = self.columns.iter().map(|c| {
+            let mut f = c.field.clone();
+            f.ty = c.ref_ty(syn::Mutability::Immutable);
             f
         }).collect();
-        let (_impl_generics, ty_generics, where_clause) = self.lt_generics.split_for_impl();
-        let ref vis = self.ast.vis;
-        quote! {
-            #[derive(Debug)]
-            #[allow(dead_code)]
-            #vis struct #name #ty_generics #where_clause {
-                #(#ref_type_fields),*
-            }
-        }
+        self.build_struct(name, &self.lt_generics, ref_type_fields)
     }
 
     fn build_ref_mut_type(&self) -> quote::Tokens {
-        let lifetime_a = || syn::Lifetime { ident: Ident::from(COLUMN_LIFETIME) };
         let ref name = self.type_ref_mut;
 
         // Add same lifetime and mutability to the field refs
-        let ref_type_fields: Vec<_> = self.fields.iter().map(|f| {
-            let mut f = f.clone();
-            f.ty = syn::Ty::Rptr(Some(lifetime_a()), Box::new(syn::MutTy { ty: f.ty, mutability: syn::Mutability::Mutable}));
+        let ref_type_fields: Vec<_> = self.columns.iter().map(|c| {
+            let mut f = c.field.clone();
+            f.ty = c.ref_ty(syn::Mutability::Mutable);
             f
         }).collect();
-        let (_impl_generics, ty_generics, where_clause) = self.lt_generics.split_for_impl();
-        let ref vis = self.ast.vis;
-        quote! {
-            #[derive(Debug)]
-            #[allow(dead_code)]
-            #vis struct #name #ty_generics #where_clause {
-                #(#ref_type_fields),*
-            }
-        }
+        self.build_struct(name, &self.lt_generics, ref_type_fields)
     }
 
     fn build_column_type(&self) -> quote::Tokens {
         let ref name = self.type_container;
 
-        // Encapsulate fields in Vec
-        let ref_type_fields: Vec<_> = self.fields.iter().map(|f| {
-            let mut f = f.clone();
-            let mut segment = syn::PathSegment::from(syn::Ident::new("Vec"));
-            let parameter_data = syn::AngleBracketedParameterData {
-                lifetimes: vec![],
-                types: vec![f.ty],
-                bindings: vec![],
-            };
-            segment.parameters = syn::PathParameters::AngleBracketed(parameter_data);
-            f.ty = syn::Ty::Path(None, syn::Path::from(segment));
+        // Encapsulate fields in Vec, custom storage, or the column of their type if nested
+        let ref_type_fields: Vec<_> = self.columns.iter().map(|c| {
+            let mut f = c.field.clone();
+            f.ty = c.storage_ty();
             f
         }).collect();
-        let (_impl_generics, ty_generics, where_clause) = self.ast.generics.split_for_impl();
-        let ref vis = self.ast.vis;
-        quote! {
-            #[derive(Debug)]
-            #[allow(dead_code)]
-            #vis struct #name #ty_generics #where_clause {
-                #(#ref_type_fields),*
+        self.build_struct(name, &self.ast.generics, ref_type_fields)
+    }
+    fn build_column_iterator_type(&self, name: &Ident, mutability: syn::Mutability) -> quote::Tokens {
+        // Encapsulate fields in slice iterators
+        let ref_type_fields: Vec<_> = self.columns.iter().map(|c| {
+            let mut f = c.field.clone();
+            f.ty = c.iter_ty(mutability);
+            if f.ident.is_some() {
+                f.ident = Some(c.iter_name());
             }
-        }
+            f
+        }).collect();
+        self.build_struct(name, &self.lt_generics, ref_type_fields)
     }
-    fn build_column_iterator_type(&self, name: &Ident, iter_type_name: &str) -> quote::Tokens {
-        let lifetime_a = || syn::Lifetime { ident: Ident::from(COLUMN_LIFETIME) };
 
-        // Encapsulate fields in Vec
-        let ref_type_fields: Vec<_> = self.fields.iter().map(|f| {
-            let mut f = f.clone();
-            let mut segment = syn::PathSegment::from(syn::Ident::new(iter_type_name));
-            let parameter_data = syn::AngleBracketedParameterData {
-                lifetimes: vec![lifetime_a()],
-                types: vec![f.ty],
-                bindings: vec![],
-            };
-            segment.parameters = syn::PathParameters::AngleBracketed(parameter_data);
-            f.ty = syn::Ty::Path(None, syn::Path::from(segment));
-            if let Some(ident) = f.ident {
-                f.ident = Some(Ident::from(format!("iter_{}", ident)));
+    fn build_column_into_iterator_type(&self) -> quote::Tokens {
+        // Encapsulate fields in the owning iterators of their columns
+        let fields: Vec<_> = self.columns.iter().map(|c| {
+            let mut f = c.field.clone();
+            f.ty = c.owned_iter_ty();
+            if f.ident.is_some() {
+                f.ident = Some(c.iter_name());
             }
             f
         }).collect();
-        let (_impl_generics, ty_generics, where_clause) = self.lt_generics.split_for_impl();
-        let ref vis = self.ast.vis;
-        quote! {
-            #[derive(Debug)]
-            #[allow(dead_code)]
-            #vis struct #name #ty_generics #where_clause {
-                #(#ref_type_fields),*
-            }
-        }
+        self.build_struct(&self.type_into_iter, &self.ast.generics, fields)
     }
 
     fn build_container_impl(&self) -> quote::Tokens {
@@ -309,7 +906,6 @@ impl<'a> ColumnData<'a> {
 
         let (_impl_generics, ty_generics, _where_clause) = self.ast.generics.split_for_impl();
         let (lt_impl_generics, _lt_ty_generics, lt_where_clause) = self.lt_generics.split_for_impl();
-        let lifetime = syn::Lifetime { ident: Ident::from(COLUMN_LIFETIME) };
 
         let new = self.build_column_new_impl();
         let with_capacity = self.build_column_with_capacity_impl();
@@ -321,8 +917,22 @@ impl<'a> ColumnData<'a> {
         let capacity = self.build_column_capacity_impl();
         let index = self.build_column_index_impl();
         let index_mut = self.build_column_index_mut_impl();
-
-        let ref type_container = self.type_container;
+        let get = self.build_column_get_impl();
+        let remove = self.build_column_remove_impl();
+        let mutation = self.build_column_mutation_impl();
+        let accessors = self.build_column_accessors_impl();
+        let derived_mutation = build_derived_mutation_impl(type_column, &ty_generics);
+        let gather = build_gather_impl(type_column, &ty_generics, &self.clone_bounds());
+
+        let types = ColumnTypes {
+            container: &self.type_container,
+            reference: &self.type_ref,
+            reference_mut: &self.type_ref_mut,
+            iter: &self.type_iter,
+            iter_mut: &self.type_iter_mut,
+            ref_generics: &self.lt_generics,
+        };
+        let column_trait_impls = build_column_trait_impls(type_column, &types, &self.ast.generics, &self.clone_predicates(), &new, &with_capacity);
 
         quote! {
             #[allow(dead_code)]
@@ -336,29 +946,37 @@ impl<'a> ColumnData<'a> {
                 #capacity
                 #index
                 #index_mut
+                #get
+                #remove
+                #mutation
+                #derived_mutation
+                #gather
+                #accessors
             }
 
-            #[allow(dead_code)]
-            impl#lt_impl_generics ::column::Column<#lifetime> for #type_column #ty_generics #lt_where_clause {
-                type Output = #type_container #ty_generics;
-
-                #new
-
-                #with_capacity
-
-            }
+            #column_trait_impls
         }
     }
 
     fn build_column_new_impl(&self) -> quote::Tokens {
         // Encapsulate fields in Vec
-        let names: Vec<_> = self.fields.iter().map(|f| f.ident.clone().unwrap()).collect();
+        let names = self.field_names();
+        let values = self.field_exprs(|c| match c.options.storage {
+            Some(_) => {
+                let ty = c.storage_ty();
+                quote! { <#ty>::new() }
+            },
+            None => quote! { Vec::new() },
+        }, |c| {
+            let ref ty = c.field.ty;
+            quote! { <#ty as ::column::Column>::new() }
+        });
         let ref name = self.type_container;
 
         quote! {
             fn new() -> Self::Output {
                 #name {
-                    #(#names: Vec::new()),*
+                    #(#names: #values),*
                 }
             }
         }
@@ -366,13 +984,23 @@ impl<'a> ColumnData<'a> {
 
     fn build_column_with_capacity_impl(&self) -> quote::Tokens {
         // Encapsulate fields in Vec
-        let names: Vec<_> = self.fields.iter().map(|f| f.ident.clone().unwrap()).collect();
+        let names = self.field_names();
+        let values = self.field_exprs(|c| match c.options.storage {
+            Some(_) => {
+                let ty = c.storage_ty();
+                quote! { <#ty>::with_capacity(capacity) }
+            },
+            None => quote! { Vec::with_capacity(capacity) },
+        }, |c| {
+            let ref ty = c.field.ty;
+            quote! { <#ty as ::column::Column>::with_capacity(capacity) }
+        });
         let ref name = self.type_container;
 
         quote! {
             fn with_capacity(capacity: usize) -> Self::Output {
                 #name {
-                    #(#names: Vec::with_capacity(capacity)),*
+                    #(#names: #values),*
                 }
             }
         }
@@ -380,26 +1008,25 @@ impl<'a> ColumnData<'a> {
 
 
     fn build_column_util_impl(&self) -> quote::Tokens {
-        // Encapsulate fields in Vec
-        let names: Vec<_> = self.fields.iter().map(|f| f.ident.clone().unwrap()).collect();
-        let names2 = names.clone();
+        let clear: Vec<_> = self.columns.iter().map(|c| c.call("clear", quote! {})).collect();
+        let reserve: Vec<_> = self.columns.iter().map(|c| c.call("reserve", quote! { additional })).collect();
         quote! {
             fn clear(&mut self) {
-                #(self.#names.clear());*
+                #(#clear);*
             }
 
             fn reserve(&mut self, additional: usize) {
-                #(self.#names2.reserve(additional));*
+                #(#reserve);*
             }
         }
     }
 
     fn build_column_capacity_impl(&self) -> quote::Tokens {
-        let name = self.get_first_field_name();
+        let capacity = self.columns[0].call("capacity", quote! {});
 
         quote! {
             fn capacity(&self) -> usize {
-                self.#name.capacity()
+                #capacity
             }
         }
     }
@@ -407,71 +1034,320 @@ impl<'a> ColumnData<'a> {
     fn build_column_index_impl(&self) -> quote::Tokens {
         let ref type_column = self.ast.ident;
         let (_impl_generics, ty_generics, _where_clause) = self.ast.generics.split_for_impl();
-        let names: Vec<_> = self.fields.iter().map(|f| f.ident.clone().unwrap()).collect();
-        let names2 = names.clone();
+        let value = self.build_owned(|c| {
+            let ref column = c.column;
+            if c.options.nested {
+                quote! { ::column::CloneContainer::index(&self.#column, index) }
+            } else {
+                quote! { Clone::clone(&self.#column[index]) }
+            }
+        });
+        let clone_bounds = self.clone_bounds();
+        quote! {
+            fn index(&self, index: usize) -> #type_column #ty_generics #clone_bounds {
+                #value
+            }
+
+        }
+    }
+
+    fn build_column_remove_impl(&self) -> quote::Tokens {
+        let ref type_column = self.ast.ident;
+        let (_impl_generics, ty_generics, _where_clause) = self.ast.generics.split_for_impl();
+        let remove = self.build_owned(|c| c.call("remove", quote! { index }));
+        let swap_remove = self.build_owned(|c| c.call("swap_remove", quote! { index }));
+        quote! {
+            fn remove(&mut self, index: usize) -> #type_column #ty_generics {
+                #remove
+            }
+
+            fn swap_remove(&mut self, index: usize) -> #type_column #ty_generics {
+                #swap_remove
+            }
+        }
+    }
+
+    fn build_column_mutation_impl(&self) -> quote::Tokens {
+        let ref type_column = self.ast.ident;
+        let ref type_ref = self.type_ref;
+        let (_impl_generics, ty_generics, _where_clause) = self.ast.generics.split_for_impl();
+        let names: &Vec<_> = &self.field_names();
+        let calls = |method: &str, args: &dyn Fn(&ColumnField) -> quote::Tokens| -> Vec<quote::Tokens> {
+            self.columns.iter().map(|c| c.call(method, args(c))).collect()
+        };
+        let push = calls("push", &|c| {
+            let ref name = c.name;
+            quote! { element.#name }
+        });
+        let insert = calls("insert", &|c| {
+            let ref name = c.name;
+            quote! { index, element.#name }
+        });
+        let truncate = calls("truncate", &|_| quote! { len });
+        let split_off = calls("split_off", &|_| quote! { at });
+        let append = calls("append", &|c| {
+            let ref column = c.column;
+            quote! { &mut other.#column }
+        });
+        let swap = calls("swap", &|_| quote! { a, b });
+        let shrink_to_fit = calls("shrink_to_fit", &|_| quote! {});
+        let swap_retained = calls("swap", &|_| quote! { write, read });
+        let truncate_retained = calls("truncate", &|_| quote! { len });
+        let pop = self.build_owned(|c| {
+            let pop = c.call("pop", quote! {});
+            quote! { #pop? }
+        });
+        quote! {
+            fn push(&mut self, element: #type_column #ty_generics) {
+                #(#push;)*
+            }
+
+            fn pop(&mut self) -> Option<#type_column #ty_generics> {
+                Some(#pop)
+            }
+
+            fn insert(&mut self, index: usize, element: #type_column #ty_generics) {
+                #(#insert;)*
+            }
+
+            fn truncate(&mut self, len: usize) {
+                #(#truncate;)*
+            }
+
+            fn split_off(&mut self, at: usize) -> Self {
+                Self { #(#names: #split_off),* }
+            }
+
+            fn append(&mut self, other: &mut Self) {
+                #(#append;)*
+            }
+
+            fn retain<F>(&mut self, f: F) where F: FnMut(#type_ref #ty_generics) -> bool {
+                let keep: Vec<bool> = self.iter().map(f).collect();
+                // Move retained elements to the front, preserving their order
+                let mut retained = 0;
+                for (index, keep) in keep.into_iter().enumerate() {
+                    if keep {
+                        self.swap(retained, index);
+                        retained += 1;
+                    }
+                }
+                self.truncate(retained);
+            }
+
+            fn swap(&mut self, a: usize, b: usize) {
+                #(#swap;)*
+            }
+
+            fn shrink_to_fit(&mut self) {
+                #(#shrink_to_fit;)*
+            }
+
+            fn retain_by_bitmap<S: ::column::bitmap::BitSet>(&mut self, bitmap: &S) {
+                assert_eq!(bitmap.len(), self.len(), "Bitmap length must match the column length");
+                let len = bitmap.count_ones();
+                #(
+                    // Move the retained elements of each column to the front
+                    for (write, read) in bitmap.iter_ones().enumerate() {
+                        #swap_retained;
+                    }
+                    #truncate_retained;
+                )*
+            }
+        }
+    }
+
+    fn build_materialize_impl(&self) -> quote::Tokens {
+        let ref type_container = self.type_container;
+        let (impl_generics, ty_generics, where_clause) = self.ast.generics.split_for_impl();
+        let lifetime = syn::Lifetime::new(CLONE_LIFETIME);
+        let clone_predicates: Vec<_> = self.columns.iter().filter(|c| !c.options.nested).map(|c| {
+            let ref ty = c.field.ty;
+            quote! { for<#lifetime> #ty: Clone }
+        }).collect();
+        let names = self.field_names();
+        let values: Vec<_> = self.columns.iter().map(|c| {
+            let ref column = c.column;
+            if c.options.nested {
+                quote! { ::column::bitmap::Materialize::materialize(&self.#column, bitmap) }
+            } else {
+                let storage_ty = c.storage_ty();
+                quote! {
+                    {
+                        let mut column = <#storage_ty>::with_capacity(bitmap.count_ones());
+                        for index in bitmap.iter_ones() {
+                            column.push(Clone::clone(&self.#column[index]));
+                        }
+                        column
+                    }
+                }
+            }
+        }).collect();
+        let ref predicates = where_clause.predicates;
         quote! {
-            fn index(&self, index: usize) -> #type_column #ty_generics {
-                #type_column { #(#names: self.#names2[index]),* }
+            impl #impl_generics ::column::bitmap::Materialize for #type_container #ty_generics
+                where #(#predicates,)* #(#clone_predicates),*
+            {
+                type Output = Self;
+                fn materialize<S: ::column::bitmap::BitSet>(&self, bitmap: &S) -> Self {
+                    use ::column::bitmap::BitSet;
+                    assert_eq!(bitmap.len(), self.len(), "Bitmap length must match the column length");
+                    Self { #(#names: #values),* }
+                }
             }
+        }
+    }
 
+    fn build_len_impl(&self) -> quote::Tokens {
+        let ref type_container = self.type_container;
+        let (impl_generics, ty_generics, where_clause) = self.ast.generics.split_for_impl();
+        let len = self.build_column_len_impl();
+        let is_empty = self.build_column_is_empty_impl();
+        quote! {
+            impl #impl_generics ::column::Len for #type_container #ty_generics #where_clause {
+                #len
+                #is_empty
+            }
+        }
+    }
+
+    /// Build conversions between the column and a tuple column holding the
+    /// storage of each field, which move the storages without copying
+    fn build_tuple_conversion_impl(&self) -> quote::Tokens {
+        if self.columns.is_empty() {
+            return quote! {};
+        }
+        let ref type_container = self.type_container;
+        let (impl_generics, ty_generics, where_clause) = self.ast.generics.split_for_impl();
+        let names: &Vec<_> = &self.field_names();
+        let indices: Vec<_> = (0..self.columns.len()).map(Ident::from).collect();
+        let storage_tys: Vec<_> = self.columns.iter().map(|c| c.storage_ty()).collect();
+        let tuple_ty = quote! { ::column::tuple::Col<(#(#storage_tys),*,)> };
+        quote! {
+            impl #impl_generics From<#type_container #ty_generics> for #tuple_ty #where_clause {
+                fn from(column: #type_container #ty_generics) -> Self {
+                    <#tuple_ty>::from_inner((#(column.#names),*,))
+                }
+            }
+
+            impl #impl_generics From<#tuple_ty> for #type_container #ty_generics #where_clause {
+                fn from(column: #tuple_ty) -> Self {
+                    let inner = column.into_inner();
+                    #type_container { #(#names: inner.#indices),* }
+                }
+            }
+        }
+    }
+
+    fn build_column_accessors_impl(&self) -> quote::Tokens {
+        let accessors: Vec<_> = self.columns.iter().map(|c| {
+            let ref vis = c.field.vis;
+            let ref column = c.column;
+            let accessor = c.accessor();
+            let accessor_mut = Ident::from(format!("{}_mut", accessor));
+            let ty = c.accessor_ty();
+            let (value, value_mut) = if c.options.nested {
+                (quote! { &self.#column }, quote! { &mut self.#column })
+            } else {
+                (quote! { &self.#column[..] }, quote! { &mut self.#column[..] })
+            };
+            quote! {
+                #vis fn #accessor(&self) -> &#ty {
+                    #value
+                }
+
+                #vis fn #accessor_mut(&mut self) -> &mut #ty {
+                    #value_mut
+                }
+            }
+        }).collect();
+        let tys: Vec<_> = self.columns.iter().map(|c| c.accessor_ty()).collect();
+        let values = self.field_exprs(|c| {
+            let ref column = c.column;
+            quote! { &mut self.#column[..] }
+        }, |c| {
+            let ref column = c.column;
+            quote! { &mut self.#column }
+        });
+        quote! {
+            #(#accessors)*
+
+            fn fields_mut(&mut self) -> (#(&mut #tys,)*) {
+                (#(#values,)*)
+            }
         }
     }
 
     fn build_column_index_mut_impl(&self) -> quote::Tokens {
         let ref type_ref_mut = self.type_ref_mut;
         let (_impl_generics, ty_generics, _where_clause) = self.ast.generics.split_for_impl();
-        let names: Vec<_> = self.fields.iter().map(|f| f.ident.clone().unwrap()).collect();
-        let names2 = names.clone();
+        let names = self.field_names();
+        let values = self.field_exprs(|c| {
+            let ref column = c.column;
+            quote! { &mut self.#column[index] }
+        }, |c| c.call("index_mut", quote! { index }));
         quote! {
             fn index_mut(&mut self, index: usize) -> #type_ref_mut #ty_generics {
-                #type_ref_mut { #(#names: &mut self.#names2[index]),* }
+                #type_ref_mut { #(#names: #values),* }
+            }
+        }
+    }
+
+    fn build_column_get_impl(&self) -> quote::Tokens {
+        let ref type_ref = self.type_ref;
+        let (_impl_generics, ty_generics, _where_clause) = self.ast.generics.split_for_impl();
+        let names = self.field_names();
+        let values = self.field_exprs(|c| {
+            let ref column = c.column;
+            quote! { &self.#column[index] }
+        }, |c| {
+            let ref column = c.column;
+            quote! { ::column::ColumnContainer::get(&self.#column, index)? }
+        });
+        quote! {
+            fn get(&self, index: usize) -> Option<#type_ref #ty_generics> {
+                if index >= self.len() {
+                    return None;
+                }
+                Some(#type_ref { #(#names: #values),* })
             }
         }
     }
 
     fn build_column_iter_impl(&self, type_name: &Ident, iter: &str, modifier: &str, ty_generics: &syn::TyGenerics) -> quote::Tokens {
-        // Encapsulate fields in Vec
-        let names: Vec<_> = self.fields.iter().map(|f| f.ident.clone().unwrap()).collect();
-        let iters: Vec<_> = self.fields.iter().map(|f| Ident::new(format!("iter_{}", f.ident.clone().unwrap()))).collect();
-        let iter = Ident::new(iter);
-        let fn_name = iter.clone();
-        let iter = ::std::iter::repeat(iter);
+        let iters = self.iter_names();
+        let values: Vec<_> = self.columns.iter().map(|c| c.call(iter, quote! {})).collect();
+        let fn_name = Ident::new(iter);
         let modifier = Ident::new(modifier);
         quote! {
             fn #fn_name(& #modifier self) -> #type_name #ty_generics {
                 #type_name {
-                    #(#iters: self.#names.#iter()),*
+                    #(#iters: #values),*
                 }
             }
         }
     }
 
     fn build_column_len_impl(&self) -> quote::Tokens {
-        let name = self.get_first_field_name();
+        let len = self.columns[0].call("len", quote! {});
         quote! {
             fn len(&self) -> usize {
-                self.#name.len()
+                #len
             }
         }
     }
 
     fn build_column_is_empty_impl(&self) -> quote::Tokens {
-        let name = if let Some(name) = self.fields.first().expect("At least one field required").clone().ident {
-            name
-        } else {
-            syn::Ident::new("0")
-        };
+        let is_empty = self.columns[0].call("is_empty", quote! {});
         quote! {
             fn is_empty(&self) -> bool {
-                self.#name.is_empty()
+                #is_empty
             }
         }
     }
 
     fn build_extend_impl(&self) -> quote::Tokens {
         // Encapsulate fields in Vec
-        let names: Vec<_> = self.fields.iter().map(|f| f.ident.clone().unwrap()).collect();
-        let names2: Vec<_> = names.clone();
         let ref name = self.ast.ident;
         let ref type_container = self.type_container;
         let (_impl_generics, ty_generics, _where_clause) = self.ast.generics.split_for_impl();
@@ -482,7 +1358,7 @@ impl<'a> ColumnData<'a> {
             impl #lt_impl_generics Extend<#name#ty_generics> for #type_container #ty_generics #lt_where_clause {
                 fn extend<T: IntoIterator<Item=#name#ty_generics>>(&mut self, iter: T) {
                     for element in iter {
-                        #(self.#names.push(element.#names2));*
+                        self.push(element);
                     }
                 }
             }
@@ -514,9 +1390,86 @@ impl<'a> ColumnData<'a> {
         }
     }
 
+    fn build_into_iter_owned_impl(&self) -> quote::Tokens {
+        let ref name = self.ast.ident;
+        let ref type_container = self.type_container;
+        let ref type_into_iter = self.type_into_iter;
+        let (impl_generics, ty_generics, where_clause) = self.ast.generics.split_for_impl();
+
+        let names = self.field_names();
+        let iters: &Vec<_> = &self.iter_names();
+        let ref first = iters[0];
+        let next = self.build_owned(|c| {
+            let iter = c.iter_name();
+            quote! { self.#iter.next()? }
+        });
+        let nth = self.build_owned(|c| {
+            let iter = c.iter_name();
+            quote! { self.#iter.nth(n)? }
+        });
+        let next_back = self.build_owned(|c| {
+            let iter = c.iter_name();
+            quote! { self.#iter.next_back()? }
+        });
+
+        quote! {
+            impl #impl_generics IntoIterator for #type_container #ty_generics #where_clause {
+                type Item = #name #ty_generics;
+                type IntoIter = #type_into_iter #ty_generics;
+                fn into_iter(self) -> Self::IntoIter {
+                    #type_into_iter {
+                        #(#iters: self.#names.into_iter()),*
+                    }
+                }
+            }
+
+            impl #impl_generics Iterator for #type_into_iter #ty_generics #where_clause {
+                type Item = #name #ty_generics;
+
+                fn next(&mut self) -> Option<Self::Item> {
+                    Some(#next)
+                }
+
+                fn size_hint(&self) -> (usize, Option<usize>) {
+                    self.#first.size_hint()
+                }
+
+                fn nth(&mut self, n: usize) -> Option<Self::Item> {
+                    Some(#nth)
+                }
+            }
+
+            impl #impl_generics DoubleEndedIterator for #type_into_iter #ty_generics #where_clause {
+                fn next_back(&mut self) -> Option<Self::Item> {
+                    Some(#next_back)
+                }
+            }
+
+            impl #impl_generics ExactSizeIterator for #type_into_iter #ty_generics #where_clause {}
+
+            impl #impl_generics ::std::iter::FusedIterator for #type_into_iter #ty_generics #where_clause {}
+        }
+    }
+
+    fn build_from_iter_impl(&self) -> quote::Tokens {
+        let ref name = self.ast.ident;
+        let ref type_container = self.type_container;
+        let (impl_generics, ty_generics, where_clause) = self.ast.generics.split_for_impl();
+        build_from_iter_impl(name, type_container, &impl_generics, &ty_generics, where_clause)
+    }
+
     fn build_ref_impl(&self, type_ref: &Ident) -> quote::Tokens {
-        let names: Vec<_> = self.fields.iter().map(|f| f.ident.clone().unwrap()).collect();
-        let names2: Vec<_> = names.clone();
+        let to_owned = Ident::new(if *type_ref == self.type_ref { "to_owned" } else { "to_owned_mut" });
+        let value = self.build_owned(|c| {
+            let ref column = c.column;
+            if c.options.nested {
+                let storage_ty = c.storage_ty();
+                quote! { <#storage_ty as ::column::CloneContainer>::#to_owned(&self.#column) }
+            } else {
+                quote! { Clone::clone(&*self.#column) }
+            }
+        });
+        let clone_bounds = self.clone_bounds();
         let ref name = self.ast.ident;
 
         let (lt_impl_generics, lt_ty_generics, lt_where_clause) = self.lt_generics.split_for_impl();
@@ -525,53 +1478,23 @@ impl<'a> ColumnData<'a> {
         quote! {
             #[allow(dead_code)]
             impl #lt_impl_generics #type_ref #lt_ty_generics #lt_where_clause {
-                fn to_owned(&self) -> #name#ty_generics {
-                    #name {
-                        #(#names: *self.#names2),*
-                    }
+                fn to_owned(&self) -> #name#ty_generics #clone_bounds {
+                    #value
                 }
             }
         }
     }
 
     fn build_column_iter_impl_iter(&self, mutable: bool) -> quote::Tokens {
-        let names: Vec<_> = self.fields.iter().map(|f| f.ident.clone().unwrap()).collect();
-        // This is...ugly. quote! seems to consume the thing and Ident doesn't implement Copy.
-        let names2 = names.clone();
-        let names3 = names.clone();
-        let names4 = names.clone();
-        let names5 = names.clone();
-        let iters: Vec<_> = self.fields.iter().map(|f| Ident::new(format!("iter_{}", f.ident.clone().unwrap()))).collect();
+        let names = self.field_names();
+        let iters = self.iter_names();
         let (type_iter, type_ref) = if mutable {
             (&self.type_iter_mut, &self.type_ref_mut)
         } else {
             (&self.type_iter, &self.type_ref)
         };
 
-        let (impl_generics, ty_generics, where_clause) = self.lt_generics.split_for_impl();
-
-        quote! {
-            impl #impl_generics Iterator for #type_iter #ty_generics #where_clause {
-                type Item = #type_ref #ty_generics;
-
-                fn next<'b>(&'b mut self) -> Option<Self::Item> {
-                    #(
-                        let #names = self.#iters.next()
-                    );*;
-                    #(
-                        if #names2.is_none() {
-                            return None;
-                        }
-                    );*
-                    #(
-                        let #names4 = #names5.unwrap()
-                    );*;
-                    Some(Self::Item {
-                        #(#names3),*
-                    })
-                }
-            }
-        }
+        build_iterator_impl(type_iter, type_ref, &self.lt_generics, &names, &iters)
     }
 
 }
@@ -0,0 +1,137 @@
+// Copyright 2017 columnar-rs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+//! Derive projections of a struct's columns.
+//!
+//! A projection `#[column(project = "id, val")]` generates the iterators
+//! `iter_id_val` and `iter_id_val_mut`, which only advance the columns of the
+//! selected fields and yield `DataIdValRef` and `DataIdValRefMut`.
+
+use quote;
+use syn;
+use syn::Ident;
+
+use ::{ColumnData, ColumnField, ContainerOptions, build_iterator_impl};
+
+impl<'a> ColumnData<'a> {
+
+    /// Build the types and iterators of all projections of the derived type
+    pub fn build_projections(&self) -> quote::Tokens {
+        let options = ContainerOptions::new(self.ast);
+        let projections: Vec<_> = options.projections.iter().map(|fields| {
+            self.build_projection(&options.name, fields)
+        }).collect();
+        quote! {
+            #(#projections)*
+        }
+    }
+
+    fn build_projection(&self, base_name: &str, fields: &[Ident]) -> quote::Tokens {
+        for (index, field) in fields.iter().enumerate() {
+            assert!(!fields[..index].contains(field), "Duplicate field in projection: {}", field);
+        }
+        let columns: Vec<&ColumnField> = fields.iter().map(|field| {
+            self.columns.iter().find(|c| c.name == *field)
+                .unwrap_or_else(|| panic!("Unknown or skipped field in projection: {}", field))
+        }).collect();
+
+        let accessors: Vec<_> = columns.iter().map(|c| c.accessor().to_string()).collect();
+        let camel_name: String = accessors.iter().map(|a| camel_case(a)).collect();
+        let type_ref = Ident::from(format!("{}{}Ref", base_name, camel_name));
+        let type_ref_mut = Ident::from(format!("{}{}RefMut", base_name, camel_name));
+        let type_iter = Ident::from(format!("{}{}Iterator", base_name, camel_name));
+        let type_iter_mut = Ident::from(format!("{}{}IteratorMut", base_name, camel_name));
+        let method = Ident::from(format!("iter_{}", accessors.join("_")));
+        let method_mut = Ident::from(format!("iter_{}_mut", accessors.join("_")));
+
+        // Fields of tuple structs are renumbered in the projected types
+        let names: &Vec<_> = &columns.iter().enumerate().map(|(index, c)| {
+            c.field.ident.clone().unwrap_or_else(|| Ident::from(index))
+        }).collect();
+        let iters: &Vec<_> = &columns.iter().enumerate().map(|(index, c)| {
+            c.field.ident.as_ref().map(|_| c.iter_name()).unwrap_or_else(|| Ident::from(index))
+        }).collect();
+        let values: Vec<_> = columns.iter().map(|c| c.call("iter", quote! {})).collect();
+        let values_mut: Vec<_> = columns.iter().map(|c| c.call("iter_mut", quote! {})).collect();
+
+        let ref_tokens = self.build_struct(&type_ref, &self.lt_generics,
+            projected_fields(&columns, |c| c.ref_ty(syn::Mutability::Immutable), false));
+        let ref_mut_tokens = self.build_struct(&type_ref_mut, &self.lt_generics,
+            projected_fields(&columns, |c| c.ref_ty(syn::Mutability::Mutable), false));
+        let iter_tokens = self.build_struct(&type_iter, &self.lt_generics,
+            projected_fields(&columns, |c| c.iter_ty(syn::Mutability::Immutable), true));
+        let iter_mut_tokens = self.build_struct(&type_iter_mut, &self.lt_generics,
+            projected_fields(&columns, |c| c.iter_ty(syn::Mutability::Mutable), true));
+
+        let iter_impl = build_iterator_impl(&type_iter, &type_ref, &self.lt_generics, names, iters);
+        let iter_mut_impl = build_iterator_impl(&type_iter_mut, &type_ref_mut, &self.lt_generics, names, iters);
+
+        let ref type_container = self.type_container;
+        let (lt_impl_generics, _lt_ty_generics, lt_where_clause) = self.lt_generics.split_for_impl();
+        let (_impl_generics, ty_generics, _where_clause) = self.ast.generics.split_for_impl();
+
+        quote! {
+            #ref_tokens
+
+            #ref_mut_tokens
+
+            #iter_tokens
+
+            #iter_mut_tokens
+
+            #[allow(dead_code)]
+            impl #lt_impl_generics #type_container #ty_generics #lt_where_clause {
+                fn #method(&self) -> #type_iter #ty_generics {
+                    #type_iter {
+                        #(#iters: #values),*
+                    }
+                }
+
+                fn #method_mut(&mut self) -> #type_iter_mut #ty_generics {
+                    #type_iter_mut {
+                        #(#iters: #values_mut),*
+                    }
+                }
+            }
+
+            #iter_impl
+
+            #iter_mut_impl
+        }
+    }
+}
+
+/// The fields of a projected type, with types returned by `ty`. Fields are
+/// renamed to the names of the column iterators if `rename` is set.
+fn projected_fields<F>(columns: &[&ColumnField], ty: F, rename: bool) -> Vec<syn::Field>
+    where F: Fn(&ColumnField) -> syn::Ty,
+{
+    columns.iter().map(|c| {
+        let mut f = c.field.clone();
+        f.ty = ty(c);
+        if rename && f.ident.is_some() {
+            f.ident = Some(c.iter_name());
+        }
+        f
+    }).collect()
+}
+
+/// Convert a `snake_case` identifier to `CamelCase`
+fn camel_case(ident: &str) -> String {
+    let mut result = String::with_capacity(ident.len());
+    let mut upper = true;
+    for c in ident.chars() {
+        if c == '_' {
+            upper = true;
+        } else if upper {
+            result.extend(c.to_uppercase());
+            upper = false;
+        } else {
+            result.push(c);
+        }
+    }
+    result
+}
@@ -0,0 +1,555 @@
+// Copyright 2017 columnar-rs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+//! Derive borrowed slice views of a struct's columns.
+//!
+//! A `DataSlice` holds a `&[T]` and a `DataSliceMut` a `&mut [T]` for every
+//! stored field of `Data`, all covering the same range of elements.
+
+use quote;
+use syn;
+use syn::Ident;
+
+use ::{COLUMN_LIFETIME, ColumnData, ContainerOptions};
+
+/// The names of the generated slice types
+struct SliceTypes {
+    slice: Ident,
+    slice_mut: Ident,
+    chunks: Ident,
+    chunks_mut: Ident,
+    windows: Ident,
+}
+
+impl<'a> ColumnData<'a> {
+
+    /// Build the slice types and the methods producing them, unless the type
+    /// opts out with `#[column(no_slices)]`
+    ///
+    /// # Panics
+    /// Panics if a field is nested, as nested columns cannot be sliced.
+    pub fn build_slices(&self) -> quote::Tokens {
+        let options = ContainerOptions::new(self.ast);
+        if options.no_slices {
+            return quote! {};
+        }
+        if let Some(c) = self.columns.iter().find(|c| c.options.nested) {
+            panic!("Slices cannot hold the nested field `{}` of `{}`, add #[column(no_slices)] to derive the column without slices",
+                   c.name, self.ast.ident);
+        }
+        let base_name = options.name;
+        let types = SliceTypes {
+            slice: Ident::from(format!("{}Slice", base_name)),
+            slice_mut: Ident::from(format!("{}SliceMut", base_name)),
+            chunks: Ident::from(format!("{}Chunks", base_name)),
+            chunks_mut: Ident::from(format!("{}ChunksMut", base_name)),
+            windows: Ident::from(format!("{}Windows", base_name)),
+        };
+
+        let slice_tokens = self.build_slice_type(&types.slice, syn::Mutability::Immutable);
+        let slice_mut_tokens = self.build_slice_type(&types.slice_mut, syn::Mutability::Mutable);
+        let iterator_tokens = self.build_slice_iterator_types(&types);
+        let slice_impl = self.build_slice_impl(&types);
+        let slice_mut_impl = self.build_slice_mut_impl(&types);
+        let iterator_impl = self.build_slice_iterator_impl(&types);
+        let into_iter_impl = self.build_slice_into_iter_impl(&types);
+        let container_impl = self.build_container_slice_impl(&types);
+        quote! {
+            #slice_tokens
+
+            #slice_mut_tokens
+
+            #iterator_tokens
+
+            #slice_impl
+
+            #slice_mut_impl
+
+            #iterator_impl
+
+            #into_iter_impl
+
+            #container_impl
+        }
+    }
+
+    fn build_slice_type(&self, name: &Ident, mutability: syn::Mutability) -> quote::Tokens {
+        let lifetime = syn::Lifetime::new(COLUMN_LIFETIME);
+
+        // Replace every field by a slice of its type
+        let fields: Vec<_> = self.columns.iter().map(|c| {
+            let mut f = c.field.clone();
+            let ty = syn::Ty::Slice(Box::new(f.ty));
+            f.ty = syn::Ty::Rptr(Some(lifetime.clone()), Box::new(syn::MutTy { ty, mutability }));
+            f
+        }).collect();
+        self.build_struct(name, &self.lt_generics, fields)
+    }
+
+    fn build_slice_iterator_types(&self, types: &SliceTypes) -> quote::Tokens {
+        let SliceTypes { ref slice, ref slice_mut, ref chunks, ref chunks_mut, ref windows } = *types;
+        let (_impl_generics, lt_ty_generics, lt_where_clause) = self.lt_generics.split_for_impl();
+        let ref vis = self.ast.vis;
+        quote! {
+            #[derive(Debug)]
+            #[allow(dead_code)]
+            #vis struct #chunks #lt_ty_generics #lt_where_clause {
+                slice: #slice #lt_ty_generics,
+                size: usize,
+                exact: bool,
+            }
+
+            #[derive(Debug)]
+            #[allow(dead_code)]
+            #vis struct #chunks_mut #lt_ty_generics #lt_where_clause {
+                slice: #slice_mut #lt_ty_generics,
+                size: usize,
+                exact: bool,
+            }
+
+            #[derive(Debug)]
+            #[allow(dead_code)]
+            #vis struct #windows #lt_ty_generics #lt_where_clause {
+                slice: #slice #lt_ty_generics,
+                size: usize,
+            }
+        }
+    }
+
+    fn build_slice_impl(&self, types: &SliceTypes) -> quote::Tokens {
+        let SliceTypes { ref slice, ref chunks, ref windows, .. } = *types;
+        let ref type_column = self.ast.ident;
+        let ref type_iter = self.type_iter;
+        let (lt_impl_generics, lt_ty_generics, lt_where_clause) = self.lt_generics.split_for_impl();
+        let (_impl_generics, ty_generics, _where_clause) = self.ast.generics.split_for_impl();
+
+        let names: &Vec<_> = &self.field_names();
+        let names2 = names;
+        let iters = self.iter_names();
+        let first = self.get_first_field_name();
+        let clone_bounds = self.clone_bounds();
+        let value = self.build_owned(|c| {
+            let ref column = c.column;
+            quote! { Clone::clone(&self.#column[index]) }
+        });
+
+        quote! {
+            impl #lt_impl_generics Clone for #slice #lt_ty_generics #lt_where_clause {
+                fn clone(&self) -> Self {
+                    *self
+                }
+            }
+
+            impl #lt_impl_generics Copy for #slice #lt_ty_generics #lt_where_clause {}
+
+            #[allow(dead_code)]
+            impl #lt_impl_generics #slice #lt_ty_generics #lt_where_clause {
+                fn len(&self) -> usize {
+                    self.#first.len()
+                }
+
+                fn is_empty(&self) -> bool {
+                    self.#first.is_empty()
+                }
+
+                fn iter(&self) -> #type_iter #lt_ty_generics {
+                    #type_iter {
+                        #(#iters: self.#names.iter()),*
+                    }
+                }
+
+                fn index(&self, index: usize) -> #type_column #ty_generics #clone_bounds {
+                    #value
+                }
+
+                fn slice<R>(&self, range: R) -> #slice #lt_ty_generics where R: ::std::ops::RangeBounds<usize> {
+                    let range = (range.start_bound().cloned(), range.end_bound().cloned());
+                    #slice { #(#names: &self.#names2[range]),* }
+                }
+
+                fn split_at(&self, mid: usize) -> (#slice #lt_ty_generics, #slice #lt_ty_generics) {
+                    (self.slice(..mid), self.slice(mid..))
+                }
+
+                fn chunks(&self, size: usize) -> #chunks #lt_ty_generics {
+                    assert!(size != 0, "chunk size must be non-zero");
+                    #chunks { slice: *self, size, exact: false }
+                }
+
+                fn chunks_exact(&self, size: usize) -> #chunks #lt_ty_generics {
+                    assert!(size != 0, "chunk size must be non-zero");
+                    #chunks { slice: *self, size, exact: true }
+                }
+
+                fn windows(&self, size: usize) -> #windows #lt_ty_generics {
+                    assert!(size != 0, "window size must be non-zero");
+                    #windows { slice: *self, size }
+                }
+            }
+        }
+    }
+
+    fn build_slice_mut_impl(&self, types: &SliceTypes) -> quote::Tokens {
+        let SliceTypes { ref slice, ref slice_mut, ref chunks, ref chunks_mut, ref windows } = *types;
+        let ref type_column = self.ast.ident;
+        let ref type_ref_mut = self.type_ref_mut;
+        let ref type_iter = self.type_iter;
+        let ref type_iter_mut = self.type_iter_mut;
+        let (lt_impl_generics, lt_ty_generics, lt_where_clause) = self.lt_generics.split_for_impl();
+        let (_impl_generics, ty_generics, _where_clause) = self.ast.generics.split_for_impl();
+
+        let names: &Vec<_> = &self.field_names();
+        let names2 = names;
+        let iters: &Vec<_> = &self.iter_names();
+        let lefts: &Vec<_> = &(0..names.len()).map(|i| Ident::from(format!("left_{}", i))).collect();
+        let rights: &Vec<_> = &(0..names.len()).map(|i| Ident::from(format!("right_{}", i))).collect();
+        let first = self.get_first_field_name();
+        let clone_bounds = self.clone_bounds();
+        let value = self.build_owned(|c| {
+            let ref column = c.column;
+            quote! { Clone::clone(&self.#column[index]) }
+        });
+
+        quote! {
+            #[allow(dead_code)]
+            impl #lt_impl_generics #slice_mut #lt_ty_generics #lt_where_clause {
+                fn len(&self) -> usize {
+                    self.#first.len()
+                }
+
+                fn is_empty(&self) -> bool {
+                    self.#first.is_empty()
+                }
+
+                fn iter(&self) -> #type_iter #ty_generics {
+                    #type_iter {
+                        #(#iters: self.#names.iter()),*
+                    }
+                }
+
+                fn iter_mut(&mut self) -> #type_iter_mut #ty_generics {
+                    #type_iter_mut {
+                        #(#iters: self.#names.iter_mut()),*
+                    }
+                }
+
+                fn index(&self, index: usize) -> #type_column #ty_generics #clone_bounds {
+                    #value
+                }
+
+                fn index_mut(&mut self, index: usize) -> #type_ref_mut #ty_generics {
+                    #type_ref_mut { #(#names: &mut self.#names2[index]),* }
+                }
+
+                fn as_slice(&self) -> #slice #ty_generics {
+                    #slice { #(#names: &*self.#names2),* }
+                }
+
+                fn slice<R>(&self, range: R) -> #slice #ty_generics where R: ::std::ops::RangeBounds<usize> {
+                    self.as_slice().slice(range)
+                }
+
+                fn slice_mut<R>(&mut self, range: R) -> #slice_mut #ty_generics where R: ::std::ops::RangeBounds<usize> {
+                    let range = (range.start_bound().cloned(), range.end_bound().cloned());
+                    #slice_mut { #(#names: &mut self.#names2[range]),* }
+                }
+
+                fn split_at(&self, mid: usize) -> (#slice #ty_generics, #slice #ty_generics) {
+                    self.as_slice().split_at(mid)
+                }
+
+                fn split_at_mut(&mut self, mid: usize) -> (#slice_mut #ty_generics, #slice_mut #ty_generics) {
+                    self.slice_mut(..).into_split_at(mid)
+                }
+
+                /// Split into two mutable slices that borrow for the lifetime
+                /// of this slice
+                fn into_split_at(self, mid: usize) -> (Self, Self) {
+                    #(let (#lefts, #rights) = self.#names.split_at_mut(mid);)*
+                    (#slice_mut { #(#names: #lefts),* }, #slice_mut { #(#names2: #rights),* })
+                }
+
+                fn chunks(&self, size: usize) -> #chunks #ty_generics {
+                    self.as_slice().chunks(size)
+                }
+
+                fn chunks_mut(&mut self, size: usize) -> #chunks_mut #ty_generics {
+                    assert!(size != 0, "chunk size must be non-zero");
+                    #chunks_mut { slice: self.slice_mut(..), size, exact: false }
+                }
+
+                fn chunks_exact(&self, size: usize) -> #chunks #ty_generics {
+                    self.as_slice().chunks_exact(size)
+                }
+
+                fn chunks_exact_mut(&mut self, size: usize) -> #chunks_mut #ty_generics {
+                    assert!(size != 0, "chunk size must be non-zero");
+                    #chunks_mut { slice: self.slice_mut(..), size, exact: true }
+                }
+
+                fn windows(&self, size: usize) -> #windows #ty_generics {
+                    self.as_slice().windows(size)
+                }
+            }
+        }
+    }
+
+    fn build_slice_iterator_impl(&self, types: &SliceTypes) -> quote::Tokens {
+        let SliceTypes { ref slice, ref slice_mut, ref chunks, ref chunks_mut, ref windows } = *types;
+        let (lt_impl_generics, lt_ty_generics, lt_where_clause) = self.lt_generics.split_for_impl();
+        let names = self.field_names();
+
+        let empty = quote! { #slice_mut { #(#names: &mut []),* } };
+        let chunk_helpers = quote! {
+            /// The number of chunks remaining in `len` elements
+            fn count_chunks(&self, len: usize) -> usize {
+                if self.exact { len / self.size } else { len.div_ceil(self.size) }
+            }
+
+            /// The length of the last of the chunks remaining in `len` elements
+            fn last_chunk_len(&self, len: usize) -> usize {
+                if self.exact || len % self.size == 0 { self.size } else { len % self.size }
+            }
+        };
+
+        quote! {
+            #[allow(dead_code)]
+            impl #lt_impl_generics #chunks #lt_ty_generics #lt_where_clause {
+                #chunk_helpers
+            }
+
+            impl #lt_impl_generics Iterator for #chunks #lt_ty_generics #lt_where_clause {
+                type Item = #slice #lt_ty_generics;
+
+                fn next(&mut self) -> Option<Self::Item> {
+                    if self.slice.is_empty() || (self.exact && self.slice.len() < self.size) {
+                        return None;
+                    }
+                    let mid = ::std::cmp::min(self.size, self.slice.len());
+                    let (head, tail) = self.slice.split_at(mid);
+                    self.slice = tail;
+                    Some(head)
+                }
+
+                fn size_hint(&self) -> (usize, Option<usize>) {
+                    let count = self.count_chunks(self.slice.len());
+                    (count, Some(count))
+                }
+
+                fn nth(&mut self, n: usize) -> Option<Self::Item> {
+                    let start = ::std::cmp::min(n.saturating_mul(self.size), self.slice.len());
+                    self.slice = self.slice.slice(start..);
+                    self.next()
+                }
+            }
+
+            impl #lt_impl_generics DoubleEndedIterator for #chunks #lt_ty_generics #lt_where_clause {
+                fn next_back(&mut self) -> Option<Self::Item> {
+                    let len = self.slice.len();
+                    if self.count_chunks(len) == 0 {
+                        return None;
+                    }
+                    // Exact chunks never include the remainder, which is dropped here
+                    let end = if self.exact { len - len % self.size } else { len };
+                    let start = end - self.last_chunk_len(len);
+                    let chunk = self.slice.slice(start..end);
+                    self.slice = self.slice.slice(..start);
+                    Some(chunk)
+                }
+            }
+
+            impl #lt_impl_generics ExactSizeIterator for #chunks #lt_ty_generics #lt_where_clause {}
+
+            impl #lt_impl_generics ::std::iter::FusedIterator for #chunks #lt_ty_generics #lt_where_clause {}
+
+            #[allow(dead_code)]
+            impl #lt_impl_generics #chunks_mut #lt_ty_generics #lt_where_clause {
+                #chunk_helpers
+            }
+
+            impl #lt_impl_generics Iterator for #chunks_mut #lt_ty_generics #lt_where_clause {
+                type Item = #slice_mut #lt_ty_generics;
+
+                fn next(&mut self) -> Option<Self::Item> {
+                    if self.slice.is_empty() || (self.exact && self.slice.len() < self.size) {
+                        return None;
+                    }
+                    let mid = ::std::cmp::min(self.size, self.slice.len());
+                    let slice = ::std::mem::replace(&mut self.slice, #empty);
+                    let (head, tail) = slice.into_split_at(mid);
+                    self.slice = tail;
+                    Some(head)
+                }
+
+                fn size_hint(&self) -> (usize, Option<usize>) {
+                    let count = self.count_chunks(self.slice.len());
+                    (count, Some(count))
+                }
+
+                fn nth(&mut self, n: usize) -> Option<Self::Item> {
+                    let start = ::std::cmp::min(n.saturating_mul(self.size), self.slice.len());
+                    let slice = ::std::mem::replace(&mut self.slice, #empty);
+                    self.slice = slice.into_split_at(start).1;
+                    self.next()
+                }
+            }
+
+            impl #lt_impl_generics DoubleEndedIterator for #chunks_mut #lt_ty_generics #lt_where_clause {
+                fn next_back(&mut self) -> Option<Self::Item> {
+                    let len = self.slice.len();
+                    if self.count_chunks(len) == 0 {
+                        return None;
+                    }
+                    // Exact chunks never include the remainder, which is dropped here
+                    let end = if self.exact { len - len % self.size } else { len };
+                    let start = end - self.last_chunk_len(len);
+                    let slice = ::std::mem::replace(&mut self.slice, #empty);
+                    let (head, tail) = slice.into_split_at(start);
+                    self.slice = head;
+                    Some(tail.into_split_at(end - start).0)
+                }
+            }
+
+            impl #lt_impl_generics ExactSizeIterator for #chunks_mut #lt_ty_generics #lt_where_clause {}
+
+            impl #lt_impl_generics ::std::iter::FusedIterator for #chunks_mut #lt_ty_generics #lt_where_clause {}
+
+            impl #lt_impl_generics Iterator for #windows #lt_ty_generics #lt_where_clause {
+                type Item = #slice #lt_ty_generics;
+
+                fn next(&mut self) -> Option<Self::Item> {
+                    if self.slice.len() < self.size {
+                        return None;
+                    }
+                    let window = self.slice.slice(..self.size);
+                    self.slice = self.slice.slice(1..);
+                    Some(window)
+                }
+
+                fn size_hint(&self) -> (usize, Option<usize>) {
+                    let count = (self.slice.len() + 1).saturating_sub(self.size);
+                    (count, Some(count))
+                }
+
+                fn nth(&mut self, n: usize) -> Option<Self::Item> {
+                    let start = ::std::cmp::min(n, self.slice.len());
+                    self.slice = self.slice.slice(start..);
+                    self.next()
+                }
+            }
+
+            impl #lt_impl_generics DoubleEndedIterator for #windows #lt_ty_generics #lt_where_clause {
+                fn next_back(&mut self) -> Option<Self::Item> {
+                    let len = self.slice.len();
+                    if len < self.size {
+                        return None;
+                    }
+                    let window = self.slice.slice(len - self.size..);
+                    self.slice = self.slice.slice(..len - 1);
+                    Some(window)
+                }
+            }
+
+            impl #lt_impl_generics ExactSizeIterator for #windows #lt_ty_generics #lt_where_clause {}
+
+            impl #lt_impl_generics ::std::iter::FusedIterator for #windows #lt_ty_generics #lt_where_clause {}
+        }
+    }
+
+    fn build_slice_into_iter_impl(&self, types: &SliceTypes) -> quote::Tokens {
+        let SliceTypes { ref slice, ref slice_mut, .. } = *types;
+        let ref type_ref = self.type_ref;
+        let ref type_ref_mut = self.type_ref_mut;
+        let ref type_iter = self.type_iter;
+        let ref type_iter_mut = self.type_iter_mut;
+        let (lt_impl_generics, lt_ty_generics, lt_where_clause) = self.lt_generics.split_for_impl();
+        let names = self.field_names();
+        let iters = self.iter_names();
+
+        quote! {
+            impl #lt_impl_generics IntoIterator for #slice #lt_ty_generics #lt_where_clause {
+                type Item = #type_ref #lt_ty_generics;
+                type IntoIter = #type_iter #lt_ty_generics;
+                fn into_iter(self) -> Self::IntoIter {
+                    self.iter()
+                }
+            }
+
+            impl #lt_impl_generics IntoIterator for #slice_mut #lt_ty_generics #lt_where_clause {
+                type Item = #type_ref_mut #lt_ty_generics;
+                type IntoIter = #type_iter_mut #lt_ty_generics;
+                fn into_iter(self) -> Self::IntoIter {
+                    #type_iter_mut {
+                        #(#iters: self.#names.iter_mut()),*
+                    }
+                }
+            }
+        }
+    }
+
+    /// Build the methods of the column type producing slices
+    fn build_container_slice_impl(&self, types: &SliceTypes) -> quote::Tokens {
+        let SliceTypes { ref slice, ref slice_mut, ref chunks, ref chunks_mut, ref windows } = *types;
+        let ref type_container = self.type_container;
+        let (lt_impl_generics, _lt_ty_generics, lt_where_clause) = self.lt_generics.split_for_impl();
+        let (_impl_generics, ty_generics, _where_clause) = self.ast.generics.split_for_impl();
+
+        let names: &Vec<_> = &self.field_names();
+        let names2 = names;
+
+        quote! {
+            #[allow(dead_code)]
+            impl #lt_impl_generics #type_container #ty_generics #lt_where_clause {
+                fn as_slice(&self) -> #slice #ty_generics {
+                    #slice { #(#names: &self.#names2[..]),* }
+                }
+
+                fn as_mut_slice(&mut self) -> #slice_mut #ty_generics {
+                    #slice_mut { #(#names: &mut self.#names2[..]),* }
+                }
+
+                fn slice<R>(&self, range: R) -> #slice #ty_generics where R: ::std::ops::RangeBounds<usize> {
+                    self.as_slice().slice(range)
+                }
+
+                fn slice_mut<R>(&mut self, range: R) -> #slice_mut #ty_generics where R: ::std::ops::RangeBounds<usize> {
+                    let range = (range.start_bound().cloned(), range.end_bound().cloned());
+                    #slice_mut { #(#names: &mut self.#names2[range]),* }
+                }
+
+                fn split_at(&self, mid: usize) -> (#slice #ty_generics, #slice #ty_generics) {
+                    self.as_slice().split_at(mid)
+                }
+
+                fn split_at_mut(&mut self, mid: usize) -> (#slice_mut #ty_generics, #slice_mut #ty_generics) {
+                    self.as_mut_slice().into_split_at(mid)
+                }
+
+                fn chunks(&self, size: usize) -> #chunks #ty_generics {
+                    self.as_slice().chunks(size)
+                }
+
+                fn chunks_mut(&mut self, size: usize) -> #chunks_mut #ty_generics {
+                    assert!(size != 0, "chunk size must be non-zero");
+                    #chunks_mut { slice: self.as_mut_slice(), size, exact: false }
+                }
+
+                fn chunks_exact(&self, size: usize) -> #chunks #ty_generics {
+                    self.as_slice().chunks_exact(size)
+                }
+
+                fn chunks_exact_mut(&mut self, size: usize) -> #chunks_mut #ty_generics {
+                    assert!(size != 0, "chunk size must be non-zero");
+                    #chunks_mut { slice: self.as_mut_slice(), size, exact: true }
+                }
+
+                fn windows(&self, size: usize) -> #windows #ty_generics {
+                    self.as_slice().windows(size)
+                }
+            }
+        }
+    }
+}
@@ -5,40 +5,1258 @@
 // http://opensource.org/licenses/MIT>, at your option. This file may not be
 // copied, modified, or distributed except according to those terms.
 //! Provide a filtered view on a collection.
+//!
+//! Visibility is stored in a `Bitmap`, which packs one bit per element into
+//! `u64` words. Very selective filters are better represented by a
+//! `SelectionVector` listing the indices of the selected elements, and
+//! `Selection` chooses between both representations. For long collections,
+//! a `CompressedBitmap` stores sparse and dense regions compactly and can
+//! back filtered collections in place of a `Bitmap`.
 //! # Examples
 //! ```
 //! use column::bitmap::FilteredCollection;
 //! let collection = vec![1, 2, 3];
-//! let mut filtered = FilteredCollection::new(&collection, collection.len());
+//! let mut filtered = FilteredCollection::new(&collection);
 //! filtered.retain(|&e| *e > 1);
 //! ```
 
-use std::iter::IntoIterator;
+use std::borrow::Borrow;
+use std::error::Error;
+use std::fmt;
+use std::iter::{FromIterator, IntoIterator};
+use std::marker::PhantomData;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex, MutexGuard};
 
-/// An iterator over a `FilteredCollection`
-pub struct FilteredCollectionIterator<'a, I>
+use Len;
+
+mod compressed;
+
+pub use self::compressed::{CompressedBitmap, CompressedIter, CompressedOnes};
+
+/// The number of bits stored in a word of a `Bitmap`
+const WORD_BITS: usize = 64;
+
+/// The number of words covered by an entry of the block index of a `Bitmap`
+const BLOCK_WORDS: usize = 8;
+
+/// A fixed set of bits packed into `u64` words
+///
+/// Bits beyond `len` in the last word are always zero, so whole words can be
+/// counted and scanned without masking.
+///
+/// Positional queries like `rank` and `select` use a block index storing the
+/// number of set bits before every block of 512 bits. The index is built up
+/// to the queried block, and a change only discards the entries after the
+/// changed block, so pushing bits keeps the index of the earlier blocks.
+///
+/// # Examples
+/// ```
+/// use column::bitmap::Bitmap;
+/// let mut bitmap = Bitmap::zeros(100);
+/// bitmap.set(3, true);
+/// bitmap.set(70, true);
+/// assert_eq!(bitmap.count_ones(), 2);
+/// assert_eq!(bitmap.iter_ones().collect::<Vec<_>>(), vec![3, 70]);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Bitmap {
+    /// The bits, least significant bit first
+    words: Vec<u64>,
+    /// The number of bits
+    len: usize,
+    /// The number of set bits before each block of `BLOCK_WORDS` words
+    blocks: BlockIndex,
+}
+
+/// A lazily built block index, which does not take part in comparisons
+///
+/// Holds the number of set bits before each block for a prefix of the
+/// blocks, followed by the total if all blocks are indexed.
+#[derive(Debug, Default)]
+struct BlockIndex(Mutex<Vec<usize>>);
+
+impl BlockIndex {
+    /// Discard the entries depending on `word`
+    fn invalidate(&mut self, word: usize) {
+        let blocks = self.0.get_mut().unwrap_or_else(|error| error.into_inner());
+        blocks.truncate(word / BLOCK_WORDS + 1);
+    }
+}
+
+impl Clone for BlockIndex {
+    fn clone(&self) -> Self {
+        BlockIndex(Mutex::new(self.0.lock().unwrap_or_else(|error| error.into_inner()).clone()))
+    }
+}
+
+impl PartialEq for BlockIndex {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for BlockIndex {}
+
+impl Bitmap {
+
+    /// Create a new `Bitmap` of `len` bits, all of which are unset
+    pub fn zeros(len: usize) -> Self {
+        Self::from_words(vec![0; len.div_ceil(WORD_BITS)], len)
+    }
+
+    /// Create a new `Bitmap` of `len` bits, all of which are set
+    pub fn ones(len: usize) -> Self {
+        let mut bitmap = Self::from_words(vec![!0; len.div_ceil(WORD_BITS)], len);
+        bitmap.clear_unused();
+        bitmap
+    }
+
+    /// The number of bits in this `Bitmap`
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Test if this `Bitmap` has no bits
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Read the bit at `index`
+    ///
+    /// # Panics
+    /// Panics if `index` is out of bounds.
+    pub fn get(&self, index: usize) -> bool {
+        assert!(index < self.len, "index out of bounds: the len is {} but the index is {}", self.len, index);
+        self.words[index / WORD_BITS] & (1 << (index % WORD_BITS)) != 0
+    }
+
+    /// Set the bit at `index` to `value`
+    ///
+    /// # Panics
+    /// Panics if `index` is out of bounds.
+    pub fn set(&mut self, index: usize, value: bool) {
+        assert!(index < self.len, "index out of bounds: the len is {} but the index is {}", self.len, index);
+        let mask = 1 << (index % WORD_BITS);
+        self.blocks.invalidate(index / WORD_BITS);
+        if value {
+            self.words[index / WORD_BITS] |= mask;
+        } else {
+            self.words[index / WORD_BITS] &= !mask;
+        }
+    }
+
+    /// Append a bit to the end of this `Bitmap`
+    pub fn push(&mut self, value: bool) {
+        if self.len.is_multiple_of(WORD_BITS) {
+            self.words.push(0);
+        }
+        self.len += 1;
+        let index = self.len - 1;
+        self.set(index, value);
+    }
+
+    /// Remove the last bit and return it, or `None` if the `Bitmap` is empty
+    pub fn pop(&mut self) -> Option<bool> {
+        let index = self.len.checked_sub(1)?;
+        let value = self.get(index);
+        self.truncate(index);
+        Some(value)
+    }
+
+    /// Insert a bit at `index`, shifting all bits after it
+    ///
+    /// # Panics
+    /// Panics if `index` is greater than the length.
+    pub fn insert(&mut self, index: usize, value: bool) {
+        assert!(index <= self.len, "insertion index {} is out of bounds for length {}", index, self.len);
+        self.push(false);
+        let word = index / WORD_BITS;
+        self.blocks.invalidate(word);
+        for i in (word + 1..self.words.len()).rev() {
+            self.words[i] = (self.words[i] << 1) | (self.words[i - 1] >> (WORD_BITS - 1));
+        }
+        // Keep the bits before `index` in the first affected word
+        let low = (1 << (index % WORD_BITS)) - 1;
+        self.words[word] = (self.words[word] & low) | ((self.words[word] & !low) << 1);
+        self.set(index, value);
+    }
+
+    /// Remove the bit at `index` and return it, shifting all bits after it
+    ///
+    /// # Panics
+    /// Panics if `index` is out of bounds.
+    pub fn remove(&mut self, index: usize) -> bool {
+        let value = self.get(index);
+        let word = index / WORD_BITS;
+        self.blocks.invalidate(word);
+        let low = (1 << (index % WORD_BITS)) - 1;
+        self.words[word] = (self.words[word] & low) | ((self.words[word] >> 1) & !low);
+        for i in word + 1..self.words.len() {
+            self.words[i - 1] |= self.words[i] << (WORD_BITS - 1);
+            self.words[i] >>= 1;
+        }
+        self.truncate(self.len - 1);
+        value
+    }
+
+    /// Shorten this `Bitmap` to `len` bits
+    pub fn truncate(&mut self, len: usize) {
+        if len < self.len {
+            self.blocks.invalidate(len / WORD_BITS);
+            self.len = len;
+            self.words.truncate(len.div_ceil(WORD_BITS));
+            self.clear_unused();
+        }
+    }
+
+    /// Split this `Bitmap` in two at `at`, returning the bits from `at` on
+    ///
+    /// # Panics
+    /// Panics if `at` is greater than the length.
+    pub fn split_off(&mut self, at: usize) -> Bitmap {
+        assert!(at <= self.len, "split index {} is out of bounds for length {}", at, self.len);
+        let (word, shift) = (at / WORD_BITS, at % WORD_BITS);
+        let words = if shift == 0 {
+            self.words.split_off(word)
+        } else {
+            // Move the bits from `at` on to the start of the words
+            (word..self.words.len()).map(|i| {
+                let high = self.words.get(i + 1).map_or(0, |next| next << (WORD_BITS - shift));
+                (self.words[i] >> shift) | high
+            }).collect()
+        };
+        let mut tail = Self::from_words(words, self.len - at);
+        tail.words.truncate(tail.len.div_ceil(WORD_BITS));
+        self.truncate(at);
+        tail
+    }
+
+    /// Move all bits of `other` to the end of this `Bitmap`
+    pub fn append(&mut self, other: &mut Bitmap) {
+        let shift = self.len % WORD_BITS;
+        self.blocks.invalidate(self.len / WORD_BITS);
+        if shift == 0 {
+            self.words.extend_from_slice(&other.words);
+        } else {
+            // Fill the unused bits of the last word and carry the rest over
+            self.words.reserve(other.words.len());
+            for &word in &other.words {
+                *self.words.last_mut().unwrap() |= word << shift;
+                self.words.push(word >> (WORD_BITS - shift));
+            }
+        }
+        self.len += other.len;
+        self.words.truncate(self.len.div_ceil(WORD_BITS));
+        other.clear();
+    }
+
+    /// Remove all bits
+    pub fn clear(&mut self) {
+        self.truncate(0);
+    }
+
+    /// Create an empty `Bitmap` with space for `capacity` bits
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::from_words(Vec::with_capacity(capacity.div_ceil(WORD_BITS)), 0)
+    }
+
+    /// The number of bits this `Bitmap` can hold without reallocating
+    pub fn capacity(&self) -> usize {
+        self.words.capacity() * WORD_BITS
+    }
+
+    /// Reserve space for at least `additional` more bits
+    pub fn reserve(&mut self, additional: usize) {
+        let words = (self.len + additional).div_ceil(WORD_BITS);
+        self.words.reserve(words.saturating_sub(self.words.len()));
+    }
+
+    /// Shrink the capacity as much as possible
+    pub fn shrink_to_fit(&mut self) {
+        self.words.shrink_to_fit();
+    }
+
+    /// The number of set bits
+    pub fn count_ones(&self) -> usize {
+        self.words.iter().map(|word| word.count_ones() as usize).sum()
+    }
+
+    /// The number of unset bits
+    pub fn count_zeros(&self) -> usize {
+        self.len - self.count_ones()
+    }
+
+    /// Iterate all bits in order
+    pub fn iter(&self) -> Iter<'_> {
+        Iter {
+            bitmap: self,
+            range: 0..self.len,
+        }
+    }
+
+    /// Iterate the indices of all set bits in ascending order, skipping
+    /// words without any set bit
+    pub fn iter_ones(&self) -> Ones<'_> {
+        Ones {
+            words: &self.words,
+            index: 0,
+            current: 0,
+        }
+    }
+
+    /// The intersection of this and another `Bitmap` of the same length
+    ///
+    /// # Panics
+    /// Panics if the lengths differ.
+    ///
+    /// # Examples
+    /// ```
+    /// use column::bitmap::Bitmap;
+    /// let a: Bitmap = vec![true, true, false].into_iter().collect();
+    /// let b: Bitmap = vec![true, false, false].into_iter().collect();
+    /// assert_eq!(a.and(&b).iter_ones().collect::<Vec<_>>(), vec![0]);
+    /// assert_eq!(a.or(&b).iter_ones().collect::<Vec<_>>(), vec![0, 1]);
+    /// assert_eq!(a.xor(&b).iter_ones().collect::<Vec<_>>(), vec![1]);
+    /// assert_eq!(a.and_not(&b).iter_ones().collect::<Vec<_>>(), vec![1]);
+    /// assert_eq!(a.not().iter_ones().collect::<Vec<_>>(), vec![2]);
+    /// ```
+    pub fn and(&self, other: &Bitmap) -> Bitmap {
+        self.combine(other, |a, b| a & b)
+    }
+
+    /// The union of this and another `Bitmap` of the same length
+    ///
+    /// # Panics
+    /// Panics if the lengths differ.
+    pub fn or(&self, other: &Bitmap) -> Bitmap {
+        self.combine(other, |a, b| a | b)
+    }
+
+    /// The bits set in exactly one of this and another `Bitmap` of the same
+    /// length
+    ///
+    /// # Panics
+    /// Panics if the lengths differ.
+    pub fn xor(&self, other: &Bitmap) -> Bitmap {
+        self.combine(other, |a, b| a ^ b)
+    }
+
+    /// The bits set in this but not in another `Bitmap` of the same length
+    ///
+    /// # Panics
+    /// Panics if the lengths differ.
+    pub fn and_not(&self, other: &Bitmap) -> Bitmap {
+        self.combine(other, |a, b| a & !b)
+    }
+
+    /// The complement of this `Bitmap`
+    #[allow(clippy::should_implement_trait)]
+    pub fn not(&self) -> Bitmap {
+        let mut bitmap = Self::from_words(self.words.iter().map(|word| !word).collect(), self.len);
+        bitmap.clear_unused();
+        bitmap
+    }
+
+    /// Combine the words of this and another `Bitmap` with `f`, which must
+    /// map unused zero bits to zero
+    fn combine<F>(&self, other: &Bitmap, f: F) -> Bitmap
+        where F: Fn(u64, u64) -> u64,
+    {
+        assert_eq!(self.len, other.len, "Bitmaps of different lengths");
+        Self::from_words(self.words.iter().zip(&other.words).map(|(&a, &b)| f(a, b)).collect(), self.len)
+    }
+
+    /// The number of set bits before the bit at `index`, for `index` up to
+    /// and including `len`
+    ///
+    /// # Panics
+    /// Panics if `index` is greater than `len`.
+    ///
+    /// # Examples
+    /// ```
+    /// use column::bitmap::Bitmap;
+    /// let bitmap: Bitmap = vec![true, false, true, true].into_iter().collect();
+    /// assert_eq!(bitmap.rank(0), 0);
+    /// assert_eq!(bitmap.rank(3), 2);
+    /// assert_eq!(bitmap.rank(4), 3);
+    /// ```
+    pub fn rank(&self, index: usize) -> usize {
+        assert!(index <= self.len, "index out of bounds: the len is {} but the index is {}", self.len, index);
+        let word = index / WORD_BITS;
+        let block = word / BLOCK_WORDS;
+        let mut rank = self.block_rank(block);
+        rank += self.words[block * BLOCK_WORDS..word].iter().map(|w| w.count_ones() as usize).sum::<usize>();
+        let bit = index % WORD_BITS;
+        if bit > 0 {
+            rank += (self.words[word] & ((1 << bit) - 1)).count_ones() as usize;
+        }
+        rank
+    }
+
+    /// The index of the set bit preceded by `rank` set bits, or `None` if
+    /// there are at most `rank` set bits
+    ///
+    /// # Examples
+    /// ```
+    /// use column::bitmap::Bitmap;
+    /// let bitmap: Bitmap = vec![true, false, true, true].into_iter().collect();
+    /// assert_eq!(bitmap.select(0), Some(0));
+    /// assert_eq!(bitmap.select(1), Some(2));
+    /// assert_eq!(bitmap.select(3), None);
+    /// ```
+    pub fn select(&self, rank: usize) -> Option<usize> {
+        let (block, mut remaining) = {
+            let last = self.words.len().div_ceil(BLOCK_WORDS);
+            let blocks = self.block_index(last);
+            if rank >= blocks[last] {
+                return None;
+            }
+            // The last block starting with at most `rank` set bits
+            let block = blocks[..=last].partition_point(|&count| count <= rank) - 1;
+            (block, rank - blocks[block])
+        };
+        for (offset, &word) in self.words[block * BLOCK_WORDS..].iter().enumerate() {
+            let ones = word.count_ones() as usize;
+            if remaining < ones {
+                let index = (block * BLOCK_WORDS + offset) * WORD_BITS;
+                return Some(index + select_in_word(word, remaining));
+            }
+            remaining -= ones;
+        }
+        unreachable!()
+    }
+
+    /// The index of the first set bit, or `None` if no bit is set
+    pub fn first_set(&self) -> Option<usize> {
+        self.next_set(0)
+    }
+
+    /// The index of the last set bit, or `None` if no bit is set
+    pub fn last_set(&self) -> Option<usize> {
+        self.prev_set(self.len)
+    }
+
+    /// Iterate the indices of all set bits in ascending order. Unlike
+    /// `iter_ones`, the iterator knows its length, can be reversed and uses
+    /// the block index to skip elements, e.g. to paginate results.
+    ///
+    /// # Examples
+    /// ```
+    /// use column::bitmap::Bitmap;
+    /// let bitmap: Bitmap = (0..1000).map(|i| i % 3 == 0).collect();
+    /// let page: Vec<_> = bitmap.iter_indices().skip(100).take(3).collect();
+    /// assert_eq!(page, vec![300, 303, 306]);
+    /// assert_eq!(bitmap.iter_indices().rev().next(), Some(999));
+    /// assert_eq!(bitmap.iter_indices().len(), 334);
+    /// ```
+    pub fn iter_indices(&self) -> Indices<'_> {
+        Indices {
+            bitmap: self,
+            front: 0,
+            back: self.len,
+            remaining: self.count_ones(),
+        }
+    }
+
+    /// The index of the first set bit at or after `index`
+    fn next_set(&self, index: usize) -> Option<usize> {
+        if index >= self.len {
+            return None;
+        }
+        let mut word = index / WORD_BITS;
+        // Ignore the bits before `index`
+        let mut bits = self.words[word] & (!0 << (index % WORD_BITS));
+        while bits == 0 {
+            word += 1;
+            bits = *self.words.get(word)?;
+        }
+        Some(word * WORD_BITS + bits.trailing_zeros() as usize)
+    }
+
+    /// The index of the last set bit before `index`
+    fn prev_set(&self, index: usize) -> Option<usize> {
+        if index == 0 {
+            return None;
+        }
+        let last = index - 1;
+        let mut word = last / WORD_BITS;
+        // Ignore the bits after `last`
+        let mut bits = self.words[word] & (!0 >> (WORD_BITS - 1 - last % WORD_BITS));
+        while bits == 0 {
+            if word == 0 {
+                return None;
+            }
+            word -= 1;
+            bits = self.words[word];
+        }
+        Some(word * WORD_BITS + WORD_BITS - 1 - bits.leading_zeros() as usize)
+    }
+
+    /// The number of set bits before each block, followed by the total number
+    /// of set bits
+    /// The block index, extended to hold the entry of `block`
+    fn block_index(&self, block: usize) -> MutexGuard<'_, Vec<usize>> {
+        let mut blocks = self.blocks.0.lock().unwrap_or_else(|error| error.into_inner());
+        if blocks.is_empty() {
+            blocks.push(0);
+        }
+        while blocks.len() <= block {
+            let start = (blocks.len() - 1) * BLOCK_WORDS;
+            let end = (start + BLOCK_WORDS).min(self.words.len());
+            let count = blocks[blocks.len() - 1] + self.words[start..end].iter().map(|w| w.count_ones() as usize).sum::<usize>();
+            blocks.push(count);
+        }
+        blocks
+    }
+
+    /// The number of set bits before `block`
+    fn block_rank(&self, block: usize) -> usize {
+        self.block_index(block)[block]
+    }
+
+    /// Create a `Bitmap` of `len` bits stored in `words`
+    fn from_words(words: Vec<u64>, len: usize) -> Self {
+        Self {
+            words,
+            len,
+            blocks: BlockIndex::default(),
+        }
+    }
+
+    /// Reset the bits beyond `len` in the last word
+    fn clear_unused(&mut self) {
+        let used = self.len % WORD_BITS;
+        if used > 0 {
+            if let Some(last) = self.words.last_mut() {
+                *last &= (1 << used) - 1;
+            }
+        }
+    }
+}
+
+/// The index of the set bit in `word` preceded by `rank` set bits
+fn select_in_word(mut word: u64, rank: usize) -> usize {
+    for _ in 0..rank {
+        // Clear the lowest set bit
+        word &= word - 1;
+    }
+    word.trailing_zeros() as usize
+}
+
+impl<'a> ::std::ops::BitAnd for &'a Bitmap {
+    type Output = Bitmap;
+    fn bitand(self, other: &'a Bitmap) -> Bitmap {
+        self.and(other)
+    }
+}
+
+impl<'a> ::std::ops::BitOr for &'a Bitmap {
+    type Output = Bitmap;
+    fn bitor(self, other: &'a Bitmap) -> Bitmap {
+        self.or(other)
+    }
+}
+
+impl<'a> ::std::ops::BitXor for &'a Bitmap {
+    type Output = Bitmap;
+    fn bitxor(self, other: &'a Bitmap) -> Bitmap {
+        self.xor(other)
+    }
+}
+
+impl ::std::ops::Not for &Bitmap {
+    type Output = Bitmap;
+    fn not(self) -> Bitmap {
+        Bitmap::not(self)
+    }
+}
+
+impl FromIterator<bool> for Bitmap {
+    fn from_iter<T: IntoIterator<Item=bool>>(iter: T) -> Self {
+        let mut bitmap = Self::default();
+        for value in iter {
+            bitmap.push(value);
+        }
+        bitmap
+    }
+}
+
+impl Extend<bool> for Bitmap {
+    fn extend<T: IntoIterator<Item=bool>>(&mut self, iter: T) {
+        for value in iter {
+            self.push(value);
+        }
+    }
+}
+
+impl IntoIterator for Bitmap {
+    type Item = bool;
+    type IntoIter = IntoIter;
+    fn into_iter(self) -> IntoIter {
+        IntoIter {
+            range: 0..self.len,
+            bitmap: self,
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a Bitmap {
+    type Item = bool;
+    type IntoIter = Iter<'a>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// An iterator over the bits of a `Bitmap`
+#[derive(Clone, Debug)]
+pub struct Iter<'a> {
+    bitmap: &'a Bitmap,
+    range: ::std::ops::Range<usize>,
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = bool;
+
+    fn next(&mut self) -> Option<bool> {
+        self.range.next().map(|index| self.bitmap.get(index))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.range.size_hint()
+    }
+
+    fn nth(&mut self, n: usize) -> Option<bool> {
+        self.range.nth(n).map(|index| self.bitmap.get(index))
+    }
+}
+
+impl<'a> DoubleEndedIterator for Iter<'a> {
+    fn next_back(&mut self) -> Option<bool> {
+        self.range.next_back().map(|index| self.bitmap.get(index))
+    }
+}
+
+impl<'a> ExactSizeIterator for Iter<'a> {}
+
+impl<'a> ::std::iter::FusedIterator for Iter<'a> {}
+
+/// An owning iterator over the bits of a `Bitmap`
+#[derive(Clone, Debug)]
+pub struct IntoIter {
+    bitmap: Bitmap,
+    range: ::std::ops::Range<usize>,
+}
+
+impl Iterator for IntoIter {
+    type Item = bool;
+
+    fn next(&mut self) -> Option<bool> {
+        self.range.next().map(|index| self.bitmap.get(index))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.range.size_hint()
+    }
+
+    fn nth(&mut self, n: usize) -> Option<bool> {
+        self.range.nth(n).map(|index| self.bitmap.get(index))
+    }
+}
+
+impl DoubleEndedIterator for IntoIter {
+    fn next_back(&mut self) -> Option<bool> {
+        self.range.next_back().map(|index| self.bitmap.get(index))
+    }
+}
+
+impl ExactSizeIterator for IntoIter {}
+
+impl ::std::iter::FusedIterator for IntoIter {}
+
+/// An iterator over the indices of the set bits of a `Bitmap`
+#[derive(Clone, Debug)]
+pub struct Ones<'a> {
+    /// The words of the bitmap
+    words: &'a [u64],
+    /// The index of the next word to load
+    index: usize,
+    /// The remaining set bits of the word at `index - 1`
+    current: u64,
+}
+
+impl<'a> Iterator for Ones<'a> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        while self.current == 0 {
+            self.current = *self.words.get(self.index)?;
+            self.index += 1;
+        }
+        let bit = self.current.trailing_zeros() as usize;
+        // Clear the lowest set bit
+        self.current &= self.current - 1;
+        Some((self.index - 1) * WORD_BITS + bit)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let current = self.current.count_ones() as usize;
+        let remaining = self.words.len() - self.index;
+        (current, Some(current + remaining * WORD_BITS))
+    }
+
+    fn nth(&mut self, mut n: usize) -> Option<usize> {
+        // Skip whole words by their number of set bits
+        loop {
+            let ones = self.current.count_ones() as usize;
+            if n < ones {
+                for _ in 0..n {
+                    self.current &= self.current - 1;
+                }
+                return self.next();
+            }
+            n -= ones;
+            self.current = 0;
+            self.current = *self.words.get(self.index)?;
+            self.index += 1;
+        }
+    }
+}
+
+impl<'a> ::std::iter::FusedIterator for Ones<'a> {}
+
+/// An iterator over the indices of the set bits of a `Bitmap`, which can be
+/// reversed and skips elements using the block index
+#[derive(Clone, Debug)]
+pub struct Indices<'a> {
+    bitmap: &'a Bitmap,
+    /// The first index to search for set bits
+    front: usize,
+    /// The index after the last index to search for set bits
+    back: usize,
+    /// The number of set bits between `front` and `back`
+    remaining: usize,
+}
+
+impl<'a> Iterator for Indices<'a> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let index = self.bitmap.next_set(self.front)?;
+        self.front = index + 1;
+        self.remaining -= 1;
+        Some(index)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+
+    fn nth(&mut self, n: usize) -> Option<usize> {
+        if n >= self.remaining {
+            self.remaining = 0;
+            return None;
+        }
+        let index = self.bitmap.select(self.bitmap.rank(self.front) + n)?;
+        self.front = index + 1;
+        self.remaining -= n + 1;
+        Some(index)
+    }
+}
+
+impl<'a> DoubleEndedIterator for Indices<'a> {
+    fn next_back(&mut self) -> Option<usize> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let index = self.bitmap.prev_set(self.back)?;
+        self.back = index;
+        self.remaining -= 1;
+        Some(index)
+    }
+}
+
+impl<'a> ExactSizeIterator for Indices<'a> {}
+
+impl<'a> ::std::iter::FusedIterator for Indices<'a> {}
+
+/// The ratio of rows to selected rows above which a `Selection` stores a
+/// `SelectionVector`. At this ratio, both representations take the same
+/// space, and the vector needs fewer steps to iterate.
+const SPARSE_RATIO: usize = 32;
+
+/// A sorted list of the indices of selected rows out of `rows` rows
+///
+/// # Examples
+/// ```
+/// use column::bitmap::{Bitmap, SelectionVector};
+/// let selection = SelectionVector::from_indices(vec![2, 7], 10);
+/// let bitmap = Bitmap::from(&selection);
+/// assert_eq!(bitmap.iter_ones().collect::<Vec<_>>(), vec![2, 7]);
+/// assert_eq!(SelectionVector::from(&bitmap), selection);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SelectionVector {
+    /// The selected indices in strictly ascending order
+    indices: Vec<u32>,
+    /// The number of rows to select from
+    rows: usize,
+}
+
+impl SelectionVector {
+
+    /// Create a new `SelectionVector` selecting none of `rows` rows
+    pub fn new(rows: usize) -> Self {
+        Self::from_indices(Vec::new(), rows)
+    }
+
+    /// Create a new `SelectionVector` of `rows` rows selecting `indices`
+    ///
+    /// # Panics
+    /// Panics if the indices are not strictly ascending or out of bounds.
+    pub fn from_indices(indices: Vec<u32>, rows: usize) -> Self {
+        assert!(indices.windows(2).all(|w| w[0] < w[1]), "Selected indices must be strictly ascending");
+        if let Some(&last) = indices.last() {
+            assert!((last as usize) < rows, "index out of bounds: the rows are {} but the index is {}", rows, last);
+        }
+        Self { indices, rows }
+    }
+
+    /// Select the row at `index`, which must be greater than all selected
+    /// indices
+    ///
+    /// # Panics
+    /// Panics if `index` is out of bounds, not greater than the last selected
+    /// index, or does not fit in a `u32`.
+    pub fn push(&mut self, index: usize) {
+        assert!(index < self.rows, "index out of bounds: the rows are {} but the index is {}", self.rows, index);
+        assert!(self.indices.last().is_none_or(|&last| (last as usize) < index), "Selected indices must be strictly ascending");
+        assert!(index <= u32::MAX as usize, "index {} does not fit in a SelectionVector", index);
+        self.indices.push(index as u32);
+    }
+
+    /// The number of selected rows
+    pub fn len(&self) -> usize {
+        self.indices.len()
+    }
+
+    /// Test if no row is selected
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    /// The number of rows to select from
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// The selected indices in ascending order
+    pub fn as_slice(&self) -> &[u32] {
+        &self.indices
+    }
+
+    /// Test if the row at `index` is selected
+    pub fn contains(&self, index: usize) -> bool {
+        index <= u32::MAX as usize && self.indices.binary_search(&(index as u32)).is_ok()
+    }
+
+    /// Iterate the selected indices in ascending order
+    pub fn iter(&self) -> SelectionVectorIter<'_> {
+        SelectionVectorIter {
+            iter: self.indices.iter(),
+        }
+    }
+}
+
+impl<'a> From<&'a Bitmap> for SelectionVector {
+    fn from(bitmap: &'a Bitmap) -> Self {
+        assert!(bitmap.len() as u64 <= u64::from(u32::MAX) + 1, "Bitmap of length {} does not fit in a SelectionVector", bitmap.len());
+        Self {
+            indices: bitmap.iter_ones().map(|index| index as u32).collect(),
+            rows: bitmap.len(),
+        }
+    }
+}
+
+impl<'a> From<&'a SelectionVector> for Bitmap {
+    fn from(selection: &'a SelectionVector) -> Self {
+        let mut bitmap = Bitmap::zeros(selection.rows);
+        for index in selection.iter() {
+            bitmap.set(index, true);
+        }
+        bitmap
+    }
+}
+
+impl<'a> IntoIterator for &'a SelectionVector {
+    type Item = usize;
+    type IntoIter = SelectionVectorIter<'a>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// An iterator over the indices of a `SelectionVector`
+#[derive(Clone, Debug)]
+pub struct SelectionVectorIter<'a> {
+    iter: ::std::slice::Iter<'a, u32>,
+}
+
+impl<'a> Iterator for SelectionVectorIter<'a> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        self.iter.next().map(|&index| index as usize)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+
+    fn nth(&mut self, n: usize) -> Option<usize> {
+        self.iter.nth(n).map(|&index| index as usize)
+    }
+}
+
+impl<'a> DoubleEndedIterator for SelectionVectorIter<'a> {
+    fn next_back(&mut self) -> Option<usize> {
+        self.iter.next_back().map(|&index| index as usize)
+    }
+}
+
+impl<'a> ExactSizeIterator for SelectionVectorIter<'a> {}
+
+impl<'a> ::std::iter::FusedIterator for SelectionVectorIter<'a> {}
+
+/// A selection of rows stored either as a `Bitmap` or, for very selective
+/// filters, as a `SelectionVector`
+///
+/// # Examples
+/// ```
+/// use column::bitmap::{Bitmap, Selection};
+/// let sparse: Bitmap = (0..1000).map(|i| i == 500).collect();
+/// let selection = Selection::from_bitmap(sparse);
+/// assert!(selection.is_vector());
+/// assert_eq!(selection.iter().collect::<Vec<_>>(), vec![500]);
+///
+/// let dense = Selection::from_bitmap(Bitmap::ones(1000));
+/// assert!(!dense.is_vector());
+/// assert_eq!(dense.len(), 1000);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Selection {
+    /// One bit for every row
+    Bitmap(Bitmap),
+    /// The indices of the selected rows
+    Vector(SelectionVector),
+}
+
+impl Selection {
+
+    /// Store the rows selected by `bitmap` in the representation suited to
+    /// its selectivity
+    pub fn from_bitmap(bitmap: Bitmap) -> Self {
+        if bitmap.count_ones() * SPARSE_RATIO < bitmap.len() {
+            Selection::Vector(SelectionVector::from(&bitmap))
+        } else {
+            Selection::Bitmap(bitmap)
+        }
+    }
+
+    /// Store the rows selected by `selection` in the representation suited to
+    /// its selectivity
+    pub fn from_selection_vector(selection: SelectionVector) -> Self {
+        if selection.len() * SPARSE_RATIO < selection.rows() {
+            Selection::Vector(selection)
+        } else {
+            Selection::Bitmap(Bitmap::from(&selection))
+        }
+    }
+
+    /// Test if the selection is stored as a `SelectionVector`
+    pub fn is_vector(&self) -> bool {
+        match *self {
+            Selection::Bitmap(_) => false,
+            Selection::Vector(_) => true,
+        }
+    }
+
+    /// The number of selected rows
+    pub fn len(&self) -> usize {
+        match *self {
+            Selection::Bitmap(ref bitmap) => bitmap.count_ones(),
+            Selection::Vector(ref selection) => selection.len(),
+        }
+    }
+
+    /// Test if no row is selected
+    pub fn is_empty(&self) -> bool {
+        self.iter().next().is_none()
+    }
+
+    /// The number of rows to select from
+    pub fn rows(&self) -> usize {
+        match *self {
+            Selection::Bitmap(ref bitmap) => bitmap.len(),
+            Selection::Vector(ref selection) => selection.rows(),
+        }
+    }
+
+    /// Test if the row at `index` is selected
+    pub fn contains(&self, index: usize) -> bool {
+        match *self {
+            Selection::Bitmap(ref bitmap) => index < bitmap.len() && bitmap.get(index),
+            Selection::Vector(ref selection) => selection.contains(index),
+        }
+    }
+
+    /// Iterate the selected indices in ascending order
+    pub fn iter(&self) -> SelectionIter<'_> {
+        match *self {
+            Selection::Bitmap(ref bitmap) => SelectionIter::Bitmap(bitmap.iter_ones()),
+            Selection::Vector(ref selection) => SelectionIter::Vector(selection.iter()),
+        }
+    }
+
+    /// Convert the selection to a `Bitmap`
+    pub fn into_bitmap(self) -> Bitmap {
+        match self {
+            Selection::Bitmap(bitmap) => bitmap,
+            Selection::Vector(selection) => Bitmap::from(&selection),
+        }
+    }
+
+    /// Convert the selection to a `SelectionVector`
+    pub fn into_selection_vector(self) -> SelectionVector {
+        match self {
+            Selection::Bitmap(bitmap) => SelectionVector::from(&bitmap),
+            Selection::Vector(selection) => selection,
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a Selection {
+    type Item = usize;
+    type IntoIter = SelectionIter<'a>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// An iterator over the indices of a `Selection`
+#[derive(Clone, Debug)]
+pub enum SelectionIter<'a> {
+    /// Iterate the set bits of a `Bitmap`
+    Bitmap(Ones<'a>),
+    /// Iterate the indices of a `SelectionVector`
+    Vector(SelectionVectorIter<'a>),
+}
+
+impl<'a> Iterator for SelectionIter<'a> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        match *self {
+            SelectionIter::Bitmap(ref mut iter) => iter.next(),
+            SelectionIter::Vector(ref mut iter) => iter.next(),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match *self {
+            SelectionIter::Bitmap(ref iter) => iter.size_hint(),
+            SelectionIter::Vector(ref iter) => iter.size_hint(),
+        }
+    }
+}
+
+impl<'a> ::std::iter::FusedIterator for SelectionIter<'a> {}
+
+/// A fixed set of bits selecting the visible elements of a filtered
+/// collection, implemented by `Bitmap` and `CompressedBitmap`
+pub trait BitSet {
+    /// An iterator over the indices of the set bits in ascending order
+    type IterOnes<'b>: Iterator<Item=usize> where Self: 'b;
+
+    /// Create a new set of `len` bits, all of which are set
+    fn ones(len: usize) -> Self;
+
+    /// The number of bits
+    fn len(&self) -> usize;
+
+    /// Test if there are no bits
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Read the bit at `index`
+    fn get(&self, index: usize) -> bool;
+
+    /// Set the bit at `index` to `value`
+    fn set(&mut self, index: usize, value: bool);
+
+    /// The number of set bits
+    fn count_ones(&self) -> usize;
+
+    /// Iterate the indices of all set bits in ascending order
+    fn iter_ones(&self) -> Self::IterOnes<'_>;
+
+    /// The index of the set bit preceded by `rank` set bits
+    fn select(&self, rank: usize) -> Option<usize>;
+
+    /// The intersection of two sets of the same length
+    fn and(&self, other: &Self) -> Self;
+
+    /// The union of two sets of the same length
+    fn or(&self, other: &Self) -> Self;
+
+    /// The bits set in exactly one of two sets of the same length
+    fn xor(&self, other: &Self) -> Self;
+
+    /// The bits set in this but not in another set of the same length
+    fn and_not(&self, other: &Self) -> Self;
+
+    /// The complement of this set
+    fn not(&self) -> Self;
+}
+
+impl BitSet for Bitmap {
+    type IterOnes<'b> = Ones<'b>;
+
+    fn ones(len: usize) -> Self {
+        Bitmap::ones(len)
+    }
+
+    fn len(&self) -> usize {
+        Bitmap::len(self)
+    }
+
+    fn get(&self, index: usize) -> bool {
+        Bitmap::get(self, index)
+    }
+
+    fn set(&mut self, index: usize, value: bool) {
+        Bitmap::set(self, index, value)
+    }
+
+    fn count_ones(&self) -> usize {
+        Bitmap::count_ones(self)
+    }
+
+    fn iter_ones(&self) -> Ones<'_> {
+        Bitmap::iter_ones(self)
+    }
+
+    fn select(&self, rank: usize) -> Option<usize> {
+        Bitmap::select(self, rank)
+    }
+
+    fn and(&self, other: &Self) -> Self {
+        Bitmap::and(self, other)
+    }
+
+    fn or(&self, other: &Self) -> Self {
+        Bitmap::or(self, other)
+    }
+
+    fn xor(&self, other: &Self) -> Self {
+        Bitmap::xor(self, other)
+    }
+
+    fn and_not(&self, other: &Self) -> Self {
+        Bitmap::and_not(self, other)
+    }
+
+    fn not(&self) -> Self {
+        Bitmap::not(self)
+    }
+}
+
+/// A collection that can copy the elements selected by a `BitSet` into a
+/// new, dense collection
+///
+/// Derived columns implement `Materialize` field by field if all stored field
+/// types implement `Clone`.
+pub trait Materialize {
+    /// The collection of the selected elements
+    type Output;
+
+    /// Copy the elements selected by `bitmap` into a new collection
+    ///
+    /// # Panics
+    /// Panics if the length of `bitmap` differs from the length of the
+    /// collection.
+    fn materialize<S: BitSet>(&self, bitmap: &S) -> Self::Output;
+}
+
+impl<T: Clone> Materialize for Vec<T> {
+    type Output = Vec<T>;
+
+    fn materialize<S: BitSet>(&self, bitmap: &S) -> Vec<T> {
+        assert_eq!(bitmap.len(), self.len(), "Bitmap length must match the collection length");
+        let mut result = Vec::with_capacity(bitmap.count_ones());
+        result.extend(bitmap.iter_ones().map(|index| self[index].clone()));
+        result
+    }
+}
+
+/// The error returned by `try_new` and `try_from_bitmap` if the requested
+/// length or the bitmap length does not match the length of the wrapped
+/// collection
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LengthMismatch {
+    /// The requested length or the length of the bitmap
+    pub len: usize,
+    /// The number of elements in the wrapped collection
+    pub collection_len: usize,
+}
+
+impl fmt::Display for LengthMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "length {} does not match the collection length {}", self.len, self.collection_len)
+    }
+}
+
+impl Error for LengthMismatch {}
+
+/// Check that `len` matches the length of `collection`
+fn check_len<A: Len + ?Sized>(collection: &A, len: usize) -> Result<(), LengthMismatch> {
+    if len == collection.len() {
+        Ok(())
+    } else {
+        Err(LengthMismatch { len, collection_len: collection.len() })
+    }
+}
+
+/// An iterator over a `FilteredCollection` or `FilteredCollectionMut`
+pub struct FilteredCollectionIterator<'a, I, B = Bitmap>
     where I: Iterator,
+          B: BitSet + 'a,
 {
     /// An iterator over data elements
     iter_wrapped: I,
-    /// A bitmap iterator to select visible elements
-    iter_bitmap: ::std::slice::Iter<'a, bool>,
+    /// The indices of the visible elements
+    iter_bitmap: B::IterOnes<'a>,
+    /// The index of the next element of `iter_wrapped`
+    position: usize,
 }
 
 #[derive(Debug)]
 /// A collection wrapper that can hide individual elements from iteration.
-pub struct FilteredCollection<'a, A>
+pub struct FilteredCollection<'a, A, B = Bitmap>
     where &'a A: IntoIterator,
           A: 'a,
+          B: BitSet,
 {
     // Test ref or Rc
     /// The wrapped collection
     wrapped: &'a A,
     /// A bitmap indicting item visibility
-    bitmap: Vec<bool>,
-    /// The number of visible items
-    // TODO: Should we really have this?
-    valid_items: usize
+    bitmap: B,
 }
 
 impl<'a, A> FilteredCollection<'a, A>
@@ -46,23 +1264,97 @@ impl<'a, A> FilteredCollection<'a, A>
           A: 'a,
 {
 
-    /// Create a new `FilteredCollection`
-    /// `new()` takes two arguments: a collection of type `A` to wrap and a lenght
-    /// of the bitmap. The lenght should be less or equal to the number of elements
-    /// in the collection. If the collection has more elements, only the first `len`
-    /// elements will be visible.
+    /// Create a new `FilteredCollection` exposing all elements of the
+    /// collection. The length of the bitmap is taken from the collection.
     ///
     /// # Examples
     /// ```
     /// # use column::bitmap::FilteredCollection;
     /// let collection = vec![1, 2, 3];
-    /// let mut filtered = FilteredCollection::new(&collection, collection.len());
+    /// let mut filtered = FilteredCollection::new(&collection);
     /// ```
-    pub fn new(wrapped: &'a A, len: usize) -> Self {
-        Self {
+    pub fn new(wrapped: &'a A) -> Self
+        where A: Len,
+    {
+        Self::from_bitmap(wrapped, Bitmap::ones(wrapped.len()))
+    }
+
+    /// Create a new `FilteredCollection` exposing all `len` elements of the
+    /// collection, or return an error if the collection has a different
+    /// number of elements
+    ///
+    /// # Examples
+    /// ```
+    /// # use column::bitmap::FilteredCollection;
+    /// let collection = vec![1, 2, 3];
+    /// assert!(FilteredCollection::try_new(&collection, 3).is_ok());
+    /// assert!(FilteredCollection::try_new(&collection, 4).is_err());
+    /// ```
+    pub fn try_new(wrapped: &'a A, len: usize) -> Result<Self, LengthMismatch>
+        where A: Len,
+    {
+        check_len(wrapped, len)?;
+        Ok(Self::new(wrapped))
+    }
+}
+
+impl<'a, A, B> FilteredCollection<'a, A, B>
+    where &'a A: IntoIterator,
+          A: 'a,
+          B: BitSet,
+{
+
+    /// Create a new `FilteredCollection` exposing the elements selected by
+    /// `bitmap`, which can be any `BitSet`, e.g. a `CompressedBitmap`
+    ///
+    /// # Panics
+    /// Panics if the length of `bitmap` does not match the length of the
+    /// collection.
+    ///
+    /// # Examples
+    /// ```
+    /// # use column::bitmap::{Bitmap, FilteredCollection};
+    /// let collection = vec![1, 2, 3];
+    /// let bitmap: Bitmap = collection.iter().map(|&e| e != 2).collect();
+    /// let filtered = FilteredCollection::from_bitmap(&collection, bitmap);
+    /// assert_eq!(filtered.iter().cloned().collect::<Vec<_>>(), vec![1, 3]);
+    /// ```
+    pub fn from_bitmap(wrapped: &'a A, bitmap: B) -> Self
+        where A: Len,
+    {
+        match Self::try_from_bitmap(wrapped, bitmap) {
+            Ok(filtered) => filtered,
+            Err(error) => panic!("Bitmap {}", error),
+        }
+    }
+
+    /// Create a new `FilteredCollection` exposing the elements selected by
+    /// `bitmap`, or return an error if the length of `bitmap` does not match
+    /// the length of the collection
+    ///
+    /// # Examples
+    /// ```
+    /// # use column::bitmap::{Bitmap, FilteredCollection};
+    /// let collection = vec![1, 2, 3];
+    /// assert!(FilteredCollection::try_from_bitmap(&collection, Bitmap::ones(3)).is_ok());
+    /// assert!(FilteredCollection::try_from_bitmap(&collection, Bitmap::ones(2)).is_err());
+    /// ```
+    pub fn try_from_bitmap(wrapped: &'a A, bitmap: B) -> Result<Self, LengthMismatch>
+        where A: Len,
+    {
+        check_len(wrapped, bitmap.len())?;
+        Ok(Self {
             wrapped,
-            bitmap: vec![true; len],
-            valid_items: len,
+            bitmap,
+        })
+    }
+
+    /// A `FilteredCollection` on the same collection with a different bitmap
+    /// of the same length
+    fn with_bitmap(&self, bitmap: B) -> Self {
+        Self {
+            wrapped: self.wrapped,
+            bitmap,
         }
     }
 
@@ -71,36 +1363,78 @@ impl<'a, A> FilteredCollection<'a, A>
     /// ```
     /// use column::bitmap::FilteredCollection;
     /// let collection : Vec<u64> = vec![1, 2, 3];
-    /// let mut filtered = FilteredCollection::new(&collection, collection.len());
+    /// let mut filtered = FilteredCollection::new(&collection);
     /// filtered.retain(|&e| *e < 3);
     /// assert_eq!(filtered.iter().cloned().collect::<Vec<u64>>(), vec![1, 2]);
     /// ```
-    pub fn iter(&'a self) -> FilteredCollectionIterator<'a, <&'a A as IntoIterator>::IntoIter> {
+    pub fn iter(&'a self) -> FilteredCollectionIterator<'a, <&'a A as IntoIterator>::IntoIter, B> {
         FilteredCollectionIterator {
             iter_wrapped: self.wrapped.into_iter(),
-            iter_bitmap: self.bitmap.iter(),
+            iter_bitmap: self.bitmap.iter_ones(),
+            position: 0,
         }
     }
 
-    pub fn iter_mut(&'a mut self) -> ::std::slice::Iter<'a, ()> {
-        panic!("Not yet implemented")
+    /// The bitmap selecting the visible elements
+    pub fn bitmap(&self) -> &B {
+        &self.bitmap
+    }
+
+    /// The visible element at position `index` among the visible elements,
+    /// or `None` if there are at most `index` visible elements
+    /// # Examples
+    /// ```
+    /// use column::bitmap::FilteredCollection;
+    /// let collection : Vec<u64> = (0..100).collect();
+    /// let mut filtered = FilteredCollection::new(&collection);
+    /// filtered.retain(|&e| *e % 10 == 0);
+    /// assert_eq!(filtered.get(3), Some(&30));
+    /// assert_eq!(filtered.get(10), None);
+    /// // The number of visible elements before the element at 55
+    /// assert_eq!(filtered.bitmap().rank(55), 6);
+    /// ```
+    pub fn get(&'a self, index: usize) -> Option<<&'a A as IntoIterator>::Item> {
+        let position = self.bitmap.select(index)?;
+        self.wrapped.into_iter().nth(position)
+    }
+
+    /// Take the bitmap selecting the visible elements, e.g. to apply it to a
+    /// `FilteredCollectionMut` on the same collection
+    pub fn into_bitmap(self) -> B {
+        self.bitmap
+    }
+
+    /// Copy the visible elements into a new collection
+    /// # Examples
+    /// ```
+    /// use column::bitmap::FilteredCollection;
+    /// let collection : Vec<u64> = vec![1, 2, 3];
+    /// let mut filtered = FilteredCollection::new(&collection);
+    /// filtered.retain(|&e| *e != 2);
+    /// assert_eq!(filtered.materialize(), vec![1, 3]);
+    /// ```
+    pub fn materialize(&self) -> A::Output
+        where A: Materialize,
+    {
+        self.wrapped.materialize(&self.bitmap)
     }
 
     /// The number of visible elements in this `FilteredCollection`
     ///
-    /// Note that the result depends on the `len` parameter passed at construction time
-    /// and might be different than the number of elements in the wrapped collection.
+    /// The bitmap has one bit for each element of the wrapped collection, as
+    /// reported by `Len`, so the result is smaller than the length of the
+    /// wrapped collection if any elements are hidden.
     ///
     /// # Examples
     /// ```
     /// use column::bitmap::FilteredCollection;
     /// let collection : Vec<u64> = vec![1, 2, 3];
-    /// let mut filtered = FilteredCollection::new(&collection, collection.len());
+    /// let mut filtered = FilteredCollection::new(&collection);
     /// filtered.retain(|&e| *e < 3);
     /// assert_eq!(filtered.len(), 2);
     /// ```
     pub fn len(&'a self) -> usize {
-        self.valid_items
+        self.bitmap.count_ones()
     }
 
     /// Test if this `FilteredCollection` has any visibile elements
@@ -108,13 +1442,82 @@ impl<'a, A> FilteredCollection<'a, A>
     /// ```
     /// use column::bitmap::FilteredCollection;
     /// let collection : Vec<u64> = vec![1, 2, 3];
-    /// let mut filtered = FilteredCollection::new(&collection, collection.len());
+    /// let mut filtered = FilteredCollection::new(&collection);
     /// filtered.retain(|_| false);
     /// assert!(filtered.is_empty());
     /// # assert_eq!(filtered.len(), 0);
     /// ```
     pub fn is_empty(&self) -> bool {
-        self.valid_items == 0
+        self.bitmap.iter_ones().next().is_none()
+    }
+
+    /// The elements visible in both this and `other`
+    ///
+    /// # Panics
+    /// Panics if `other` wraps a different collection or its bitmap has a
+    /// different length.
+    ///
+    /// # Examples
+    /// ```
+    /// use column::bitmap::FilteredCollection;
+    /// let collection : Vec<u64> = vec![1, 2, 3, 4];
+    /// let mut small = FilteredCollection::new(&collection);
+    /// small.retain(|&e| *e < 3);
+    /// let mut even = FilteredCollection::new(&collection);
+    /// even.retain(|&e| *e % 2 == 0);
+    /// assert_eq!(small.and(&even).iter().cloned().collect::<Vec<_>>(), vec![2]);
+    /// assert_eq!(small.or(&even).iter().cloned().collect::<Vec<_>>(), vec![1, 2, 4]);
+    /// assert_eq!(small.xor(&even).iter().cloned().collect::<Vec<_>>(), vec![1, 4]);
+    /// assert_eq!(small.and_not(&even).iter().cloned().collect::<Vec<_>>(), vec![1]);
+    /// assert_eq!(small.not().iter().cloned().collect::<Vec<_>>(), vec![3, 4]);
+    /// ```
+    pub fn and(&self, other: &Self) -> Self {
+        self.check_compatible(other);
+        self.with_bitmap(self.bitmap.and(&other.bitmap))
+    }
+
+    /// The elements visible in this or `other`
+    ///
+    /// # Panics
+    /// Panics if `other` wraps a different collection or its bitmap has a
+    /// different length.
+    pub fn or(&self, other: &Self) -> Self {
+        self.check_compatible(other);
+        self.with_bitmap(self.bitmap.or(&other.bitmap))
+    }
+
+    /// The elements visible in exactly one of this and `other`
+    ///
+    /// # Panics
+    /// Panics if `other` wraps a different collection or its bitmap has a
+    /// different length.
+    pub fn xor(&self, other: &Self) -> Self {
+        self.check_compatible(other);
+        self.with_bitmap(self.bitmap.xor(&other.bitmap))
+    }
+
+    /// The elements visible in this but not in `other`
+    ///
+    /// # Panics
+    /// Panics if `other` wraps a different collection or its bitmap has a
+    /// different length.
+    pub fn and_not(&self, other: &Self) -> Self {
+        self.check_compatible(other);
+        self.with_bitmap(self.bitmap.and_not(&other.bitmap))
+    }
+
+    /// The elements hidden in this `FilteredCollection`, out of all elements
+    /// of the wrapped collection
+    #[allow(clippy::should_implement_trait)]
+    pub fn not(&self) -> Self {
+        self.with_bitmap(self.bitmap.not())
+    }
+
+    /// Assert that `other` filters the same collection with a bitmap of the
+    /// same length
+    fn check_compatible(&self, other: &Self) {
+        assert!(::std::ptr::eq(self.wrapped, other.wrapped), "FilteredCollections wrap different collections");
+        assert_eq!(self.bitmap.len(), other.bitmap.len(), "FilteredCollections of different lengths");
     }
 
     /// Supply a function to select visible elements in this `FilteredCollection`
@@ -122,47 +1525,382 @@ impl<'a, A> FilteredCollection<'a, A>
     /// ```
     /// use column::bitmap::FilteredCollection;
     /// let collection : Vec<u64> = vec![1, 2, 3];
-    /// let mut filtered = FilteredCollection::new(&collection, collection.len());
+    /// let mut filtered = FilteredCollection::new(&collection);
     /// filtered.retain(|&e| *e == 2);
     /// # assert_eq!(filtered.iter().cloned().collect::<Vec<u64>>(), vec![2]);
     /// ```
     pub fn retain<F>(&mut self, mut f: F) 
         where F: FnMut(&<&'a A as IntoIterator>::Item) -> bool,
     {
-        for (valid, item) in self.bitmap.iter_mut().zip(self.wrapped.into_iter()) {
-            if *valid {
-                *valid = f(&item);
-                if !*valid {
-                    self.valid_items -= 1;
-                }
+        for (index, item) in self.wrapped.into_iter().take(self.bitmap.len()).enumerate() {
+            if self.bitmap.get(index) && !f(&item) {
+                self.bitmap.set(index, false);
+            }
+        }
+    }
+
+}
+
+#[derive(Debug)]
+/// A collection wrapper that can hide individual elements from mutable
+/// iteration.
+///
+/// # Examples
+/// ```
+/// use column::bitmap::{FilteredCollection, FilteredCollectionMut};
+/// let mut collection : Vec<u64> = vec![1, 2, 3];
+/// let mut filtered = FilteredCollection::new(&collection);
+/// filtered.retain(|&e| *e != 2);
+/// let bitmap = filtered.into_bitmap();
+/// let mut filtered = FilteredCollectionMut::from_bitmap(&mut collection, bitmap);
+/// for e in filtered.iter_mut() {
+///     *e *= 10;
+/// }
+/// assert_eq!(collection, vec![10, 2, 30]);
+/// ```
+pub struct FilteredCollectionMut<'a, A, B = Bitmap>
+    where A: 'a,
+          B: BitSet,
+{
+    /// The wrapped collection
+    wrapped: &'a mut A,
+    /// A bitmap indicting item visibility
+    bitmap: B,
+}
+
+impl<'a, A> FilteredCollectionMut<'a, A>
+    where A: 'a,
+{
+
+    /// Create a new `FilteredCollectionMut` exposing all elements of the
+    /// collection, see `FilteredCollection::new()`
+    ///
+    /// # Examples
+    /// ```
+    /// # use column::bitmap::FilteredCollectionMut;
+    /// let mut collection = vec![1, 2, 3];
+    /// let mut filtered = FilteredCollectionMut::new(&mut collection);
+    /// ```
+    pub fn new(wrapped: &'a mut A) -> Self
+        where A: Len,
+    {
+        let len = wrapped.len();
+        Self::from_bitmap(wrapped, Bitmap::ones(len))
+    }
+
+    /// Create a new `FilteredCollectionMut` exposing all `len` elements of
+    /// the collection, see `FilteredCollection::try_new()`
+    pub fn try_new(wrapped: &'a mut A, len: usize) -> Result<Self, LengthMismatch>
+        where A: Len,
+    {
+        check_len(wrapped, len)?;
+        Ok(Self::new(wrapped))
+    }
+}
+
+impl<'a, A, B> FilteredCollectionMut<'a, A, B>
+    where A: 'a,
+          B: BitSet,
+{
+
+    /// Create a new `FilteredCollectionMut` exposing the elements selected by
+    /// `bitmap`, see `FilteredCollection::from_bitmap()`
+    ///
+    /// # Panics
+    /// Panics if the length of `bitmap` does not match the length of the
+    /// collection.
+    pub fn from_bitmap(wrapped: &'a mut A, bitmap: B) -> Self
+        where A: Len,
+    {
+        match Self::try_from_bitmap(wrapped, bitmap) {
+            Ok(filtered) => filtered,
+            Err(error) => panic!("Bitmap {}", error),
+        }
+    }
+
+    /// Create a new `FilteredCollectionMut` exposing the elements selected by
+    /// `bitmap`, see `FilteredCollection::try_from_bitmap()`
+    pub fn try_from_bitmap(wrapped: &'a mut A, bitmap: B) -> Result<Self, LengthMismatch>
+        where A: Len,
+    {
+        check_len(wrapped, bitmap.len())?;
+        Ok(Self {
+            wrapped,
+            bitmap,
+        })
+    }
+
+    /// Obtain a mutable iterator on the visible elements in this
+    /// `FilteredCollectionMut`
+    /// # Examples
+    /// ```
+    /// use column::bitmap::FilteredCollectionMut;
+    /// let mut collection : Vec<u64> = vec![1, 2, 3];
+    /// let mut filtered = FilteredCollectionMut::new(&mut collection);
+    /// filtered.retain(|e| **e > 1);
+    /// for e in filtered.iter_mut() {
+    ///     *e = 0;
+    /// }
+    /// assert_eq!(collection, vec![1, 0, 0]);
+    /// ```
+    pub fn iter_mut<'b>(&'b mut self) -> FilteredCollectionIterator<'b, <&'b mut A as IntoIterator>::IntoIter, B>
+        where &'b mut A: IntoIterator,
+    {
+        FilteredCollectionIterator {
+            iter_wrapped: (&mut *self.wrapped).into_iter(),
+            iter_bitmap: self.bitmap.iter_ones(),
+            position: 0,
+        }
+    }
+
+    /// The bitmap selecting the visible elements
+    pub fn bitmap(&self) -> &B {
+        &self.bitmap
+    }
+
+    /// Take the bitmap selecting the visible elements
+    pub fn into_bitmap(self) -> B {
+        self.bitmap
+    }
+
+    /// Copy the visible elements into a new collection
+    pub fn materialize(&self) -> A::Output
+        where A: Materialize,
+    {
+        self.wrapped.materialize(&self.bitmap)
+    }
+
+    /// The number of visible elements in this `FilteredCollectionMut`
+    pub fn len(&self) -> usize {
+        self.bitmap.count_ones()
+    }
+
+    /// Test if this `FilteredCollectionMut` has any visibile elements
+    pub fn is_empty(&self) -> bool {
+        self.bitmap.iter_ones().next().is_none()
+    }
+
+    /// Supply a function to select visible elements in this
+    /// `FilteredCollectionMut`
+    pub fn retain<'b, F>(&'b mut self, mut f: F)
+        where &'b mut A: IntoIterator,
+              F: FnMut(&<&'b mut A as IntoIterator>::Item) -> bool,
+    {
+        let len = self.bitmap.len();
+        for (index, item) in (&mut *self.wrapped).into_iter().take(len).enumerate() {
+            if self.bitmap.get(index) && !f(&item) {
+                self.bitmap.set(index, false);
+            }
+        }
+    }
+}
+
+impl<'b, 'a, A, B> IntoIterator for &'b mut FilteredCollectionMut<'a, A, B>
+    where &'b mut A: IntoIterator,
+          A: 'a,
+          B: BitSet,
+{
+    type Item = <&'b mut A as IntoIterator>::Item;
+    type IntoIter = FilteredCollectionIterator<'b, <&'b mut A as IntoIterator>::IntoIter, B>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+#[derive(Debug)]
+/// A `FilteredCollection` that owns the wrapped collection `A`, or holds a
+/// shared handle `W` to it, such as `Rc<A>` or `Arc<A>`. It is not tied to
+/// the lifetime of a borrow, so it can be stored in structs and, given a
+/// `Send` handle, be moved to other threads.
+///
+/// # Examples
+/// ```
+/// use column::bitmap::{ArcFilteredCollection, OwnedFilteredCollection};
+/// use std::sync::Arc;
+/// let mut filtered: OwnedFilteredCollection<Vec<u64>> = OwnedFilteredCollection::new(vec![1, 2, 3]);
+/// filtered.retain(|&e| *e > 1);
+/// assert_eq!(filtered.iter().cloned().collect::<Vec<u64>>(), vec![2, 3]);
+///
+/// let shared = ArcFilteredCollection::new(Arc::new(vec![1, 2, 3]));
+/// let sum = std::thread::spawn(move || shared.iter().sum::<u64>()).join().unwrap();
+/// assert_eq!(sum, 6);
+/// ```
+pub struct OwnedFilteredCollection<A, W = A, B = Bitmap>
+    where W: Borrow<A>,
+          B: BitSet,
+{
+    /// The wrapped collection
+    wrapped: W,
+    /// A bitmap indicting item visibility
+    bitmap: B,
+    _marker: PhantomData<A>,
+}
+
+/// A `FilteredCollection` sharing the wrapped collection through an `Rc`
+pub type RcFilteredCollection<A, B = Bitmap> = OwnedFilteredCollection<A, Rc<A>, B>;
+
+/// A `FilteredCollection` sharing the wrapped collection through an `Arc`
+pub type ArcFilteredCollection<A, B = Bitmap> = OwnedFilteredCollection<A, Arc<A>, B>;
+
+impl<A, W> OwnedFilteredCollection<A, W>
+    where W: Borrow<A>,
+{
+
+    /// Create a new `OwnedFilteredCollection` exposing all elements of the
+    /// collection, see `FilteredCollection::new()`
+    pub fn new(wrapped: W) -> Self
+        where A: Len,
+    {
+        let len = wrapped.borrow().len();
+        Self::from_bitmap(wrapped, Bitmap::ones(len))
+    }
+
+    /// Create a new `OwnedFilteredCollection` exposing all `len` elements of
+    /// the collection, see `FilteredCollection::try_new()`
+    pub fn try_new(wrapped: W, len: usize) -> Result<Self, LengthMismatch>
+        where A: Len,
+    {
+        check_len(wrapped.borrow(), len)?;
+        Ok(Self::new(wrapped))
+    }
+}
+
+impl<A, W, B> OwnedFilteredCollection<A, W, B>
+    where W: Borrow<A>,
+          B: BitSet,
+{
+
+    /// Create a new `OwnedFilteredCollection` exposing the elements selected
+    /// by `bitmap`, see `FilteredCollection::from_bitmap()`
+    ///
+    /// # Panics
+    /// Panics if the length of `bitmap` does not match the length of the
+    /// collection.
+    pub fn from_bitmap(wrapped: W, bitmap: B) -> Self
+        where A: Len,
+    {
+        match Self::try_from_bitmap(wrapped, bitmap) {
+            Ok(filtered) => filtered,
+            Err(error) => panic!("Bitmap {}", error),
+        }
+    }
+
+    /// Create a new `OwnedFilteredCollection` exposing the elements selected
+    /// by `bitmap`, see `FilteredCollection::try_from_bitmap()`
+    pub fn try_from_bitmap(wrapped: W, bitmap: B) -> Result<Self, LengthMismatch>
+        where A: Len,
+    {
+        check_len(wrapped.borrow(), bitmap.len())?;
+        Ok(Self {
+            wrapped,
+            bitmap,
+            _marker: PhantomData,
+        })
+    }
+
+    /// Obtain an iterator on the visible elements in this
+    /// `OwnedFilteredCollection`
+    pub fn iter<'b>(&'b self) -> FilteredCollectionIterator<'b, <&'b A as IntoIterator>::IntoIter, B>
+        where &'b A: IntoIterator,
+    {
+        FilteredCollectionIterator {
+            iter_wrapped: self.wrapped.borrow().into_iter(),
+            iter_bitmap: self.bitmap.iter_ones(),
+            position: 0,
+        }
+    }
+
+    /// The visible element at position `index` among the visible elements,
+    /// or `None` if there are at most `index` visible elements
+    pub fn get<'b>(&'b self, index: usize) -> Option<<&'b A as IntoIterator>::Item>
+        where &'b A: IntoIterator,
+    {
+        let position = self.bitmap.select(index)?;
+        self.wrapped.borrow().into_iter().nth(position)
+    }
+
+    /// The wrapped collection
+    pub fn wrapped(&self) -> &A {
+        self.wrapped.borrow()
+    }
+
+    /// Take the wrapped collection or handle
+    pub fn into_inner(self) -> W {
+        self.wrapped
+    }
+
+    /// The bitmap selecting the visible elements
+    pub fn bitmap(&self) -> &B {
+        &self.bitmap
+    }
+
+    /// Take the bitmap selecting the visible elements
+    pub fn into_bitmap(self) -> B {
+        self.bitmap
+    }
+
+    /// Copy the visible elements into a new collection
+    pub fn materialize(&self) -> A::Output
+        where A: Materialize,
+    {
+        self.wrapped.borrow().materialize(&self.bitmap)
+    }
+
+    /// The number of visible elements in this `OwnedFilteredCollection`
+    pub fn len(&self) -> usize {
+        self.bitmap.count_ones()
+    }
+
+    /// Test if this `OwnedFilteredCollection` has any visibile elements
+    pub fn is_empty(&self) -> bool {
+        self.bitmap.iter_ones().next().is_none()
+    }
+
+    /// Supply a function to select visible elements in this
+    /// `OwnedFilteredCollection`
+    pub fn retain<'b, F>(&'b mut self, mut f: F)
+        where &'b A: IntoIterator,
+              F: FnMut(&<&'b A as IntoIterator>::Item) -> bool,
+    {
+        let len = self.bitmap.len();
+        for (index, item) in self.wrapped.borrow().into_iter().take(len).enumerate() {
+            if self.bitmap.get(index) && !f(&item) {
+                self.bitmap.set(index, false);
             }
         }
     }
+}
 
+impl<A, W, B> Clone for OwnedFilteredCollection<A, W, B>
+    where W: Borrow<A> + Clone,
+          B: BitSet + Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            wrapped: self.wrapped.clone(),
+            bitmap: self.bitmap.clone(),
+            _marker: PhantomData,
+        }
+    }
 }
 
-impl<'a, I> Iterator for FilteredCollectionIterator<'a, I>
+impl<'a, I, B> Iterator for FilteredCollectionIterator<'a, I, B>
     where I: Iterator,
+          B: BitSet + 'a,
 {
     type Item = I::Item;
     fn next(&mut self) -> Option<Self::Item> {
-        let mut valid = self.iter_bitmap.next();
-        let mut item = self.iter_wrapped.next();
-        while item.is_some() {
-            if let Some(valid) = valid {
-                if *valid {
-                    return Some(item.expect("is_some wrong!"));
-                }
-            } else {
-                // TODO: panicing here is bad because it might happen much later
-                // than when the problem really happened, i.e. when creating
-                // a FilteredCollection.
-                // panic!("Bitmap iterator ended prematurely!");
-                return None;
-            }
-            valid = self.iter_bitmap.next();
-            item = self.iter_wrapped.next();
-        }
-        None
+        // Skip hidden elements at once, which is cheap for slice iterators
+        let index = self.iter_bitmap.next()?;
+        let item = self.iter_wrapped.nth(index - self.position);
+        self.position = index + 1;
+        item
+    }
+
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        let index = self.iter_bitmap.nth(n)?;
+        let item = self.iter_wrapped.nth(index - self.position);
+        self.position = index + 1;
+        item
     }
 }
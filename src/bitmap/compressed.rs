@@ -0,0 +1,877 @@
+// Copyright 2017 columnar-rs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+//! A compressed bitmap splitting the bits into chunks of 64K bits, each
+//! stored in the most compact of three container kinds.
+
+use std::iter::FromIterator;
+use std::ops::{BitAnd, BitOr, BitXor, Not};
+use std::slice;
+
+use super::{BitSet, Bitmap, WORD_BITS};
+
+/// The number of bits covered by a container
+const CHUNK_BITS: usize = 1 << 16;
+
+/// The number of words of a bitset container
+const CHUNK_WORDS: usize = CHUNK_BITS / WORD_BITS;
+
+/// The maximum number of entries of an array container
+const ARRAY_MAX: usize = 4096;
+
+/// The bits of a chunk of 64K bits
+#[derive(Clone, Debug)]
+enum Container {
+    /// The sorted positions of the set bits
+    Array(Vec<u16>),
+    /// The bits packed into `CHUNK_WORDS` words and the number of set bits
+    Bitset(Vec<u64>, usize),
+    /// Sorted, disjoint and non-adjacent runs of set bits, given by their
+    /// first and last position
+    Run(Vec<(u16, u16)>),
+}
+
+impl Container {
+
+    /// Pick the smallest container for the bits in `words`, or `None` if no
+    /// bit is set
+    fn from_words(words: Vec<u64>) -> Option<Self> {
+        let count = words.iter().map(|w| w.count_ones() as usize).sum::<usize>();
+        if count == 0 {
+            return None;
+        }
+        // A run starts at every set bit whose predecessor is unset
+        let mut runs = 0;
+        let mut carry = 0;
+        for &word in &words {
+            runs += (word & !((word << 1) | carry)).count_ones() as usize;
+            carry = word >> (WORD_BITS - 1);
+        }
+        // Sizes in bytes of the three representations
+        let array_size = if count <= ARRAY_MAX { 2 * count } else { usize::MAX };
+        let bitset_size = 8 * CHUNK_WORDS;
+        let run_size = 4 * runs;
+        let container = if run_size < array_size.min(bitset_size) {
+            let mut ranges: Vec<(u16, u16)> = Vec::with_capacity(runs);
+            for position in Self::ones_of_words(&words) {
+                match ranges.last_mut() {
+                    Some(&mut (_, ref mut last)) if *last as u32 + 1 == position as u32 => *last = position,
+                    _ => ranges.push((position, position)),
+                }
+            }
+            Container::Run(ranges)
+        } else if array_size < bitset_size {
+            Container::Array(Self::ones_of_words(&words).collect())
+        } else {
+            Container::Bitset(words, count)
+        };
+        Some(container)
+    }
+
+    /// The positions of the set bits in `words`
+    fn ones_of_words(words: &[u64]) -> WordOnes<'_> {
+        WordOnes {
+            words: words.iter(),
+            base: 0,
+            current: 0,
+        }
+    }
+
+    /// The bits of this container packed into `CHUNK_WORDS` words
+    fn to_words(&self) -> Vec<u64> {
+        match *self {
+            Container::Bitset(ref words, _) => words.clone(),
+            _ => {
+                let mut words = vec![0; CHUNK_WORDS];
+                for position in self.iter() {
+                    words[position as usize / WORD_BITS] |= 1 << (position as usize % WORD_BITS);
+                }
+                words
+            },
+        }
+    }
+
+    /// The number of set bits
+    fn count_ones(&self) -> usize {
+        match *self {
+            Container::Array(ref positions) => positions.len(),
+            Container::Bitset(_, count) => count,
+            Container::Run(ref runs) => runs.iter().map(|&(first, last)| (last - first) as usize + 1).sum(),
+        }
+    }
+
+    /// Test if no bit is set
+    fn is_empty(&self) -> bool {
+        match *self {
+            Container::Array(ref positions) => positions.is_empty(),
+            Container::Bitset(_, count) => count == 0,
+            Container::Run(ref runs) => runs.is_empty(),
+        }
+    }
+
+    /// Test if the bit at `position` is set
+    fn get(&self, position: u16) -> bool {
+        match *self {
+            Container::Array(ref positions) => positions.binary_search(&position).is_ok(),
+            Container::Bitset(ref words, _) => words[position as usize / WORD_BITS] >> (position as usize % WORD_BITS) & 1 == 1,
+            Container::Run(ref runs) => {
+                let index = runs.partition_point(|&(_, last)| last < position);
+                index < runs.len() && runs[index].0 <= position
+            },
+        }
+    }
+
+    /// Set the bit at `position`, converting to a bitset if an array grows
+    /// too large and to a smaller container if runs get fragmented
+    fn insert(&mut self, position: u16) {
+        match *self {
+            Container::Array(ref mut positions) => {
+                if let Err(index) = positions.binary_search(&position) {
+                    positions.insert(index, position);
+                }
+            },
+            Container::Bitset(ref mut words, ref mut count) => {
+                let word = &mut words[position as usize / WORD_BITS];
+                let mask = 1 << (position as usize % WORD_BITS);
+                if *word & mask == 0 {
+                    *word |= mask;
+                    *count += 1;
+                }
+            },
+            Container::Run(ref mut runs) => {
+                let index = runs.partition_point(|&(_, last)| last < position);
+                if index < runs.len() && runs[index].0 <= position {
+                    return;
+                }
+                let joins_previous = index > 0 && runs[index - 1].1 as u32 + 1 == position as u32;
+                let joins_next = index < runs.len() && position as u32 + 1 == runs[index].0 as u32;
+                match (joins_previous, joins_next) {
+                    (true, true) => {
+                        runs[index - 1].1 = runs[index].1;
+                        runs.remove(index);
+                    },
+                    (true, false) => runs[index - 1].1 = position,
+                    (false, true) => runs[index].0 = position,
+                    (false, false) => runs.insert(index, (position, position)),
+                }
+            },
+        }
+        self.normalize();
+    }
+
+    /// Unset the bit at `position`, returning `false` if the container
+    /// became empty
+    fn remove(&mut self, position: u16) -> bool {
+        match *self {
+            Container::Array(ref mut positions) => {
+                if let Ok(index) = positions.binary_search(&position) {
+                    positions.remove(index);
+                }
+            },
+            Container::Bitset(ref mut words, ref mut count) => {
+                let word = &mut words[position as usize / WORD_BITS];
+                let mask = 1 << (position as usize % WORD_BITS);
+                if *word & mask != 0 {
+                    *word &= !mask;
+                    *count -= 1;
+                }
+            },
+            Container::Run(ref mut runs) => {
+                let index = runs.partition_point(|&(_, last)| last < position);
+                if index == runs.len() || runs[index].0 > position {
+                    return true;
+                }
+                let (first, last) = runs[index];
+                if first == last {
+                    runs.remove(index);
+                } else if position == first {
+                    runs[index].0 += 1;
+                } else if position == last {
+                    runs[index].1 -= 1;
+                } else {
+                    runs[index].1 = position - 1;
+                    runs.insert(index + 1, (position + 1, last));
+                }
+            },
+        }
+        self.normalize();
+        !self.is_empty()
+    }
+
+    /// Convert containers that outgrew their representation
+    fn normalize(&mut self) {
+        let convert = match *self {
+            Container::Array(ref positions) => positions.len() > ARRAY_MAX,
+            Container::Bitset(_, count) => count <= ARRAY_MAX,
+            Container::Run(ref runs) => 4 * runs.len() > 8 * CHUNK_WORDS,
+        };
+        if convert {
+            if let Some(container) = Container::from_words(self.to_words()) {
+                *self = container;
+            }
+        }
+    }
+
+    /// The number of set bits before `position`
+    fn rank(&self, position: u16) -> usize {
+        match *self {
+            Container::Array(ref positions) => positions.partition_point(|&p| p < position),
+            Container::Bitset(ref words, _) => {
+                let word = position as usize / WORD_BITS;
+                let bit = position as usize % WORD_BITS;
+                words[..word].iter().map(|w| w.count_ones() as usize).sum::<usize>()
+                    + (words[word] & ((1 << bit) - 1)).count_ones() as usize
+            },
+            Container::Run(ref runs) => runs.iter()
+                .take_while(|&&(first, _)| first < position)
+                .map(|&(first, last)| (last.min(position - 1) - first) as usize + 1)
+                .sum(),
+        }
+    }
+
+    /// The position of the set bit preceded by `rank` set bits, which must
+    /// be less than `count_ones()`
+    fn select(&self, mut rank: usize) -> u16 {
+        match *self {
+            Container::Array(ref positions) => positions[rank],
+            Container::Bitset(ref words, _) => {
+                for (index, &word) in words.iter().enumerate() {
+                    let ones = word.count_ones() as usize;
+                    if rank < ones {
+                        return (index * WORD_BITS + super::select_in_word(word, rank)) as u16;
+                    }
+                    rank -= ones;
+                }
+                unreachable!("rank exceeds the number of set bits")
+            },
+            Container::Run(ref runs) => {
+                for &(first, last) in runs {
+                    let ones = (last - first) as usize + 1;
+                    if rank < ones {
+                        return first + rank as u16;
+                    }
+                    rank -= ones;
+                }
+                unreachable!("rank exceeds the number of set bits")
+            },
+        }
+    }
+
+    /// The position of the last set bit
+    fn last(&self) -> u16 {
+        match *self {
+            Container::Array(ref positions) => positions[positions.len() - 1],
+            Container::Bitset(ref words, _) => {
+                let index = words.iter().rposition(|&w| w != 0).expect("bitset containers are not empty");
+                (index * WORD_BITS + WORD_BITS - 1 - words[index].leading_zeros() as usize) as u16
+            },
+            Container::Run(ref runs) => runs[runs.len() - 1].1,
+        }
+    }
+
+    /// Iterate the positions of the set bits in ascending order
+    fn iter(&self) -> ContainerOnes<'_> {
+        match *self {
+            Container::Array(ref positions) => ContainerOnes::Array(positions.iter()),
+            Container::Bitset(ref words, _) => ContainerOnes::Bitset(Self::ones_of_words(words)),
+            Container::Run(ref runs) => ContainerOnes::Run(runs.iter(), 1, 0),
+        }
+    }
+
+    /// Combine two containers word by word
+    fn combine<F>(&self, other: &Self, op: F) -> Option<Self>
+        where F: Fn(u64, u64) -> u64,
+    {
+        match (self, other) {
+            (Container::Array(left), Container::Array(right)) => {
+                // Merge the positions, which avoids expanding sparse chunks
+                let mut positions = Vec::new();
+                let (mut i, mut j) = (0, 0);
+                while i < left.len() || j < right.len() {
+                    let position = match (left.get(i), right.get(j)) {
+                        (Some(&l), Some(&r)) => l.min(r),
+                        (Some(&l), None) => l,
+                        (None, Some(&r)) => r,
+                        (None, None) => unreachable!(),
+                    };
+                    let in_left = left.get(i) == Some(&position);
+                    let in_right = right.get(j) == Some(&position);
+                    i += in_left as usize;
+                    j += in_right as usize;
+                    if op(in_left as u64, in_right as u64) & 1 == 1 {
+                        positions.push(position);
+                    }
+                }
+                if positions.is_empty() {
+                    return None;
+                }
+                let mut container = Container::Array(positions);
+                container.normalize();
+                Some(container)
+            },
+            _ => {
+                let right = other.to_words();
+                let mut words = self.to_words();
+                for (left, right) in words.iter_mut().zip(right) {
+                    *left = op(*left, right);
+                }
+                Container::from_words(words)
+            },
+        }
+    }
+
+    /// The complement of this container within the first `bits` positions
+    fn complement(&self, bits: usize) -> Option<Self> {
+        let mut words = self.to_words();
+        for (index, word) in words.iter_mut().enumerate() {
+            let valid = bits.saturating_sub(index * WORD_BITS).min(WORD_BITS);
+            let mask = if valid == WORD_BITS { !0 } else { (1 << valid) - 1 };
+            *word = !*word & mask;
+        }
+        Container::from_words(words)
+    }
+
+    /// A container with the first `bits` positions set
+    fn full(bits: usize) -> Self {
+        Container::Run(vec![(0, (bits - 1) as u16)])
+    }
+}
+
+impl PartialEq for Container {
+    fn eq(&self, other: &Self) -> bool {
+        self.count_ones() == other.count_ones() && self.iter().eq(other.iter())
+    }
+}
+
+impl Eq for Container {}
+
+/// An iterator over the positions of the set bits in a slice of words
+#[derive(Clone, Debug)]
+struct WordOnes<'a> {
+    words: slice::Iter<'a, u64>,
+    base: usize,
+    current: u64,
+}
+
+impl<'a> Iterator for WordOnes<'a> {
+    type Item = u16;
+    fn next(&mut self) -> Option<u16> {
+        while self.current == 0 {
+            self.current = *self.words.next()?;
+            self.base += WORD_BITS;
+        }
+        let bit = self.current.trailing_zeros() as usize;
+        self.current &= self.current - 1;
+        Some((self.base - WORD_BITS + bit) as u16)
+    }
+}
+
+/// An iterator over the positions of the set bits of a container
+#[derive(Clone, Debug)]
+enum ContainerOnes<'a> {
+    Array(slice::Iter<'a, u16>),
+    Bitset(WordOnes<'a>),
+    /// The remaining runs and the next and last position of the current run,
+    /// which is exhausted if the next position exceeds the last
+    Run(slice::Iter<'a, (u16, u16)>, u32, u32),
+}
+
+impl<'a> Iterator for ContainerOnes<'a> {
+    type Item = u16;
+    fn next(&mut self) -> Option<u16> {
+        match *self {
+            ContainerOnes::Array(ref mut positions) => positions.next().cloned(),
+            ContainerOnes::Bitset(ref mut ones) => ones.next(),
+            ContainerOnes::Run(ref mut runs, ref mut next, ref mut last) => {
+                if *next > *last {
+                    let &(first, end) = runs.next()?;
+                    *next = first as u32;
+                    *last = end as u32;
+                }
+                *next += 1;
+                Some((*next - 1) as u16)
+            },
+        }
+    }
+}
+
+/// A fixed set of bits split into chunks of 64K bits, each stored as a sorted
+/// array of positions, a bitset or a list of runs, whichever is smallest
+///
+/// Chunks without set bits take no space, and dense filters compress into a
+/// few runs, so `CompressedBitmap` suits very long collections with very
+/// sparse or very dense filters. It provides the same iteration, set algebra
+/// and positional queries as `Bitmap` and can be used in its place in any
+/// `FilteredCollection`.
+///
+/// # Examples
+/// ```
+/// use column::bitmap::{CompressedBitmap, FilteredCollection};
+/// let collection: Vec<_> = (0..200_000).collect();
+/// let mut bitmap = CompressedBitmap::zeros(collection.len());
+/// bitmap.set(7, true);
+/// bitmap.set(150_000, true);
+/// let filtered = FilteredCollection::from_bitmap(&collection, bitmap);
+/// assert_eq!(filtered.iter().collect::<Vec<_>>(), vec![&7, &150_000]);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CompressedBitmap {
+    /// The non-empty containers, sorted by the index of their chunk
+    containers: Vec<(usize, Container)>,
+    /// The number of bits
+    len: usize,
+}
+
+impl CompressedBitmap {
+
+    /// Create a new `CompressedBitmap` of `len` bits, all of which are unset
+    pub fn zeros(len: usize) -> Self {
+        Self {
+            containers: Vec::new(),
+            len,
+        }
+    }
+
+    /// Create a new `CompressedBitmap` of `len` bits, all of which are set
+    pub fn ones(len: usize) -> Self {
+        let containers = (0..len.div_ceil(CHUNK_BITS))
+            .map(|chunk| (chunk, Container::full(Self::chunk_bits(len, chunk))))
+            .collect();
+        Self {
+            containers,
+            len,
+        }
+    }
+
+    /// The number of bits
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Test if there are no bits
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Read the bit at `index`
+    ///
+    /// # Panics
+    /// Panics if `index` is out of bounds.
+    pub fn get(&self, index: usize) -> bool {
+        assert!(index < self.len, "index out of bounds: the len is {} but the index is {}", self.len, index);
+        match self.find(index / CHUNK_BITS) {
+            Ok(position) => self.containers[position].1.get((index % CHUNK_BITS) as u16),
+            Err(_) => false,
+        }
+    }
+
+    /// Set the bit at `index` to `value`
+    ///
+    /// # Panics
+    /// Panics if `index` is out of bounds.
+    pub fn set(&mut self, index: usize, value: bool) {
+        assert!(index < self.len, "index out of bounds: the len is {} but the index is {}", self.len, index);
+        let low = (index % CHUNK_BITS) as u16;
+        match (self.find(index / CHUNK_BITS), value) {
+            (Ok(position), true) => self.containers[position].1.insert(low),
+            (Ok(position), false) => {
+                if !self.containers[position].1.remove(low) {
+                    self.containers.remove(position);
+                }
+            },
+            (Err(position), true) => self.containers.insert(position, (index / CHUNK_BITS, Container::Array(vec![low]))),
+            (Err(_), false) => {},
+        }
+    }
+
+    /// Append a bit
+    pub fn push(&mut self, value: bool) {
+        self.len += 1;
+        if value {
+            let index = self.len - 1;
+            self.set(index, true);
+        }
+    }
+
+    /// The number of set bits
+    pub fn count_ones(&self) -> usize {
+        self.containers.iter().map(|(_, container)| container.count_ones()).sum()
+    }
+
+    /// The number of unset bits
+    pub fn count_zeros(&self) -> usize {
+        self.len - self.count_ones()
+    }
+
+    /// Iterate all bits
+    pub fn iter(&self) -> CompressedIter<'_> {
+        CompressedIter {
+            ones: self.iter_ones().peekable(),
+            index: 0,
+            len: self.len,
+        }
+    }
+
+    /// Iterate the indices of all set bits in ascending order
+    pub fn iter_ones(&self) -> CompressedOnes<'_> {
+        CompressedOnes {
+            containers: self.containers.iter(),
+            current: None,
+        }
+    }
+
+    /// The intersection of two bitmaps of the same length
+    ///
+    /// # Panics
+    /// Panics if the bitmaps have different lengths.
+    pub fn and(&self, other: &Self) -> Self {
+        self.combine(other, false, false, |a, b| a & b)
+    }
+
+    /// The union of two bitmaps of the same length
+    ///
+    /// # Panics
+    /// Panics if the bitmaps have different lengths.
+    pub fn or(&self, other: &Self) -> Self {
+        self.combine(other, true, true, |a, b| a | b)
+    }
+
+    /// The bits set in exactly one of two bitmaps of the same length
+    ///
+    /// # Panics
+    /// Panics if the bitmaps have different lengths.
+    pub fn xor(&self, other: &Self) -> Self {
+        self.combine(other, true, true, |a, b| a ^ b)
+    }
+
+    /// The bits set in this but not in another bitmap of the same length
+    ///
+    /// # Panics
+    /// Panics if the bitmaps have different lengths.
+    pub fn and_not(&self, other: &Self) -> Self {
+        self.combine(other, true, false, |a, b| a & !b)
+    }
+
+    /// The complement of this bitmap
+    #[allow(clippy::should_implement_trait)]
+    pub fn not(&self) -> Self {
+        let mut containers = Vec::new();
+        let mut existing = self.containers.iter().peekable();
+        for chunk in 0..self.len.div_ceil(CHUNK_BITS) {
+            let bits = Self::chunk_bits(self.len, chunk);
+            let complement = match existing.peek().cloned() {
+                Some(&(key, ref container)) if key == chunk => {
+                    existing.next();
+                    container.complement(bits)
+                },
+                _ => Some(Container::full(bits)),
+            };
+            if let Some(container) = complement {
+                containers.push((chunk, container));
+            }
+        }
+        Self {
+            containers,
+            len: self.len,
+        }
+    }
+
+    /// The number of set bits before `index`
+    ///
+    /// # Panics
+    /// Panics if `index` is greater than the length of the bitmap.
+    pub fn rank(&self, index: usize) -> usize {
+        assert!(index <= self.len, "index out of bounds: the len is {} but the index is {}", self.len, index);
+        let chunk = index / CHUNK_BITS;
+        self.containers.iter()
+            .take_while(|&&(key, _)| key <= chunk)
+            .map(|&(key, ref container)| if key < chunk {
+                container.count_ones()
+            } else {
+                container.rank((index % CHUNK_BITS) as u16)
+            })
+            .sum()
+    }
+
+    /// The index of the set bit preceded by `rank` set bits, or `None` if
+    /// there are at most `rank` set bits
+    pub fn select(&self, mut rank: usize) -> Option<usize> {
+        for &(key, ref container) in &self.containers {
+            let ones = container.count_ones();
+            if rank < ones {
+                return Some(key * CHUNK_BITS + container.select(rank) as usize);
+            }
+            rank -= ones;
+        }
+        None
+    }
+
+    /// The index of the first set bit
+    pub fn first_set(&self) -> Option<usize> {
+        self.select(0)
+    }
+
+    /// The index of the last set bit
+    pub fn last_set(&self) -> Option<usize> {
+        self.containers.last().map(|&(key, ref container)| key * CHUNK_BITS + container.last() as usize)
+    }
+
+    /// The number of bits of `chunk` within a bitmap of `len` bits
+    fn chunk_bits(len: usize, chunk: usize) -> usize {
+        (len - chunk * CHUNK_BITS).min(CHUNK_BITS)
+    }
+
+    /// Find the position of the container of `chunk`
+    fn find(&self, chunk: usize) -> Result<usize, usize> {
+        self.containers.binary_search_by_key(&chunk, |&(key, _)| key)
+    }
+
+    /// Combine two bitmaps chunk by chunk, keeping the chunks present only
+    /// on the left or right side if `keep_left` or `keep_right` is set
+    fn combine<F>(&self, other: &Self, keep_left: bool, keep_right: bool, op: F) -> Self
+        where F: Fn(u64, u64) -> u64,
+    {
+        assert_eq!(self.len, other.len, "Bitmaps of different lengths");
+        let mut containers = Vec::new();
+        let mut left = self.containers.iter().peekable();
+        let mut right = other.containers.iter().peekable();
+        loop {
+            let (key, container) = match (left.peek().cloned(), right.peek().cloned()) {
+                (Some(&(l, ref a)), Some(&(r, ref b))) if l == r => {
+                    left.next();
+                    right.next();
+                    (l, a.combine(b, &op))
+                },
+                (Some(&(l, ref a)), Some(&(r, _))) if l < r => {
+                    left.next();
+                    (l, if keep_left { Some(a.clone()) } else { None })
+                },
+                (Some(&(l, ref a)), None) => {
+                    left.next();
+                    (l, if keep_left { Some(a.clone()) } else { None })
+                },
+                (_, Some(&(r, ref b))) => {
+                    right.next();
+                    (r, if keep_right { Some(b.clone()) } else { None })
+                },
+                (None, None) => break,
+            };
+            if let Some(container) = container {
+                containers.push((key, container));
+            }
+        }
+        Self {
+            containers,
+            len: self.len,
+        }
+    }
+}
+
+impl BitSet for CompressedBitmap {
+    type IterOnes<'b> = CompressedOnes<'b>;
+
+    fn ones(len: usize) -> Self {
+        CompressedBitmap::ones(len)
+    }
+
+    fn len(&self) -> usize {
+        CompressedBitmap::len(self)
+    }
+
+    fn get(&self, index: usize) -> bool {
+        CompressedBitmap::get(self, index)
+    }
+
+    fn set(&mut self, index: usize, value: bool) {
+        CompressedBitmap::set(self, index, value)
+    }
+
+    fn count_ones(&self) -> usize {
+        CompressedBitmap::count_ones(self)
+    }
+
+    fn iter_ones(&self) -> CompressedOnes<'_> {
+        CompressedBitmap::iter_ones(self)
+    }
+
+    fn select(&self, rank: usize) -> Option<usize> {
+        CompressedBitmap::select(self, rank)
+    }
+
+    fn and(&self, other: &Self) -> Self {
+        CompressedBitmap::and(self, other)
+    }
+
+    fn or(&self, other: &Self) -> Self {
+        CompressedBitmap::or(self, other)
+    }
+
+    fn xor(&self, other: &Self) -> Self {
+        CompressedBitmap::xor(self, other)
+    }
+
+    fn and_not(&self, other: &Self) -> Self {
+        CompressedBitmap::and_not(self, other)
+    }
+
+    fn not(&self) -> Self {
+        CompressedBitmap::not(self)
+    }
+}
+
+impl BitAnd for &CompressedBitmap {
+    type Output = CompressedBitmap;
+    fn bitand(self, other: Self) -> CompressedBitmap {
+        self.and(other)
+    }
+}
+
+impl BitOr for &CompressedBitmap {
+    type Output = CompressedBitmap;
+    fn bitor(self, other: Self) -> CompressedBitmap {
+        self.or(other)
+    }
+}
+
+impl BitXor for &CompressedBitmap {
+    type Output = CompressedBitmap;
+    fn bitxor(self, other: Self) -> CompressedBitmap {
+        self.xor(other)
+    }
+}
+
+impl Not for &CompressedBitmap {
+    type Output = CompressedBitmap;
+    fn not(self) -> CompressedBitmap {
+        CompressedBitmap::not(self)
+    }
+}
+
+impl FromIterator<bool> for CompressedBitmap {
+    fn from_iter<I: IntoIterator<Item = bool>>(iter: I) -> Self {
+        let mut bitmap = CompressedBitmap::default();
+        for value in iter {
+            bitmap.push(value);
+        }
+        bitmap
+    }
+}
+
+impl<'a> IntoIterator for &'a CompressedBitmap {
+    type Item = bool;
+    type IntoIter = CompressedIter<'a>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a> From<&'a Bitmap> for CompressedBitmap {
+    fn from(bitmap: &'a Bitmap) -> Self {
+        let containers = bitmap.words.chunks(CHUNK_WORDS)
+            .enumerate()
+            .filter_map(|(chunk, words)| {
+                let mut words = words.to_vec();
+                words.resize(CHUNK_WORDS, 0);
+                Container::from_words(words).map(|container| (chunk, container))
+            })
+            .collect();
+        Self {
+            containers,
+            len: bitmap.len,
+        }
+    }
+}
+
+impl<'a> From<&'a CompressedBitmap> for Bitmap {
+    fn from(bitmap: &'a CompressedBitmap) -> Self {
+        let mut words = vec![0; bitmap.len.div_ceil(WORD_BITS)];
+        for &(key, ref container) in &bitmap.containers {
+            let offset = key * CHUNK_WORDS;
+            let end = words.len().min(offset + CHUNK_WORDS);
+            words[offset..end].copy_from_slice(&container.to_words()[..end - offset]);
+        }
+        Bitmap::from_words(words, bitmap.len)
+    }
+}
+
+/// An iterator over the indices of the set bits of a `CompressedBitmap`
+#[derive(Clone, Debug)]
+pub struct CompressedOnes<'a> {
+    /// The remaining containers
+    containers: slice::Iter<'a, (usize, Container)>,
+    /// The first index of the current chunk, the iterator over its bits and
+    /// the number of bits it has left
+    current: Option<(usize, ContainerOnes<'a>, usize)>,
+}
+
+impl<'a> Iterator for CompressedOnes<'a> {
+    type Item = usize;
+    fn next(&mut self) -> Option<usize> {
+        loop {
+            if let Some((base, ref mut ones, ref mut remaining)) = self.current {
+                if let Some(position) = ones.next() {
+                    *remaining -= 1;
+                    return Some(base + position as usize);
+                }
+            }
+            let &(key, ref container) = self.containers.next()?;
+            self.current = Some((key * CHUNK_BITS, container.iter(), container.count_ones()));
+        }
+    }
+
+    fn nth(&mut self, mut n: usize) -> Option<usize> {
+        if let Some((base, ref mut ones, ref mut remaining)) = self.current {
+            if n < *remaining {
+                *remaining -= n + 1;
+                return ones.nth(n).map(|position| base + position as usize);
+            }
+            n -= *remaining;
+        }
+        self.current = None;
+        // Skip whole containers without visiting their bits
+        loop {
+            let &(key, ref container) = self.containers.next()?;
+            let ones = container.count_ones();
+            if n < ones {
+                let mut iter = container.iter();
+                let position = iter.nth(n)?;
+                self.current = Some((key * CHUNK_BITS, iter, ones - n - 1));
+                return Some(key * CHUNK_BITS + position as usize);
+            }
+            n -= ones;
+        }
+    }
+}
+
+/// An iterator over all bits of a `CompressedBitmap`
+#[derive(Clone, Debug)]
+pub struct CompressedIter<'a> {
+    ones: ::std::iter::Peekable<CompressedOnes<'a>>,
+    index: usize,
+    len: usize,
+}
+
+impl<'a> Iterator for CompressedIter<'a> {
+    type Item = bool;
+    fn next(&mut self) -> Option<bool> {
+        if self.index == self.len {
+            return None;
+        }
+        let set = self.ones.peek() == Some(&self.index);
+        if set {
+            self.ones.next();
+        }
+        self.index += 1;
+        Some(set)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.len - self.index;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<'a> ExactSizeIterator for CompressedIter<'a> {}
@@ -0,0 +1,111 @@
+// Copyright 2017 columnar-rs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+//! `Column` implementations for primitive and std types.
+//!
+//! Primitive types are stored in a `Vec`. The other std types have layouts of
+//! their own:
+//!
+//! * `String` and `Vec<T>` are stored in a `Ragged` column, which concatenates
+//!   all elements into one buffer and records where each element ends.
+//! * `Option<T>` and `Result<T, E>` are stored like derived enums with dense
+//!   columns holding the values of each variant, but record the variant of each
+//!   element in a `Bitmap`. The position of a value is the `rank` of its
+//!   element.
+//! * `Box<T>` stores the unboxed values in the column of `T`.
+//! * `[T; N]` stores the values at each position in a separate column of `T`.
+//!
+//! All of them can be used as nested fields of derived columns.
+//!
+//! # Examples
+//! ```
+//! # #[macro_use] extern crate column_derive;
+//! # extern crate column;
+//! use column::Column;
+//! #[derive(Column)]
+//! #[column(no_slices)]
+//! struct Person {
+//!     #[column(nested)]
+//!     name: String,
+//!     #[column(nested)]
+//!     age: Option<u8>,
+//! }
+//! # fn main() {
+//! let mut column = <Person as Column>::new();
+//! column.push(Person { name: "Ada".to_owned(), age: Some(36) });
+//! column.push(Person { name: "Bob".to_owned(), age: None });
+//! let names: Vec<&str> = column.iter().map(|person| person.name).collect();
+//! assert_eq!(names, vec!["Ada", "Bob"]);
+//! # }
+//! ```
+
+use ::{CloneContainer, Column, ColumnContainer};
+use bitmap::BitSet;
+
+mod array;
+mod boxed;
+mod option;
+mod ragged;
+mod result;
+
+pub use self::array::{ArrayColumn, ArrayIter};
+pub use self::boxed::BoxColumn;
+pub use self::option::{OptionColumn, OptionIter};
+pub use self::ragged::{Buffer, Ragged, RaggedIntoIter, RaggedIter, RaggedIterMut, StringColumn, VecColumn};
+pub use self::result::{ResultColumn, ResultIter};
+
+// Implement `Column` for types stored in a `Vec`
+macro_rules! vec_columns {
+    ($($T:ty),*) => {
+        $(
+            impl Column for $T {
+                type Output = Vec<$T>;
+                fn new() -> Vec<$T> {
+                    Vec::new()
+                }
+                fn with_capacity(capacity: usize) -> Vec<$T> {
+                    Vec::with_capacity(capacity)
+                }
+            }
+        )*
+    }
+}
+
+vec_columns!(bool, char, u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64);
+
+/// Copy the elements of `column` selected by `bitmap` into a new column
+fn materialize<C: CloneContainer, S: BitSet>(column: &C, bitmap: &S) -> C {
+    assert_eq!(bitmap.len(), column.len(), "Bitmap length must match the column length");
+    let mut result = C::with_capacity(bitmap.count_ones());
+    for index in bitmap.iter_ones() {
+        result.push(column.index(index));
+    }
+    result
+}
+
+/// Move the element at `from` of a column to `to`, shifting the elements in
+/// between by one position. Takes time linear in the distance.
+fn shift<C: ColumnContainer>(column: &mut C, from: usize, to: usize) {
+    if from < to {
+        for index in from..to {
+            column.swap(index, index + 1);
+        }
+    } else {
+        for index in (to..from).rev() {
+            column.swap(index, index + 1);
+        }
+    }
+}
+
+/// Remove the element at `index` of a column and replace it by the last
+/// element, by swapping both and popping the last element. Takes the time of
+/// `swap`.
+fn swap_remove<C: ColumnContainer>(column: &mut C, index: usize) -> C::Element {
+    let len = column.len();
+    assert!(index < len, "swap_remove index {} out of bounds for length {}", index, len);
+    column.swap(index, len - 1);
+    column.pop().expect("column is not empty")
+}
@@ -0,0 +1,276 @@
+// Copyright 2017 columnar-rs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+//! The column of arrays, storing the values at each position separately.
+
+use std::iter::{FromIterator, FusedIterator};
+
+use ::{CloneContainer, Column, ColumnContainer, Len};
+use bitmap::{BitSet, Materialize};
+
+/// A column of arrays `[T; N]`, storing the values at each position in a
+/// separate column `C`
+///
+/// Elements are referenced as arrays `[C::Ref; N]`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ArrayColumn<C, const N: usize> {
+    /// The values at each position
+    columns: [C; N],
+    /// The number of elements, which is tracked separately for `N = 0`
+    len: usize,
+}
+
+impl<C, const N: usize> ArrayColumn<C, N> {
+
+    /// The columns of the values at each position
+    pub fn columns(&self) -> &[C; N] {
+        &self.columns
+    }
+}
+
+impl<C: ColumnContainer, const N: usize> Default for ArrayColumn<C, N> {
+    fn default() -> Self {
+        ColumnContainer::with_capacity(0)
+    }
+}
+
+impl<C, const N: usize> Len for ArrayColumn<C, N> {
+    fn len(&self) -> usize {
+        self.len
+    }
+}
+
+impl<C: ColumnContainer, const N: usize> ColumnContainer for ArrayColumn<C, N> {
+    type Element = [C::Element; N];
+    type Ref<'a> = [C::Ref<'a>; N] where Self: 'a;
+    type RefMut<'a> = [C::RefMut<'a>; N] where Self: 'a;
+    type Iter<'a> = ArrayIter<C::Iter<'a>, N> where Self: 'a;
+    type IterMut<'a> = ArrayIter<C::IterMut<'a>, N> where Self: 'a;
+
+    fn with_capacity(capacity: usize) -> Self {
+        ArrayColumn { columns: ::std::array::from_fn(|_| C::with_capacity(capacity)), len: 0 }
+    }
+
+    fn capacity(&self) -> usize {
+        self.columns.iter().map(ColumnContainer::capacity).min().unwrap_or(usize::MAX)
+    }
+
+    fn reserve(&mut self, additional: usize) {
+        for column in &mut self.columns {
+            column.reserve(additional);
+        }
+    }
+
+    fn shrink_to_fit(&mut self) {
+        for column in &mut self.columns {
+            column.shrink_to_fit();
+        }
+    }
+
+    fn push(&mut self, element: [C::Element; N]) {
+        for (column, value) in self.columns.iter_mut().zip(element) {
+            column.push(value);
+        }
+        self.len += 1;
+    }
+
+    fn pop(&mut self) -> Option<[C::Element; N]> {
+        if self.len == 0 {
+            return None;
+        }
+        self.len -= 1;
+        Some(self.columns.each_mut().map(|column| column.pop().expect("Columns have the same length")))
+    }
+
+    fn insert(&mut self, index: usize, element: [C::Element; N]) {
+        assert!(index <= self.len, "insertion index {} is out of bounds for length {}", index, self.len);
+        for (column, value) in self.columns.iter_mut().zip(element) {
+            column.insert(index, value);
+        }
+        self.len += 1;
+    }
+
+    fn remove(&mut self, index: usize) -> [C::Element; N] {
+        assert!(index < self.len, "removal index {} is out of bounds for length {}", index, self.len);
+        self.len -= 1;
+        self.columns.each_mut().map(|column| column.remove(index))
+    }
+
+    fn swap_remove(&mut self, index: usize) -> [C::Element; N] {
+        assert!(index < self.len, "swap_remove index {} is out of bounds for length {}", index, self.len);
+        self.len -= 1;
+        self.columns.each_mut().map(|column| column.swap_remove(index))
+    }
+
+    fn swap(&mut self, a: usize, b: usize) {
+        assert!(a < self.len && b < self.len, "swap indices {} and {} out of bounds for length {}", a, b, self.len);
+        for column in &mut self.columns {
+            column.swap(a, b);
+        }
+    }
+
+    fn truncate(&mut self, len: usize) {
+        for column in &mut self.columns {
+            column.truncate(len);
+        }
+        self.len = self.len.min(len);
+    }
+
+    fn split_off(&mut self, at: usize) -> Self {
+        assert!(at <= self.len, "split index {} is out of bounds for length {}", at, self.len);
+        let len = self.len - at;
+        self.len = at;
+        ArrayColumn { columns: self.columns.each_mut().map(|column| column.split_off(at)), len }
+    }
+
+    fn append(&mut self, other: &mut Self) {
+        for (column, other) in self.columns.iter_mut().zip(&mut other.columns) {
+            column.append(other);
+        }
+        self.len += other.len;
+        other.len = 0;
+    }
+
+    fn clear(&mut self) {
+        for column in &mut self.columns {
+            column.clear();
+        }
+        self.len = 0;
+    }
+
+    fn get(&self, index: usize) -> Option<[C::Ref<'_>; N]> {
+        if index >= self.len {
+            return None;
+        }
+        Some(self.columns.each_ref().map(|column| column.get(index).expect("Columns have the same length")))
+    }
+
+    fn index_mut(&mut self, index: usize) -> [C::RefMut<'_>; N] {
+        assert!(index < self.len, "index {} out of bounds for length {}", index, self.len);
+        self.columns.each_mut().map(|column| column.index_mut(index))
+    }
+
+    fn iter(&self) -> ArrayIter<C::Iter<'_>, N> {
+        ArrayIter { iters: self.columns.each_ref().map(ColumnContainer::iter), len: self.len }
+    }
+
+    fn iter_mut(&mut self) -> ArrayIter<C::IterMut<'_>, N> {
+        ArrayIter { iters: self.columns.each_mut().map(ColumnContainer::iter_mut), len: self.len }
+    }
+}
+
+impl<C: CloneContainer, const N: usize> CloneContainer for ArrayColumn<C, N> {
+    fn to_owned(reference: &[C::Ref<'_>; N]) -> [C::Element; N] {
+        reference.each_ref().map(C::to_owned)
+    }
+
+    fn to_owned_mut(reference: &[C::RefMut<'_>; N]) -> [C::Element; N] {
+        reference.each_ref().map(C::to_owned_mut)
+    }
+}
+
+impl<C: CloneContainer, const N: usize> Materialize for ArrayColumn<C, N> {
+    type Output = Self;
+
+    fn materialize<S: BitSet>(&self, bitmap: &S) -> Self {
+        super::materialize(self, bitmap)
+    }
+}
+
+impl<T: Column, const N: usize> Column for [T; N] {
+    type Output = ArrayColumn<T::Output, N>;
+
+    fn new() -> Self::Output {
+        ArrayColumn::default()
+    }
+
+    fn with_capacity(capacity: usize) -> Self::Output {
+        ColumnContainer::with_capacity(capacity)
+    }
+}
+
+impl<C: ColumnContainer, const N: usize> Extend<[C::Element; N]> for ArrayColumn<C, N> {
+    fn extend<I: IntoIterator<Item = [C::Element; N]>>(&mut self, iter: I) {
+        for element in iter {
+            self.push(element);
+        }
+    }
+}
+
+impl<C: ColumnContainer, const N: usize> FromIterator<[C::Element; N]> for ArrayColumn<C, N> {
+    fn from_iter<I: IntoIterator<Item = [C::Element; N]>>(iter: I) -> Self {
+        let mut column = Self::default();
+        column.extend(iter);
+        column
+    }
+}
+
+impl<C: ColumnContainer, const N: usize> IntoIterator for ArrayColumn<C, N> {
+    type Item = [C::Element; N];
+    type IntoIter = ArrayIter<C::IntoIter, N>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        ArrayIter { iters: self.columns.map(IntoIterator::into_iter), len: self.len }
+    }
+}
+
+impl<'a, C: ColumnContainer, const N: usize> IntoIterator for &'a ArrayColumn<C, N> {
+    type Item = [C::Ref<'a>; N];
+    type IntoIter = ArrayIter<C::Iter<'a>, N>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, C: ColumnContainer, const N: usize> IntoIterator for &'a mut ArrayColumn<C, N> {
+    type Item = [C::RefMut<'a>; N];
+    type IntoIter = ArrayIter<C::IterMut<'a>, N>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+/// An iterator over the elements of an `ArrayColumn`, zipping the iterators
+/// over each position
+#[derive(Debug)]
+pub struct ArrayIter<I, const N: usize> {
+    /// The iterators over each position
+    iters: [I; N],
+    /// The number of remaining elements
+    len: usize,
+}
+
+impl<I: Iterator, const N: usize> Iterator for ArrayIter<I, N> {
+    type Item = [I::Item; N];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        self.len -= 1;
+        Some(self.iters.each_mut().map(|iter| iter.next().expect("Columns have the same length")))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<I: DoubleEndedIterator, const N: usize> DoubleEndedIterator for ArrayIter<I, N> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        self.len -= 1;
+        Some(self.iters.each_mut().map(|iter| iter.next_back().expect("Columns have the same length")))
+    }
+}
+
+impl<I: Iterator, const N: usize> ExactSizeIterator for ArrayIter<I, N> {}
+
+impl<I: Iterator, const N: usize> FusedIterator for ArrayIter<I, N> {}
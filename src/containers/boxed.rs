@@ -0,0 +1,200 @@
+// Copyright 2017 columnar-rs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+//! The column of `Box`es, storing the unboxed values.
+
+use std::iter::{FromIterator, Map};
+
+use ::{CloneContainer, Column, ColumnContainer, Len};
+use bitmap::{BitSet, Materialize};
+
+/// A column of `Box`es, storing the unboxed values in a column `C`
+///
+/// Elements are boxed when they are moved out of the column, and referenced
+/// as `C::Ref`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BoxColumn<C> {
+    /// The unboxed values
+    values: C,
+}
+
+impl<C> BoxColumn<C> {
+
+    /// The column of the unboxed values
+    pub fn values(&self) -> &C {
+        &self.values
+    }
+
+    /// Take out the column of the unboxed values
+    pub fn into_values(self) -> C {
+        self.values
+    }
+}
+
+impl<C: ColumnContainer> Default for BoxColumn<C> {
+    fn default() -> Self {
+        ColumnContainer::with_capacity(0)
+    }
+}
+
+impl<C: ColumnContainer> Len for BoxColumn<C> {
+    fn len(&self) -> usize {
+        self.values.len()
+    }
+}
+
+impl<C: ColumnContainer> ColumnContainer for BoxColumn<C> {
+    type Element = Box<C::Element>;
+    type Ref<'a> = C::Ref<'a> where Self: 'a;
+    type RefMut<'a> = C::RefMut<'a> where Self: 'a;
+    type Iter<'a> = C::Iter<'a> where Self: 'a;
+    type IterMut<'a> = C::IterMut<'a> where Self: 'a;
+
+    fn with_capacity(capacity: usize) -> Self {
+        BoxColumn { values: C::with_capacity(capacity) }
+    }
+
+    fn capacity(&self) -> usize {
+        self.values.capacity()
+    }
+
+    fn reserve(&mut self, additional: usize) {
+        self.values.reserve(additional)
+    }
+
+    fn shrink_to_fit(&mut self) {
+        self.values.shrink_to_fit()
+    }
+
+    fn push(&mut self, element: Box<C::Element>) {
+        self.values.push(*element)
+    }
+
+    fn pop(&mut self) -> Option<Box<C::Element>> {
+        self.values.pop().map(Box::new)
+    }
+
+    fn insert(&mut self, index: usize, element: Box<C::Element>) {
+        self.values.insert(index, *element)
+    }
+
+    fn remove(&mut self, index: usize) -> Box<C::Element> {
+        Box::new(self.values.remove(index))
+    }
+
+    fn swap_remove(&mut self, index: usize) -> Box<C::Element> {
+        Box::new(self.values.swap_remove(index))
+    }
+
+    fn swap(&mut self, a: usize, b: usize) {
+        self.values.swap(a, b)
+    }
+
+    fn truncate(&mut self, len: usize) {
+        self.values.truncate(len)
+    }
+
+    fn split_off(&mut self, at: usize) -> Self {
+        BoxColumn { values: self.values.split_off(at) }
+    }
+
+    fn append(&mut self, other: &mut Self) {
+        self.values.append(&mut other.values)
+    }
+
+    fn clear(&mut self) {
+        self.values.clear()
+    }
+
+    fn get(&self, index: usize) -> Option<C::Ref<'_>> {
+        self.values.get(index)
+    }
+
+    fn index_mut(&mut self, index: usize) -> C::RefMut<'_> {
+        self.values.index_mut(index)
+    }
+
+    fn iter(&self) -> C::Iter<'_> {
+        self.values.iter()
+    }
+
+    fn iter_mut(&mut self) -> C::IterMut<'_> {
+        self.values.iter_mut()
+    }
+}
+
+impl<C: CloneContainer> CloneContainer for BoxColumn<C> {
+    fn to_owned(reference: &C::Ref<'_>) -> Box<C::Element> {
+        Box::new(C::to_owned(reference))
+    }
+
+    fn to_owned_mut(reference: &C::RefMut<'_>) -> Box<C::Element> {
+        Box::new(C::to_owned_mut(reference))
+    }
+}
+
+impl<C: CloneContainer> Materialize for BoxColumn<C> {
+    type Output = Self;
+
+    fn materialize<S: BitSet>(&self, bitmap: &S) -> Self {
+        super::materialize(self, bitmap)
+    }
+}
+
+impl<T: Column> Column for Box<T> {
+    type Output = BoxColumn<T::Output>;
+
+    fn new() -> Self::Output {
+        BoxColumn::default()
+    }
+
+    fn with_capacity(capacity: usize) -> Self::Output {
+        ColumnContainer::with_capacity(capacity)
+    }
+}
+
+impl<C: ColumnContainer> Extend<Box<C::Element>> for BoxColumn<C> {
+    fn extend<I: IntoIterator<Item = Box<C::Element>>>(&mut self, iter: I) {
+        for element in iter {
+            self.push(element);
+        }
+    }
+}
+
+impl<C: ColumnContainer> FromIterator<Box<C::Element>> for BoxColumn<C> {
+    fn from_iter<I: IntoIterator<Item = Box<C::Element>>>(iter: I) -> Self {
+        let mut column = Self::default();
+        column.extend(iter);
+        column
+    }
+}
+
+impl<C: ColumnContainer> IntoIterator for BoxColumn<C> {
+    type Item = Box<C::Element>;
+    type IntoIter = Map<C::IntoIter, fn(C::Element) -> Box<C::Element>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.values.into_iter().map(Box::new)
+    }
+}
+
+impl<'a, C: ColumnContainer> IntoIterator for &'a BoxColumn<C> {
+    type Item = C::Ref<'a>;
+    type IntoIter = C::Iter<'a>;
+
+    fn into_iter(self) -> C::Iter<'a> {
+        self.iter()
+    }
+}
+
+impl<'a, C: ColumnContainer> IntoIterator for &'a mut BoxColumn<C> {
+    type Item = C::RefMut<'a>;
+    type IntoIter = C::IterMut<'a>;
+
+    fn into_iter(self) -> C::IterMut<'a> {
+        self.iter_mut()
+    }
+}
@@ -0,0 +1,293 @@
+// Copyright 2017 columnar-rs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+//! The column of `Option`s, storing only the present values.
+
+use std::iter::{FromIterator, FusedIterator};
+
+use ::{CloneContainer, Column, ColumnContainer, Len};
+use bitmap::{self, BitSet, Bitmap, Materialize};
+
+/// A column of `Option`s, storing the present values densely in a column `C`
+///
+/// Whether each element is `Some` is recorded in a `Bitmap`, and the position
+/// of a present value in `values` is the `rank` of its element. Elements are
+/// referenced as `Option<C::Ref>`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OptionColumn<C> {
+    /// Whether each element is `Some`
+    some: Bitmap,
+    /// The present values
+    values: C,
+}
+
+impl<C: ColumnContainer> OptionColumn<C> {
+
+    /// Whether each element is `Some`
+    pub fn some(&self) -> &Bitmap {
+        &self.some
+    }
+
+    /// The column of the present values
+    pub fn values(&self) -> &C {
+        &self.values
+    }
+
+    /// The position in `values` of the element at `index`, if it is `Some`
+    fn position(&self, index: usize) -> Option<usize> {
+        if self.some.get(index) { Some(self.some.rank(index)) } else { None }
+    }
+}
+
+impl<C: ColumnContainer> Default for OptionColumn<C> {
+    fn default() -> Self {
+        ColumnContainer::with_capacity(0)
+    }
+}
+
+impl<C: ColumnContainer> Len for OptionColumn<C> {
+    fn len(&self) -> usize {
+        self.some.len()
+    }
+}
+
+impl<C: ColumnContainer> ColumnContainer for OptionColumn<C> {
+    type Element = Option<C::Element>;
+    type Ref<'a> = Option<C::Ref<'a>> where Self: 'a;
+    type RefMut<'a> = Option<C::RefMut<'a>> where Self: 'a;
+    type Iter<'a> = OptionIter<bitmap::Iter<'a>, C::Iter<'a>> where Self: 'a;
+    type IterMut<'a> = OptionIter<bitmap::Iter<'a>, C::IterMut<'a>> where Self: 'a;
+
+    fn with_capacity(capacity: usize) -> Self {
+        OptionColumn {
+            some: Bitmap::with_capacity(capacity),
+            values: C::with_capacity(capacity),
+        }
+    }
+
+    fn capacity(&self) -> usize {
+        let spare = (self.some.capacity() - self.len()).min(self.values.capacity() - self.values.len());
+        self.len() + spare
+    }
+
+    fn reserve(&mut self, additional: usize) {
+        self.some.reserve(additional);
+        self.values.reserve(additional);
+    }
+
+    fn shrink_to_fit(&mut self) {
+        self.some.shrink_to_fit();
+        self.values.shrink_to_fit();
+    }
+
+    fn push(&mut self, element: Option<C::Element>) {
+        self.some.push(element.is_some());
+        if let Some(value) = element {
+            self.values.push(value);
+        }
+    }
+
+    fn pop(&mut self) -> Option<Option<C::Element>> {
+        let some = self.some.pop()?;
+        Some(if some { self.values.pop() } else { None })
+    }
+
+    fn insert(&mut self, index: usize, element: Option<C::Element>) {
+        assert!(index <= self.len(), "insertion index {} is out of bounds for length {}", index, self.len());
+        let position = self.some.rank(index);
+        self.some.insert(index, element.is_some());
+        if let Some(value) = element {
+            self.values.insert(position, value);
+        }
+    }
+
+    fn remove(&mut self, index: usize) -> Option<C::Element> {
+        let position = self.position(index);
+        self.some.remove(index);
+        position.map(|position| self.values.remove(position))
+    }
+
+    fn swap_remove(&mut self, index: usize) -> Option<C::Element> {
+        super::swap_remove(self, index)
+    }
+
+    /// Swap the elements at `a` and `b`. Takes constant time if both are
+    /// `Some` or `None`, and otherwise moves the present values in between.
+    fn swap(&mut self, a: usize, b: usize) {
+        let len = self.len();
+        assert!(a < len && b < len, "swap indices {} and {} out of bounds for length {}", a, b, len);
+        let (a, b) = if a < b { (a, b) } else { (b, a) };
+        let (some_a, some_b) = (self.some.get(a), self.some.get(b));
+        let (position_a, position_b) = (self.some.rank(a), self.some.rank(b));
+        match (some_a, some_b) {
+            (true, true) => self.values.swap(position_a, position_b),
+            (false, false) => {},
+            // The value of `a` moves behind the values in between
+            (true, false) => super::shift(&mut self.values, position_a, position_b - 1),
+            // The value of `b` moves in front of the values in between
+            (false, true) => super::shift(&mut self.values, position_b, position_a),
+        }
+        self.some.set(a, some_b);
+        self.some.set(b, some_a);
+    }
+
+    fn truncate(&mut self, len: usize) {
+        if len < self.len() {
+            self.values.truncate(self.some.rank(len));
+            self.some.truncate(len);
+        }
+    }
+
+    fn split_off(&mut self, at: usize) -> Self {
+        assert!(at <= self.len(), "split index {} is out of bounds for length {}", at, self.len());
+        let split = self.some.rank(at);
+        OptionColumn {
+            some: self.some.split_off(at),
+            values: self.values.split_off(split),
+        }
+    }
+
+    fn append(&mut self, other: &mut Self) {
+        self.values.append(&mut other.values);
+        self.some.append(&mut other.some);
+    }
+
+    fn clear(&mut self) {
+        self.some.clear();
+        self.values.clear();
+    }
+
+    fn get(&self, index: usize) -> Option<Option<C::Ref<'_>>> {
+        if index < self.len() {
+            Some(self.position(index).and_then(|position| self.values.get(position)))
+        } else {
+            None
+        }
+    }
+
+    fn index_mut(&mut self, index: usize) -> Option<C::RefMut<'_>> {
+        let position = self.position(index)?;
+        Some(self.values.index_mut(position))
+    }
+
+    fn iter(&self) -> Self::Iter<'_> {
+        OptionIter { some: self.some.iter(), values: self.values.iter() }
+    }
+
+    fn iter_mut(&mut self) -> Self::IterMut<'_> {
+        OptionIter { some: self.some.iter(), values: self.values.iter_mut() }
+    }
+}
+
+impl<C: CloneContainer> CloneContainer for OptionColumn<C> {
+    fn to_owned(reference: &Option<C::Ref<'_>>) -> Option<C::Element> {
+        reference.as_ref().map(C::to_owned)
+    }
+
+    fn to_owned_mut(reference: &Option<C::RefMut<'_>>) -> Option<C::Element> {
+        reference.as_ref().map(C::to_owned_mut)
+    }
+}
+
+impl<C: CloneContainer> Materialize for OptionColumn<C> {
+    type Output = Self;
+
+    fn materialize<S: BitSet>(&self, bitmap: &S) -> Self {
+        super::materialize(self, bitmap)
+    }
+}
+
+impl<T: Column> Column for Option<T> {
+    type Output = OptionColumn<T::Output>;
+
+    fn new() -> Self::Output {
+        OptionColumn::default()
+    }
+
+    fn with_capacity(capacity: usize) -> Self::Output {
+        ColumnContainer::with_capacity(capacity)
+    }
+}
+
+impl<C: ColumnContainer> Extend<Option<C::Element>> for OptionColumn<C> {
+    fn extend<I: IntoIterator<Item = Option<C::Element>>>(&mut self, iter: I) {
+        for element in iter {
+            self.push(element);
+        }
+    }
+}
+
+impl<C: ColumnContainer> FromIterator<Option<C::Element>> for OptionColumn<C> {
+    fn from_iter<I: IntoIterator<Item = Option<C::Element>>>(iter: I) -> Self {
+        let mut column = Self::default();
+        column.extend(iter);
+        column
+    }
+}
+
+impl<C: ColumnContainer> IntoIterator for OptionColumn<C> {
+    type Item = Option<C::Element>;
+    type IntoIter = OptionIter<bitmap::IntoIter, C::IntoIter>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        OptionIter { some: self.some.into_iter(), values: self.values.into_iter() }
+    }
+}
+
+impl<'a, C: ColumnContainer> IntoIterator for &'a OptionColumn<C> {
+    type Item = Option<C::Ref<'a>>;
+    type IntoIter = OptionIter<bitmap::Iter<'a>, C::Iter<'a>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, C: ColumnContainer> IntoIterator for &'a mut OptionColumn<C> {
+    type Item = Option<C::RefMut<'a>>;
+    type IntoIter = OptionIter<bitmap::Iter<'a>, C::IterMut<'a>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+/// An iterator over the elements of an `OptionColumn`, combining an iterator
+/// over whether each element is `Some` with an iterator over the present
+/// values
+#[derive(Debug)]
+pub struct OptionIter<S, I> {
+    /// Whether each remaining element is `Some`
+    some: S,
+    /// The remaining present values
+    values: I,
+}
+
+impl<S: Iterator<Item = bool>, I: Iterator> Iterator for OptionIter<S, I> {
+    type Item = Option<I::Item>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let some = self.some.next()?;
+        Some(if some { self.values.next() } else { None })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.some.size_hint()
+    }
+}
+
+impl<S, I> DoubleEndedIterator for OptionIter<S, I>
+    where S: DoubleEndedIterator<Item = bool>, I: DoubleEndedIterator
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let some = self.some.next_back()?;
+        Some(if some { self.values.next_back() } else { None })
+    }
+}
+
+impl<S: ExactSizeIterator<Item = bool>, I: Iterator> ExactSizeIterator for OptionIter<S, I> {}
+
+impl<S: FusedIterator<Item = bool>, I: Iterator> FusedIterator for OptionIter<S, I> {}
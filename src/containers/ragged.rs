@@ -0,0 +1,624 @@
+// Copyright 2017 columnar-rs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+//! Columns of variable-length elements, stored in one buffer.
+
+use std::iter::{FromIterator, FusedIterator};
+use std::ops::Range;
+
+use ::{CloneContainer, Column, ColumnContainer, Len};
+use bitmap::{BitSet, Materialize};
+
+/// A growable buffer holding the concatenated elements of a `Ragged` column
+///
+/// Elements are handed out as views on ranges of the buffer, so `String`
+/// elements are referenced as `&str` and `Vec<T>` elements as `&[T]`.
+pub trait Buffer: Default {
+    /// The view on a range of the buffer
+    type Slice: ?Sized;
+
+    /// An owning iterator over the values in the buffer
+    type IntoIter: DoubleEndedIterator;
+
+    /// Create an empty buffer with space for `capacity` values
+    fn with_capacity(capacity: usize) -> Self;
+
+    /// The number of values in the buffer
+    fn len(&self) -> usize;
+
+    /// Test if the buffer holds no values
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Shrink the capacity as much as possible
+    fn shrink_to_fit(&mut self);
+
+    /// Shorten the buffer to `len` values
+    fn truncate(&mut self, len: usize);
+
+    /// Split the buffer in two at `at`, returning the values from `at` on
+    fn split_off(&mut self, at: usize) -> Self;
+
+    /// Move all values of `other` to the end of this buffer
+    fn append(&mut self, other: &mut Self);
+
+    /// Insert `values` at `index`
+    fn insert(&mut self, index: usize, values: Self);
+
+    /// Remove the values in `range` and return them
+    fn drain(&mut self, range: Range<usize>) -> Self;
+
+    /// Rotate the values in `range` such that the value at `range.start + mid`
+    /// comes first. `mid` is at the boundary of two elements.
+    fn rotate_left(&mut self, range: Range<usize>, mid: usize);
+
+    /// A view on the values in `range`
+    fn slice(&self, range: Range<usize>) -> &Self::Slice;
+
+    /// A mutable view on the values in `range`
+    fn slice_mut(&mut self, range: Range<usize>) -> &mut Self::Slice;
+
+    /// The number of values in `slice`
+    fn slice_len(slice: &Self::Slice) -> usize;
+
+    /// Split `slice` in two at `mid`
+    fn split_at(slice: &Self::Slice, mid: usize) -> (&Self::Slice, &Self::Slice);
+
+    /// Split the mutable `slice` in two at `mid`
+    fn split_at_mut(slice: &mut Self::Slice, mid: usize) -> (&mut Self::Slice, &mut Self::Slice);
+
+    /// Iterate the values of the buffer by value
+    fn into_values(self) -> Self::IntoIter;
+
+    /// Collect the next `len` values from the front of `values`
+    fn collect_front(values: &mut Self::IntoIter, len: usize) -> Self;
+
+    /// Collect the next `len` values from the back of `values`, in order
+    fn collect_back(values: &mut Self::IntoIter, len: usize) -> Self;
+}
+
+impl Buffer for String {
+    type Slice = str;
+    type IntoIter = ::std::vec::IntoIter<u8>;
+
+    fn with_capacity(capacity: usize) -> Self {
+        String::with_capacity(capacity)
+    }
+
+    fn len(&self) -> usize {
+        String::len(self)
+    }
+
+    fn shrink_to_fit(&mut self) {
+        String::shrink_to_fit(self)
+    }
+
+    fn truncate(&mut self, len: usize) {
+        String::truncate(self, len)
+    }
+
+    fn split_off(&mut self, at: usize) -> Self {
+        String::split_off(self, at)
+    }
+
+    fn append(&mut self, other: &mut Self) {
+        self.push_str(other);
+        other.clear();
+    }
+
+    fn insert(&mut self, index: usize, values: Self) {
+        self.insert_str(index, &values)
+    }
+
+    fn drain(&mut self, range: Range<usize>) -> Self {
+        String::drain(self, range).collect()
+    }
+
+    fn rotate_left(&mut self, range: Range<usize>, mid: usize) {
+        let rotated = [&self[range.start + mid..range.end], &self[range.start..range.start + mid]].concat();
+        self.replace_range(range, &rotated);
+    }
+
+    fn slice(&self, range: Range<usize>) -> &str {
+        &self[range]
+    }
+
+    fn slice_mut(&mut self, range: Range<usize>) -> &mut str {
+        &mut self[range]
+    }
+
+    fn slice_len(slice: &str) -> usize {
+        slice.len()
+    }
+
+    fn split_at(slice: &str, mid: usize) -> (&str, &str) {
+        slice.split_at(mid)
+    }
+
+    fn split_at_mut(slice: &mut str, mid: usize) -> (&mut str, &mut str) {
+        slice.split_at_mut(mid)
+    }
+
+    fn into_values(self) -> Self::IntoIter {
+        self.into_bytes().into_iter()
+    }
+
+    fn collect_front(values: &mut Self::IntoIter, len: usize) -> Self {
+        String::from_utf8(values.take(len).collect()).expect("Elements end at char boundaries")
+    }
+
+    fn collect_back(values: &mut Self::IntoIter, len: usize) -> Self {
+        let mut bytes: Vec<u8> = values.rev().take(len).collect();
+        bytes.reverse();
+        String::from_utf8(bytes).expect("Elements start at char boundaries")
+    }
+}
+
+impl<T> Buffer for Vec<T> {
+    type Slice = [T];
+    type IntoIter = ::std::vec::IntoIter<T>;
+
+    fn with_capacity(capacity: usize) -> Self {
+        Vec::with_capacity(capacity)
+    }
+
+    fn len(&self) -> usize {
+        Vec::len(self)
+    }
+
+    fn shrink_to_fit(&mut self) {
+        Vec::shrink_to_fit(self)
+    }
+
+    fn truncate(&mut self, len: usize) {
+        Vec::truncate(self, len)
+    }
+
+    fn split_off(&mut self, at: usize) -> Self {
+        Vec::split_off(self, at)
+    }
+
+    fn append(&mut self, other: &mut Self) {
+        Vec::append(self, other)
+    }
+
+    fn insert(&mut self, index: usize, values: Self) {
+        self.splice(index..index, values);
+    }
+
+    fn drain(&mut self, range: Range<usize>) -> Self {
+        Vec::drain(self, range).collect()
+    }
+
+    fn rotate_left(&mut self, range: Range<usize>, mid: usize) {
+        self[range].rotate_left(mid)
+    }
+
+    fn slice(&self, range: Range<usize>) -> &[T] {
+        &self[range]
+    }
+
+    fn slice_mut(&mut self, range: Range<usize>) -> &mut [T] {
+        &mut self[range]
+    }
+
+    fn slice_len(slice: &[T]) -> usize {
+        slice.len()
+    }
+
+    fn split_at(slice: &[T], mid: usize) -> (&[T], &[T]) {
+        slice.split_at(mid)
+    }
+
+    fn split_at_mut(slice: &mut [T], mid: usize) -> (&mut [T], &mut [T]) {
+        slice.split_at_mut(mid)
+    }
+
+    fn into_values(self) -> Self::IntoIter {
+        self.into_iter()
+    }
+
+    fn collect_front(values: &mut Self::IntoIter, len: usize) -> Self {
+        values.take(len).collect()
+    }
+
+    fn collect_back(values: &mut Self::IntoIter, len: usize) -> Self {
+        let mut result: Vec<T> = values.rev().take(len).collect();
+        result.reverse();
+        result
+    }
+}
+
+/// A column of variable-length elements, such as `String` or `Vec<T>`
+///
+/// All elements are concatenated into one `Buffer`, and the column records
+/// the offset in the buffer at which each element ends.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Ragged<B> {
+    /// The end offset of each element in `values`
+    ends: Vec<usize>,
+    /// The concatenated elements
+    values: B,
+}
+
+/// The column of `String`s, referencing elements as `&str`
+pub type StringColumn = Ragged<String>;
+
+/// The column of `Vec<T>`s, referencing elements as `&[T]`
+pub type VecColumn<T> = Ragged<Vec<T>>;
+
+impl<B: Buffer> Ragged<B> {
+
+    /// The concatenated elements
+    pub fn values(&self) -> &B {
+        &self.values
+    }
+
+    /// The offset in `values` at which each element ends
+    pub fn ends(&self) -> &[usize] {
+        &self.ends
+    }
+
+    /// The offset in `values` at which the element at `index` starts
+    fn start(&self, index: usize) -> usize {
+        if index == 0 { 0 } else { self.ends[index - 1] }
+    }
+
+    /// The range of the element at `index` in `values`
+    fn range(&self, index: usize) -> Range<usize> {
+        self.start(index)..self.ends[index]
+    }
+}
+
+impl<B: Buffer> Len for Ragged<B> {
+    fn len(&self) -> usize {
+        self.ends.len()
+    }
+}
+
+impl<B: Buffer> ColumnContainer for Ragged<B> {
+    type Element = B;
+    type Ref<'a> = &'a B::Slice where Self: 'a;
+    type RefMut<'a> = &'a mut B::Slice where Self: 'a;
+    type Iter<'a> = RaggedIter<'a, B> where Self: 'a;
+    type IterMut<'a> = RaggedIterMut<'a, B> where Self: 'a;
+
+    fn with_capacity(capacity: usize) -> Self {
+        Ragged { ends: Vec::with_capacity(capacity), values: B::default() }
+    }
+
+    fn capacity(&self) -> usize {
+        self.ends.capacity()
+    }
+
+    fn reserve(&mut self, additional: usize) {
+        self.ends.reserve(additional)
+    }
+
+    fn shrink_to_fit(&mut self) {
+        self.ends.shrink_to_fit();
+        self.values.shrink_to_fit();
+    }
+
+    fn push(&mut self, mut element: B) {
+        self.values.append(&mut element);
+        self.ends.push(self.values.len());
+    }
+
+    fn pop(&mut self) -> Option<B> {
+        self.ends.pop()?;
+        let start = self.ends.last().cloned().unwrap_or(0);
+        Some(self.values.split_off(start))
+    }
+
+    fn insert(&mut self, index: usize, element: B) {
+        assert!(index <= self.len(), "insertion index {} is out of bounds for length {}", index, self.len());
+        let start = self.start(index);
+        let len = element.len();
+        self.values.insert(start, element);
+        self.ends.insert(index, start);
+        for end in &mut self.ends[index..] {
+            *end += len;
+        }
+    }
+
+    fn remove(&mut self, index: usize) -> B {
+        let range = self.range(index);
+        let len = range.len();
+        let element = self.values.drain(range);
+        self.ends.remove(index);
+        for end in &mut self.ends[index..] {
+            *end -= len;
+        }
+        element
+    }
+
+    fn swap_remove(&mut self, index: usize) -> B {
+        super::swap_remove(self, index)
+    }
+
+    /// Swap the elements at `a` and `b`, moving the values of the elements in
+    /// between if the elements differ in length
+    fn swap(&mut self, a: usize, b: usize) {
+        let len = self.len();
+        assert!(a < len && b < len, "swap indices {} and {} out of bounds for length {}", a, b, len);
+        let (a, b) = if a < b { (a, b) } else { (b, a) };
+        let (range_a, range_b) = (self.range(a), self.range(b));
+        if a == b || range_a.is_empty() && range_b.is_empty() {
+            return;
+        }
+        // Turn `a`, the values in between and `b` into `b`, in between and `a`
+        let between = range_b.start - range_a.end;
+        self.values.rotate_left(range_a.start..range_b.end, range_a.len());
+        self.values.rotate_left(range_a.start..range_b.end - range_a.len(), between);
+        for end in &mut self.ends[a..b] {
+            *end = *end - range_a.len() + range_b.len();
+        }
+    }
+
+    fn truncate(&mut self, len: usize) {
+        if len < self.len() {
+            self.ends.truncate(len);
+            let end = self.start(len);
+            self.values.truncate(end);
+        }
+    }
+
+    fn split_off(&mut self, at: usize) -> Self {
+        assert!(at <= self.len(), "split index {} is out of bounds for length {}", at, self.len());
+        let start = self.start(at);
+        let values = self.values.split_off(start);
+        let ends = self.ends.split_off(at).into_iter().map(|end| end - start).collect();
+        Ragged { ends, values }
+    }
+
+    fn append(&mut self, other: &mut Self) {
+        let offset = self.values.len();
+        self.values.append(&mut other.values);
+        self.ends.extend(other.ends.drain(..).map(|end| end + offset));
+    }
+
+    fn clear(&mut self) {
+        self.ends.clear();
+        self.values.truncate(0);
+    }
+
+    fn get(&self, index: usize) -> Option<&B::Slice> {
+        if index < self.len() { Some(self.values.slice(self.range(index))) } else { None }
+    }
+
+    fn index_mut(&mut self, index: usize) -> &mut B::Slice {
+        let range = self.range(index);
+        self.values.slice_mut(range)
+    }
+
+    fn iter(&self) -> RaggedIter<'_, B> {
+        RaggedIter { ends: self.ends.iter(), rest: self.values.slice(0..self.values.len()), front: 0 }
+    }
+
+    fn iter_mut(&mut self) -> RaggedIterMut<'_, B> {
+        let len = self.values.len();
+        RaggedIterMut { ends: self.ends.iter(), rest: Some(self.values.slice_mut(0..len)), front: 0 }
+    }
+}
+
+impl CloneContainer for Ragged<String> {
+    fn to_owned(reference: &&str) -> String {
+        String::from(*reference)
+    }
+
+    fn to_owned_mut(reference: &&mut str) -> String {
+        String::from(&**reference)
+    }
+}
+
+impl<T: Clone> CloneContainer for Ragged<Vec<T>> {
+    fn to_owned(reference: &&[T]) -> Vec<T> {
+        reference.to_vec()
+    }
+
+    fn to_owned_mut(reference: &&mut [T]) -> Vec<T> {
+        reference.to_vec()
+    }
+}
+
+impl<B: Buffer> Materialize for Ragged<B> where Self: CloneContainer {
+    type Output = Self;
+
+    fn materialize<S: BitSet>(&self, bitmap: &S) -> Self {
+        super::materialize(self, bitmap)
+    }
+}
+
+impl Column for String {
+    type Output = StringColumn;
+
+    fn new() -> StringColumn {
+        Ragged::default()
+    }
+
+    fn with_capacity(capacity: usize) -> StringColumn {
+        ColumnContainer::with_capacity(capacity)
+    }
+}
+
+impl<T> Column for Vec<T> {
+    type Output = VecColumn<T>;
+
+    fn new() -> VecColumn<T> {
+        Ragged::default()
+    }
+
+    fn with_capacity(capacity: usize) -> VecColumn<T> {
+        ColumnContainer::with_capacity(capacity)
+    }
+}
+
+impl<B: Buffer> Extend<B> for Ragged<B> {
+    fn extend<I: IntoIterator<Item = B>>(&mut self, iter: I) {
+        for element in iter {
+            self.push(element);
+        }
+    }
+}
+
+impl<B: Buffer> FromIterator<B> for Ragged<B> {
+    fn from_iter<I: IntoIterator<Item = B>>(iter: I) -> Self {
+        let mut column = Self::default();
+        column.extend(iter);
+        column
+    }
+}
+
+impl<B: Buffer> IntoIterator for Ragged<B> {
+    type Item = B;
+    type IntoIter = RaggedIntoIter<B>;
+
+    fn into_iter(self) -> RaggedIntoIter<B> {
+        RaggedIntoIter { ends: self.ends.into_iter(), values: self.values.into_values(), front: 0 }
+    }
+}
+
+impl<'a, B: Buffer> IntoIterator for &'a Ragged<B> {
+    type Item = &'a B::Slice;
+    type IntoIter = RaggedIter<'a, B>;
+
+    fn into_iter(self) -> RaggedIter<'a, B> {
+        self.iter()
+    }
+}
+
+impl<'a, B: Buffer> IntoIterator for &'a mut Ragged<B> {
+    type Item = &'a mut B::Slice;
+    type IntoIter = RaggedIterMut<'a, B>;
+
+    fn into_iter(self) -> RaggedIterMut<'a, B> {
+        self.iter_mut()
+    }
+}
+
+/// An iterator over the elements of a `Ragged` column
+#[derive(Debug)]
+pub struct RaggedIter<'a, B: Buffer + 'a> {
+    /// The end offsets of the remaining elements
+    ends: ::std::slice::Iter<'a, usize>,
+    /// The values of the remaining elements
+    rest: &'a B::Slice,
+    /// The offset at which `rest` starts
+    front: usize,
+}
+
+impl<'a, B: Buffer> Iterator for RaggedIter<'a, B> {
+    type Item = &'a B::Slice;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let end = *self.ends.next()?;
+        let (element, rest) = B::split_at(self.rest, end - self.front);
+        self.rest = rest;
+        self.front = end;
+        Some(element)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.ends.size_hint()
+    }
+}
+
+impl<'a, B: Buffer> DoubleEndedIterator for RaggedIter<'a, B> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let end = *self.ends.next_back()?;
+        let start = self.ends.as_slice().last().cloned().unwrap_or(self.front);
+        let (rest, element) = B::split_at(self.rest, B::slice_len(self.rest) - (end - start));
+        self.rest = rest;
+        Some(element)
+    }
+}
+
+impl<'a, B: Buffer> ExactSizeIterator for RaggedIter<'a, B> {}
+
+impl<'a, B: Buffer> FusedIterator for RaggedIter<'a, B> {}
+
+/// An iterator over mutable references to the elements of a `Ragged` column
+#[derive(Debug)]
+pub struct RaggedIterMut<'a, B: Buffer + 'a> {
+    /// The end offsets of the remaining elements
+    ends: ::std::slice::Iter<'a, usize>,
+    /// The values of the remaining elements
+    rest: Option<&'a mut B::Slice>,
+    /// The offset at which `rest` starts
+    front: usize,
+}
+
+impl<'a, B: Buffer> Iterator for RaggedIterMut<'a, B> {
+    type Item = &'a mut B::Slice;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let end = *self.ends.next()?;
+        let (element, rest) = B::split_at_mut(self.rest.take()?, end - self.front);
+        self.rest = Some(rest);
+        self.front = end;
+        Some(element)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.ends.size_hint()
+    }
+}
+
+impl<'a, B: Buffer> DoubleEndedIterator for RaggedIterMut<'a, B> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let end = *self.ends.next_back()?;
+        let start = self.ends.as_slice().last().cloned().unwrap_or(self.front);
+        let rest = self.rest.take()?;
+        let mid = B::slice_len(rest) - (end - start);
+        let (rest, element) = B::split_at_mut(rest, mid);
+        self.rest = Some(rest);
+        Some(element)
+    }
+}
+
+impl<'a, B: Buffer> ExactSizeIterator for RaggedIterMut<'a, B> {}
+
+impl<'a, B: Buffer> FusedIterator for RaggedIterMut<'a, B> {}
+
+/// An iterator moving the elements out of a `Ragged` column
+#[derive(Debug)]
+pub struct RaggedIntoIter<B: Buffer> {
+    /// The end offsets of the remaining elements
+    ends: ::std::vec::IntoIter<usize>,
+    /// The values of the remaining elements
+    values: B::IntoIter,
+    /// The offset of the first remaining value
+    front: usize,
+}
+
+impl<B: Buffer> Iterator for RaggedIntoIter<B> {
+    type Item = B;
+
+    fn next(&mut self) -> Option<B> {
+        let end = self.ends.next()?;
+        let element = B::collect_front(&mut self.values, end - self.front);
+        self.front = end;
+        Some(element)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.ends.size_hint()
+    }
+}
+
+impl<B: Buffer> DoubleEndedIterator for RaggedIntoIter<B> {
+    fn next_back(&mut self) -> Option<B> {
+        let end = self.ends.next_back()?;
+        let start = self.ends.as_slice().last().cloned().unwrap_or(self.front);
+        Some(B::collect_back(&mut self.values, end - start))
+    }
+}
+
+impl<B: Buffer> ExactSizeIterator for RaggedIntoIter<B> {}
+
+impl<B: Buffer> FusedIterator for RaggedIntoIter<B> {}
@@ -0,0 +1,339 @@
+// Copyright 2017 columnar-rs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+//! The column of `Result`s, storing the values of each variant separately.
+
+use std::iter::{FromIterator, FusedIterator};
+
+use ::{CloneContainer, Column, ColumnContainer, Len};
+use bitmap::{self, BitSet, Bitmap, Materialize};
+
+/// A column of `Result`s, storing the `Ok` values densely in a column `T` and
+/// the `Err` values in a column `E`
+///
+/// Whether each element is `Ok` is recorded in a `Bitmap`. The position of
+/// the value of an `Ok` element in `oks` is its `rank`, and of an `Err`
+/// element in `errs` its index minus its `rank`. Elements are referenced as
+/// `Result<T::Ref, E::Ref>`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ResultColumn<T, E> {
+    /// Whether each element is `Ok`
+    ok: Bitmap,
+    /// The `Ok` values
+    oks: T,
+    /// The `Err` values
+    errs: E,
+}
+
+impl<T: ColumnContainer, E: ColumnContainer> ResultColumn<T, E> {
+
+    /// Whether each element is `Ok`
+    pub fn ok(&self) -> &Bitmap {
+        &self.ok
+    }
+
+    /// The column of the `Ok` values
+    pub fn oks(&self) -> &T {
+        &self.oks
+    }
+
+    /// The column of the `Err` values
+    pub fn errs(&self) -> &E {
+        &self.errs
+    }
+
+    /// The position of the value of the element at `index` in `oks` if it is
+    /// `Ok`, or in `errs` if it is an `Err`
+    fn position(&self, index: usize) -> Result<usize, usize> {
+        let rank = self.ok.rank(index);
+        if self.ok.get(index) { Ok(rank) } else { Err(index - rank) }
+    }
+}
+
+impl<T: ColumnContainer, E: ColumnContainer> Default for ResultColumn<T, E> {
+    fn default() -> Self {
+        ColumnContainer::with_capacity(0)
+    }
+}
+
+impl<T: ColumnContainer, E: ColumnContainer> Len for ResultColumn<T, E> {
+    fn len(&self) -> usize {
+        self.ok.len()
+    }
+}
+
+impl<T: ColumnContainer, E: ColumnContainer> ColumnContainer for ResultColumn<T, E> {
+    type Element = Result<T::Element, E::Element>;
+    type Ref<'a> = Result<T::Ref<'a>, E::Ref<'a>> where Self: 'a;
+    type RefMut<'a> = Result<T::RefMut<'a>, E::RefMut<'a>> where Self: 'a;
+    type Iter<'a> = ResultIter<bitmap::Iter<'a>, T::Iter<'a>, E::Iter<'a>> where Self: 'a;
+    type IterMut<'a> = ResultIter<bitmap::Iter<'a>, T::IterMut<'a>, E::IterMut<'a>> where Self: 'a;
+
+    fn with_capacity(capacity: usize) -> Self {
+        ResultColumn {
+            ok: Bitmap::with_capacity(capacity),
+            oks: T::with_capacity(capacity),
+            errs: E::with_capacity(capacity),
+        }
+    }
+
+    fn capacity(&self) -> usize {
+        let spare = (self.ok.capacity() - self.len())
+            .min(self.oks.capacity() - self.oks.len())
+            .min(self.errs.capacity() - self.errs.len());
+        self.len() + spare
+    }
+
+    fn reserve(&mut self, additional: usize) {
+        self.ok.reserve(additional);
+        self.oks.reserve(additional);
+        self.errs.reserve(additional);
+    }
+
+    fn shrink_to_fit(&mut self) {
+        self.ok.shrink_to_fit();
+        self.oks.shrink_to_fit();
+        self.errs.shrink_to_fit();
+    }
+
+    fn push(&mut self, element: Self::Element) {
+        self.ok.push(element.is_ok());
+        match element {
+            Ok(value) => self.oks.push(value),
+            Err(error) => self.errs.push(error),
+        }
+    }
+
+    fn pop(&mut self) -> Option<Self::Element> {
+        let ok = self.ok.pop()?;
+        Some(if ok { Ok(self.oks.pop()?) } else { Err(self.errs.pop()?) })
+    }
+
+    fn insert(&mut self, index: usize, element: Self::Element) {
+        assert!(index <= self.len(), "insertion index {} is out of bounds for length {}", index, self.len());
+        let rank = self.ok.rank(index);
+        self.ok.insert(index, element.is_ok());
+        match element {
+            Ok(value) => self.oks.insert(rank, value),
+            Err(error) => self.errs.insert(index - rank, error),
+        }
+    }
+
+    fn remove(&mut self, index: usize) -> Self::Element {
+        let position = self.position(index);
+        self.ok.remove(index);
+        match position {
+            Ok(position) => Ok(self.oks.remove(position)),
+            Err(position) => Err(self.errs.remove(position)),
+        }
+    }
+
+    fn swap_remove(&mut self, index: usize) -> Self::Element {
+        super::swap_remove(self, index)
+    }
+
+    /// Swap the elements at `a` and `b`. Takes constant time if both are of
+    /// the same variant, and otherwise moves the values in between.
+    fn swap(&mut self, a: usize, b: usize) {
+        let len = self.len();
+        assert!(a < len && b < len, "swap indices {} and {} out of bounds for length {}", a, b, len);
+        let (a, b) = if a < b { (a, b) } else { (b, a) };
+        match (self.position(a), self.position(b)) {
+            (Ok(position_a), Ok(position_b)) => self.oks.swap(position_a, position_b),
+            (Err(position_a), Err(position_b)) => self.errs.swap(position_a, position_b),
+            // The value of `a` moves behind the values of its variant in
+            // between, and the value of `b` in front of those of its variant
+            (Ok(position_a), Err(position_b)) => {
+                let rank_b = b - position_b;
+                super::shift(&mut self.oks, position_a, rank_b - 1);
+                super::shift(&mut self.errs, position_b, a - position_a);
+                self.ok.set(a, false);
+                self.ok.set(b, true);
+            },
+            (Err(position_a), Ok(position_b)) => {
+                let rank_a = a - position_a;
+                super::shift(&mut self.errs, position_a, (b - position_b) - 1);
+                super::shift(&mut self.oks, position_b, rank_a);
+                self.ok.set(a, true);
+                self.ok.set(b, false);
+            },
+        }
+    }
+
+    fn truncate(&mut self, len: usize) {
+        if len < self.len() {
+            let rank = self.ok.rank(len);
+            self.oks.truncate(rank);
+            self.errs.truncate(len - rank);
+            self.ok.truncate(len);
+        }
+    }
+
+    fn split_off(&mut self, at: usize) -> Self {
+        assert!(at <= self.len(), "split index {} is out of bounds for length {}", at, self.len());
+        let split = self.ok.rank(at);
+        ResultColumn {
+            ok: self.ok.split_off(at),
+            oks: self.oks.split_off(split),
+            errs: self.errs.split_off(at - split),
+        }
+    }
+
+    fn append(&mut self, other: &mut Self) {
+        self.oks.append(&mut other.oks);
+        self.errs.append(&mut other.errs);
+        self.ok.append(&mut other.ok);
+    }
+
+    fn clear(&mut self) {
+        self.ok.clear();
+        self.oks.clear();
+        self.errs.clear();
+    }
+
+    fn get(&self, index: usize) -> Option<Self::Ref<'_>> {
+        if index >= self.len() {
+            return None;
+        }
+        match self.position(index) {
+            Ok(position) => self.oks.get(position).map(Ok),
+            Err(position) => self.errs.get(position).map(Err),
+        }
+    }
+
+    fn index_mut(&mut self, index: usize) -> Self::RefMut<'_> {
+        match self.position(index) {
+            Ok(position) => Ok(self.oks.index_mut(position)),
+            Err(position) => Err(self.errs.index_mut(position)),
+        }
+    }
+
+    fn iter(&self) -> Self::Iter<'_> {
+        ResultIter { ok: self.ok.iter(), oks: self.oks.iter(), errs: self.errs.iter() }
+    }
+
+    fn iter_mut(&mut self) -> Self::IterMut<'_> {
+        ResultIter { ok: self.ok.iter(), oks: self.oks.iter_mut(), errs: self.errs.iter_mut() }
+    }
+}
+
+impl<T: CloneContainer, E: CloneContainer> CloneContainer for ResultColumn<T, E> {
+    fn to_owned(reference: &Self::Ref<'_>) -> Self::Element {
+        match *reference {
+            Ok(ref value) => Ok(T::to_owned(value)),
+            Err(ref error) => Err(E::to_owned(error)),
+        }
+    }
+
+    fn to_owned_mut(reference: &Self::RefMut<'_>) -> Self::Element {
+        match *reference {
+            Ok(ref value) => Ok(T::to_owned_mut(value)),
+            Err(ref error) => Err(E::to_owned_mut(error)),
+        }
+    }
+}
+
+impl<T: CloneContainer, E: CloneContainer> Materialize for ResultColumn<T, E> {
+    type Output = Self;
+
+    fn materialize<S: BitSet>(&self, bitmap: &S) -> Self {
+        super::materialize(self, bitmap)
+    }
+}
+
+impl<T: Column, E: Column> Column for Result<T, E> {
+    type Output = ResultColumn<T::Output, E::Output>;
+
+    fn new() -> Self::Output {
+        ResultColumn::default()
+    }
+
+    fn with_capacity(capacity: usize) -> Self::Output {
+        ColumnContainer::with_capacity(capacity)
+    }
+}
+
+impl<T: ColumnContainer, E: ColumnContainer> Extend<Result<T::Element, E::Element>> for ResultColumn<T, E> {
+    fn extend<I: IntoIterator<Item = Result<T::Element, E::Element>>>(&mut self, iter: I) {
+        for element in iter {
+            self.push(element);
+        }
+    }
+}
+
+impl<T: ColumnContainer, E: ColumnContainer> FromIterator<Result<T::Element, E::Element>> for ResultColumn<T, E> {
+    fn from_iter<I: IntoIterator<Item = Result<T::Element, E::Element>>>(iter: I) -> Self {
+        let mut column = Self::default();
+        column.extend(iter);
+        column
+    }
+}
+
+impl<T: ColumnContainer, E: ColumnContainer> IntoIterator for ResultColumn<T, E> {
+    type Item = Result<T::Element, E::Element>;
+    type IntoIter = ResultIter<bitmap::IntoIter, T::IntoIter, E::IntoIter>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        ResultIter { ok: self.ok.into_iter(), oks: self.oks.into_iter(), errs: self.errs.into_iter() }
+    }
+}
+
+impl<'a, T: ColumnContainer, E: ColumnContainer> IntoIterator for &'a ResultColumn<T, E> {
+    type Item = Result<T::Ref<'a>, E::Ref<'a>>;
+    type IntoIter = ResultIter<bitmap::Iter<'a>, T::Iter<'a>, E::Iter<'a>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T: ColumnContainer, E: ColumnContainer> IntoIterator for &'a mut ResultColumn<T, E> {
+    type Item = Result<T::RefMut<'a>, E::RefMut<'a>>;
+    type IntoIter = ResultIter<bitmap::Iter<'a>, T::IterMut<'a>, E::IterMut<'a>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+/// An iterator over the elements of a `ResultColumn`, combining an iterator
+/// over whether each element is `Ok` with iterators over the `Ok` and `Err`
+/// values
+#[derive(Debug)]
+pub struct ResultIter<S, I, J> {
+    /// Whether each remaining element is `Ok`
+    ok: S,
+    /// The remaining `Ok` values
+    oks: I,
+    /// The remaining `Err` values
+    errs: J,
+}
+
+impl<S: Iterator<Item = bool>, I: Iterator, J: Iterator> Iterator for ResultIter<S, I, J> {
+    type Item = Result<I::Item, J::Item>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let ok = self.ok.next()?;
+        Some(if ok { Ok(self.oks.next()?) } else { Err(self.errs.next()?) })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.ok.size_hint()
+    }
+}
+
+impl<S, I, J> DoubleEndedIterator for ResultIter<S, I, J>
+    where S: DoubleEndedIterator<Item = bool>, I: DoubleEndedIterator, J: DoubleEndedIterator
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let ok = self.ok.next_back()?;
+        Some(if ok { Ok(self.oks.next_back()?) } else { Err(self.errs.next_back()?) })
+    }
+}
+
+impl<S: ExactSizeIterator<Item = bool>, I: Iterator, J: Iterator> ExactSizeIterator for ResultIter<S, I, J> {}
+
+impl<S: FusedIterator<Item = bool>, I: Iterator, J: Iterator> FusedIterator for ResultIter<S, I, J> {}
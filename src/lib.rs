@@ -7,12 +7,18 @@
 //! Column is a Rust library to repesent collections of elements
 //! in a columnar memory layout.
 
+use std::iter::FusedIterator;
+
 pub mod bitmap;
+pub mod containers;
 pub mod tuple;
 
 /// Trait describing associated and generated types for a type
 /// that can be represented in a columnar layout
 ///
+/// The columnar representation is `Output`, which implements
+/// `ColumnContainer` to access the stored elements.
+///
 /// # Example
 ///
 /// ```
@@ -25,10 +31,20 @@ pub mod tuple;
 /// let column = <Data as Column>::new();
 /// # }
 /// ```
-pub trait Column<'a> {
+///
+/// Derived structs need at least one field that is not skipped:
+///
+/// ```compile_fail
+/// # #[macro_use] extern crate column_derive;
+/// # extern crate column;
+/// #[derive(Column)]
+/// struct Data {#[column(skip)] x: usize}
+/// # fn main() {}
+/// ```
+pub trait Column {
 
     /// The type representing the wrapped data in a columnar data layout.
-    type Output;
+    type Output: ColumnContainer;
 
     /// Construct a new `Columar` with default capacity.
     /// # Example
@@ -60,3 +76,260 @@ pub trait Column<'a> {
     /// ```
     fn with_capacity(len: usize) -> Self::Output;
 }
+
+/// A collection storing elements in a columnar layout
+///
+/// Containers hand out references to the elements they store, which for
+/// derived columns are the generated `Ref` and `RefMut` types. Operators can
+/// be written generically over any container:
+///
+/// ```
+/// # #[macro_use] extern crate column_derive;
+/// # extern crate column;
+/// use column::{Column, ColumnContainer};
+/// #[derive(Column)]
+/// struct Data {x: usize}
+///
+/// fn first<C: ColumnContainer>(column: &C) -> Option<C::Ref<'_>> {
+///     column.iter().next()
+/// }
+/// # fn main() {
+/// let mut column = <Data as Column>::new();
+/// column.push(Data { x: 3 });
+/// assert_eq!(first(&column).map(|data| *data.x), Some(3));
+/// # }
+/// ```
+pub trait ColumnContainer: Len + IntoIterator<Item = <Self as ColumnContainer>::Element, IntoIter: DoubleEndedIterator + ExactSizeIterator> {
+    /// The type of the stored elements
+    type Element;
+
+    /// A reference to a stored element, e.g. `DataRef<'a>`
+    type Ref<'a> where Self: 'a;
+
+    /// A mutable reference to a stored element, e.g. `DataRefMut<'a>`
+    type RefMut<'a> where Self: 'a;
+
+    /// An iterator over the stored elements, yielding `Ref`s
+    type Iter<'a>: DoubleEndedIterator<Item = Self::Ref<'a>> + ExactSizeIterator + FusedIterator where Self: 'a;
+
+    /// An iterator over the stored elements, yielding `RefMut`s
+    type IterMut<'a>: DoubleEndedIterator<Item = Self::RefMut<'a>> + ExactSizeIterator + FusedIterator where Self: 'a;
+
+    /// Create an empty container with space for `capacity` elements
+    fn with_capacity(capacity: usize) -> Self;
+
+    /// The number of elements the container can hold without reallocating
+    fn capacity(&self) -> usize;
+
+    /// Reserve space for at least `additional` more elements
+    fn reserve(&mut self, additional: usize);
+
+    /// Shrink the capacity as much as possible
+    fn shrink_to_fit(&mut self);
+
+    /// Append an element
+    fn push(&mut self, element: Self::Element);
+
+    /// Remove the last element and return it, or `None` if the container is
+    /// empty
+    fn pop(&mut self) -> Option<Self::Element>;
+
+    /// Insert an element at `index`, shifting all elements after it
+    ///
+    /// # Panics
+    /// Panics if `index` is greater than the length.
+    fn insert(&mut self, index: usize, element: Self::Element);
+
+    /// Remove the element at `index` and return it, shifting all elements
+    /// after it
+    ///
+    /// # Panics
+    /// Panics if `index` is out of bounds.
+    fn remove(&mut self, index: usize) -> Self::Element;
+
+    /// Remove the element at `index` and return it, replacing it by the last
+    /// element
+    ///
+    /// # Panics
+    /// Panics if `index` is out of bounds.
+    fn swap_remove(&mut self, index: usize) -> Self::Element;
+
+    /// Swap the elements at `a` and `b`
+    ///
+    /// # Panics
+    /// Panics if `a` or `b` are out of bounds.
+    fn swap(&mut self, a: usize, b: usize);
+
+    /// Shorten the container to `len` elements
+    fn truncate(&mut self, len: usize);
+
+    /// Split the container in two at `at`, returning the elements from `at` on
+    ///
+    /// # Panics
+    /// Panics if `at` is greater than the length.
+    fn split_off(&mut self, at: usize) -> Self;
+
+    /// Move all elements of `other` to the end of this container
+    fn append(&mut self, other: &mut Self);
+
+    /// Remove all elements
+    fn clear(&mut self);
+
+    /// A reference to the element at `index`, or `None` if `index` is out of
+    /// bounds
+    fn get(&self, index: usize) -> Option<Self::Ref<'_>>;
+
+    /// A mutable reference to the element at `index`
+    ///
+    /// # Panics
+    /// Panics if `index` is out of bounds.
+    fn index_mut(&mut self, index: usize) -> Self::RefMut<'_>;
+
+    /// Iterate the stored elements
+    fn iter(&self) -> Self::Iter<'_>;
+
+    /// Iterate the stored elements mutably
+    fn iter_mut(&mut self) -> Self::IterMut<'_>;
+}
+
+impl<T> ColumnContainer for Vec<T> {
+    type Element = T;
+    type Ref<'a> = &'a T where T: 'a;
+    type RefMut<'a> = &'a mut T where T: 'a;
+    type Iter<'a> = ::std::slice::Iter<'a, T> where T: 'a;
+    type IterMut<'a> = ::std::slice::IterMut<'a, T> where T: 'a;
+
+    fn with_capacity(capacity: usize) -> Self {
+        Vec::with_capacity(capacity)
+    }
+
+    fn capacity(&self) -> usize {
+        Vec::capacity(self)
+    }
+
+    fn reserve(&mut self, additional: usize) {
+        Vec::reserve(self, additional)
+    }
+
+    fn shrink_to_fit(&mut self) {
+        Vec::shrink_to_fit(self)
+    }
+
+    fn push(&mut self, element: T) {
+        Vec::push(self, element)
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        Vec::pop(self)
+    }
+
+    fn insert(&mut self, index: usize, element: T) {
+        Vec::insert(self, index, element)
+    }
+
+    fn remove(&mut self, index: usize) -> T {
+        Vec::remove(self, index)
+    }
+
+    fn swap_remove(&mut self, index: usize) -> T {
+        Vec::swap_remove(self, index)
+    }
+
+    fn swap(&mut self, a: usize, b: usize) {
+        <[T]>::swap(self, a, b)
+    }
+
+    fn truncate(&mut self, len: usize) {
+        Vec::truncate(self, len)
+    }
+
+    fn split_off(&mut self, at: usize) -> Self {
+        Vec::split_off(self, at)
+    }
+
+    fn append(&mut self, other: &mut Self) {
+        Vec::append(self, other)
+    }
+
+    fn clear(&mut self) {
+        Vec::clear(self)
+    }
+
+    fn get(&self, index: usize) -> Option<&T> {
+        <[T]>::get(self, index)
+    }
+
+    fn index_mut(&mut self, index: usize) -> &mut T {
+        &mut self[index]
+    }
+
+    fn iter(&self) -> ::std::slice::Iter<'_, T> {
+        <[T]>::iter(self)
+    }
+
+    fn iter_mut(&mut self) -> ::std::slice::IterMut<'_, T> {
+        <[T]>::iter_mut(self)
+    }
+}
+
+/// A `ColumnContainer` whose elements can be cloned out
+pub trait CloneContainer: ColumnContainer {
+    /// An owned copy of the element behind `reference`
+    fn to_owned(reference: &Self::Ref<'_>) -> Self::Element;
+
+    /// An owned copy of the element behind the mutable `reference`
+    fn to_owned_mut(reference: &Self::RefMut<'_>) -> Self::Element;
+
+    /// An owned copy of the element at `index`
+    ///
+    /// # Panics
+    /// Panics if `index` is out of bounds.
+    fn index(&self, index: usize) -> Self::Element {
+        match self.get(index) {
+            Some(reference) => Self::to_owned(&reference),
+            None => panic!("index {} out of bounds for length {}", index, self.len()),
+        }
+    }
+}
+
+impl<T: Clone> CloneContainer for Vec<T> {
+    fn to_owned(reference: &&T) -> T {
+        T::clone(reference)
+    }
+
+    fn to_owned_mut(reference: &&mut T) -> T {
+        T::clone(reference)
+    }
+
+    fn index(&self, index: usize) -> T {
+        self[index].clone()
+    }
+}
+
+/// The number of elements in a collection
+///
+/// `FilteredCollection` and its variants use `Len` to size their bitmap to
+/// the wrapped collection. It is implemented for `Vec`, slices, tuple columns
+/// and all derived columns.
+pub trait Len {
+
+    /// The number of elements in the collection
+    fn len(&self) -> usize;
+
+    /// Test if the collection contains no elements
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<T> Len for Vec<T> {
+    fn len(&self) -> usize {
+        Vec::len(self)
+    }
+}
+
+impl<T> Len for [T] {
+    fn len(&self) -> usize {
+        <[T]>::len(self)
+    }
+}
@@ -5,8 +5,33 @@
 // http://opensource.org/licenses/MIT>, at your option. This file may not be
 // copied, modified, or distributed except according to those terms.
 //! Provide a `Column` representation for tuples.
+//!
+//! `Col<(A, B, ...)>` stores the values at each position of a tuple in a
+//! separate `Vec`. To store nested tuples as nested tuple columns instead,
+//! wrap the tuple in `Nested`: `Nested<((K, V1), (K, V2))>` stores all four
+//! fields in separate vectors and iterates nested reference tuples like
+//! `((&K, &V1), (&K, &V2))`. Its values implement `Element` to pick their
+//! storage.
+//!
+//! # Examples
+//! ```
+//! use column::Column;
+//! use column::tuple::Nested;
+//! let mut column = <Nested<((u64, f32), (u64, bool))> as Column>::new();
+//! column.push(((1, 0.5), (1, true)));
+//! assert_eq!(column.iter().next(), Some(((&1, &0.5), (&1, &true))));
+//! let (left, right) = column.into_inner();
+//! assert_eq!(left.col0(), &[1]);
+//! assert_eq!(right.col1(), &[true]);
+//! ```
 
-use ::Column;
+use std::iter::FusedIterator;
+use std::marker::PhantomData;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use ::{CloneContainer, Column, ColumnContainer, Len};
+use bitmap::{BitSet, Materialize};
 
 /// A placeholder struct to wrap a type `T`. Here, `T` is used
 /// to represent different kinds of tuples.
@@ -15,193 +40,574 @@ pub struct Col<T> {
     t: T,
 }
 
-// macro for implementing n-ary tuple functions and operations
+impl<T> Col<T> {
+
+    /// Create a column from a tuple of the storages of the values at each
+    /// position, which must have the same length
+    pub fn from_inner(t: T) -> Self {
+        Col { t }
+    }
+
+    /// Take out the tuple of the storages of the values at each position
+    pub fn into_inner(self) -> T {
+        self.t
+    }
+}
+
+/// A placeholder struct like `Col`, whose column stores nested tuples as
+/// nested tuple columns
+///
+/// `<Nested<T> as Column>::Output` is a `Col` of the `Element::Storage` of
+/// the values at each position of `T`.
+pub struct Nested<T>(PhantomData<T>);
+
+/// An iterator over a `Col`, zipping the iterators over each position
+pub struct ColIter<T> {
+    /// The iterators over each position
+    t: T,
+}
+
+/// A container storing the values at one position of a tuple column
+pub trait Storage: ColumnContainer {
+    /// The view on the stored values returned by the position accessors
+    type Slice: ?Sized;
+
+    /// A view on the stored values
+    fn as_slice(&self) -> &Self::Slice;
+
+    /// A mutable view on the stored values
+    fn as_mut_slice(&mut self) -> &mut Self::Slice;
+}
+
+/// A type that can be stored at a position of a `Nested` tuple column
+///
+/// Tuples are stored as nested tuple columns and primitive and std types in
+/// a `Vec`. Other types can be stored in a `Vec` by implementing `Element`:
+/// ```
+/// use column::tuple::Element;
+/// struct Point { x: f32, y: f32 }
+/// impl Element for Point {
+///     type Storage = Vec<Point>;
+/// }
+/// ```
+pub trait Element: Sized {
+    /// The storage of values of this type
+    type Storage: Storage<Element = Self>;
+}
+
+impl<T> Storage for Vec<T> {
+    type Slice = [T];
+
+    fn as_slice(&self) -> &[T] {
+        self
+    }
+
+    fn as_mut_slice(&mut self) -> &mut [T] {
+        self
+    }
+}
+
+// Implement `Element` for types stored in a `Vec`
+macro_rules! vec_elements {
+    ($($T:ty),*) => {
+        $(
+            impl Element for $T {
+                type Storage = Vec<$T>;
+            }
+        )*
+    }
+}
+
+vec_elements!(bool, char, u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64, String);
+
+impl<T> Element for Option<T> {
+    type Storage = Vec<Option<T>>;
+}
+
+impl<T, E> Element for Result<T, E> {
+    type Storage = Vec<Result<T, E>>;
+}
+
+impl<T> Element for Vec<T> {
+    type Storage = Vec<Vec<T>>;
+}
+
+impl<T: ?Sized> Element for Box<T> {
+    type Storage = Vec<Box<T>>;
+}
+
+impl<T: ?Sized> Element for Rc<T> {
+    type Storage = Vec<Rc<T>>;
+}
+
+impl<T: ?Sized> Element for Arc<T> {
+    type Storage = Vec<Arc<T>>;
+}
+
+impl<T, const N: usize> Element for [T; N] {
+    type Storage = Vec<[T; N]>;
+}
+
+impl<'a, T: ?Sized> Element for &'a T {
+    type Storage = Vec<&'a T>;
+}
+
+// macro for implementing n-ary tuple functions and operations. `$T` names
+// the element types in the `Column` and `Element` impls, and the storage or
+// iterator types at each position in all other impls.
 #[doc(hidden)]
 macro_rules! tuple_impls {
     ($(
         $Tuple:ident {
-            $(($idx:tt) -> $T:ident)+
+            $(($idx:tt, $col:ident, $col_mut:ident) -> $T:ident)+
         }
     )+) => {
         $(
 
-            impl<'column, $($T),+> Col<($(Vec<$T>),+,)> {
-                pub fn iter(&'column self) -> Col<($(::std::slice::Iter<'column, $T>),+,)> {
-                    Col { t: ($(self.t.$idx.iter()),+,) }
+            impl<$($T: Storage),+> Col<($($T),+,)> {
+                /// Iterate the elements
+                pub fn iter(&self) -> ColIter<($($T::Iter<'_>),+,)> {
+                    ColIter { t: ($(self.t.$idx.iter()),+,) }
                 }
-                pub fn iter_mut(&'column mut self) -> Col<($(::std::slice::IterMut<'column, $T>),+,)> {
-                    Col { t: ($(self.t.$idx.iter_mut()),+,) }
+                /// Iterate the elements mutably
+                pub fn iter_mut(&mut self) -> ColIter<($($T::IterMut<'_>),+,)> {
+                    ColIter { t: ($(self.t.$idx.iter_mut()),+,) }
                 }
-                pub fn len(&'column self) -> usize {
+                /// The number of elements
+                pub fn len(&self) -> usize {
                     self.t.0.len()
                 }
-                pub fn is_empty(&'column self) -> bool {
+                /// Test if there are no elements
+                pub fn is_empty(&self) -> bool {
                     self.t.0.is_empty()
                 }
+                /// Append an element
+                pub fn push(&mut self, element: ($($T::Element),+,)) {
+                    $(self.t.$idx.push(element.$idx);)+
+                }
+                /// Remove the last element and return it, or `None` if the
+                /// column is empty
+                pub fn pop(&mut self) -> Option<($($T::Element),+,)> {
+                    Some(($(self.t.$idx.pop()?),+,))
+                }
+                /// Insert an element at `index`, shifting all elements after it
+                pub fn insert(&mut self, index: usize, element: ($($T::Element),+,)) {
+                    $(self.t.$idx.insert(index, element.$idx);)+
+                }
+                /// Remove the element at `index`, shifting all elements after it
+                pub fn remove(&mut self, index: usize) -> ($($T::Element),+,) {
+                    ($(self.t.$idx.remove(index)),+,)
+                }
+                /// Remove the element at `index`, replacing it by the last element
+                pub fn swap_remove(&mut self, index: usize) -> ($($T::Element),+,) {
+                    ($(self.t.$idx.swap_remove(index)),+,)
+                }
+                /// Swap the elements at `a` and `b`
+                pub fn swap(&mut self, a: usize, b: usize) {
+                    $(self.t.$idx.swap(a, b);)+
+                }
+                /// Split the column in two at `at`, returning the elements from `at` on
+                pub fn split_off(&mut self, at: usize) -> Self {
+                    Col { t: ($(self.t.$idx.split_off(at)),+,) }
+                }
+                /// Move all elements of `other` to the end of this column
+                pub fn append(&mut self, other: &mut Self) {
+                    $(self.t.$idx.append(&mut other.t.$idx);)+
+                }
+                /// A copy of the element at `index`
+                pub fn index(&self, index: usize) -> ($($T::Element),+,) where $($T: CloneContainer),+ {
+                    ($(self.t.$idx.index(index)),+,)
+                }
+                /// Mutable references to the fields of the element at `index`
+                pub fn index_mut(&mut self, index: usize) -> ($($T::RefMut<'_>),+,) {
+                    ($(self.t.$idx.index_mut(index)),+,)
+                }
+                /// References to the fields of the element at `index`, or
+                /// `None` if `index` is out of bounds
+                pub fn get(&self, index: usize) -> Option<($($T::Ref<'_>),+,)> {
+                    Some(($(self.t.$idx.get(index)?),+,))
+                }
+                /// Remove all elements
+                pub fn clear(&mut self) {
+                    $(self.t.$idx.clear();)+
+                }
+                /// Reserve capacity for at least `additional` more elements
+                pub fn reserve(&mut self, additional: usize) {
+                    $(self.t.$idx.reserve(additional);)+
+                }
+                /// Shrink the capacity as much as possible
+                pub fn shrink_to_fit(&mut self) {
+                    $(self.t.$idx.shrink_to_fit();)+
+                }
+                /// The number of elements the column can hold without reallocating
+                pub fn capacity(&self) -> usize {
+                    self.t.0.capacity()
+                }
+                /// Shorten the column to `len` elements
+                pub fn truncate(&mut self, len: usize) {
+                    $(self.t.$idx.truncate(len);)+
+                }
+                $(
+                    /// The values at this position of all elements
+                    pub fn $col(&self) -> &$T::Slice {
+                        self.t.$idx.as_slice()
+                    }
+                    /// The values at this position of all elements, mutably
+                    pub fn $col_mut(&mut self) -> &mut $T::Slice {
+                        self.t.$idx.as_mut_slice()
+                    }
+                )+
+            }
+
+            impl<$($T: Storage),+> Default for Col<($($T),+,)> {
+                fn default() -> Self {
+                    Col { t: ($($T::with_capacity(0)),+,) }
+                }
             }
 
-            impl<'column, $($T),+> Column<'column> for Col<($($T),+,)> {
+            impl<$($T: Storage),+> ColumnContainer for Col<($($T),+,)> {
+                type Element = ($($T::Element),+,);
+                type Ref<'a> = ($($T::Ref<'a>),+,) where Self: 'a;
+                type RefMut<'a> = ($($T::RefMut<'a>),+,) where Self: 'a;
+                type Iter<'a> = ColIter<($($T::Iter<'a>),+,)> where Self: 'a;
+                type IterMut<'a> = ColIter<($($T::IterMut<'a>),+,)> where Self: 'a;
+
+                fn with_capacity(capacity: usize) -> Self {
+                    Col { t: ($($T::with_capacity(capacity)),+,) }
+                }
+                fn capacity(&self) -> usize {
+                    self.capacity()
+                }
+                fn reserve(&mut self, additional: usize) {
+                    self.reserve(additional)
+                }
+                fn shrink_to_fit(&mut self) {
+                    self.shrink_to_fit()
+                }
+                fn push(&mut self, element: Self::Element) {
+                    self.push(element)
+                }
+                fn pop(&mut self) -> Option<Self::Element> {
+                    self.pop()
+                }
+                fn insert(&mut self, index: usize, element: Self::Element) {
+                    self.insert(index, element)
+                }
+                fn remove(&mut self, index: usize) -> Self::Element {
+                    self.remove(index)
+                }
+                fn swap_remove(&mut self, index: usize) -> Self::Element {
+                    self.swap_remove(index)
+                }
+                fn swap(&mut self, a: usize, b: usize) {
+                    self.swap(a, b)
+                }
+                fn truncate(&mut self, len: usize) {
+                    self.truncate(len)
+                }
+                fn split_off(&mut self, at: usize) -> Self {
+                    self.split_off(at)
+                }
+                fn append(&mut self, other: &mut Self) {
+                    self.append(other)
+                }
+                fn clear(&mut self) {
+                    self.clear()
+                }
+                fn get(&self, index: usize) -> Option<Self::Ref<'_>> {
+                    self.get(index)
+                }
+                fn index_mut(&mut self, index: usize) -> Self::RefMut<'_> {
+                    self.index_mut(index)
+                }
+                fn iter(&self) -> Self::Iter<'_> {
+                    self.iter()
+                }
+                fn iter_mut(&mut self) -> Self::IterMut<'_> {
+                    self.iter_mut()
+                }
+            }
+
+            impl<$($T: Storage),+> Storage for Col<($($T),+,)> {
+                type Slice = Self;
+
+                fn as_slice(&self) -> &Self {
+                    self
+                }
+                fn as_mut_slice(&mut self) -> &mut Self {
+                    self
+                }
+            }
+
+            impl<$($T: Storage + CloneContainer),+> CloneContainer for Col<($($T),+,)> {
+                fn to_owned(reference: &Self::Ref<'_>) -> Self::Element {
+                    ($($T::to_owned(&reference.$idx)),+,)
+                }
+                fn to_owned_mut(reference: &Self::RefMut<'_>) -> Self::Element {
+                    ($($T::to_owned_mut(&reference.$idx)),+,)
+                }
+                fn index(&self, index: usize) -> Self::Element {
+                    self.index(index)
+                }
+            }
+
+            impl<$($T: Element),+> Element for ($($T),+,) {
+                type Storage = Col<($($T::Storage),+,)>;
+            }
+
+            impl<$($T),+> Column for Col<($($T),+,)> {
                 type Output = Col<($(Vec<$T>),+,)>;
                 fn new() -> Self::Output {
-                    Col { t: ($(Vec::<$T>::new()),+,) }
+                    Col::default()
+                }
+                fn with_capacity(capacity: usize) -> Self::Output {
+                    ColumnContainer::with_capacity(capacity)
+                }
+            }
+
+            impl<$($T: Element),+> Column for Nested<($($T),+,)> {
+                type Output = Col<($($T::Storage),+,)>;
+                fn new() -> Self::Output {
+                    Col::default()
                 }
                 fn with_capacity(capacity: usize) -> Self::Output {
-                    Col { t: ($(Vec::<$T>::with_capacity(capacity)),+,) }
+                    ColumnContainer::with_capacity(capacity)
+                }
+            }
+
+            impl<$($T: Storage),+> Len for Col<($($T),+,)> {
+                fn len(&self) -> usize {
+                    self.t.0.len()
+                }
+            }
+
+            impl<$($T),+> Materialize for Col<($($T),+,)>
+                where $($T: Materialize<Output = $T>),+
+            {
+                type Output = Self;
+                fn materialize<S: BitSet>(&self, bitmap: &S) -> Self {
+                    Col { t: ($(self.t.$idx.materialize(bitmap)),+,) }
                 }
             }
 
-            impl<'column, $($T),+> Extend<($($T),+,)> for Col<($(Vec<$T>),+,)> {
-                fn extend<T: IntoIterator<Item = ($($T),+,)>>(&mut self, iter: T) {
+            impl<$($T: Storage),+> Extend<($($T::Element),+,)> for Col<($($T),+,)> {
+                fn extend<T: IntoIterator<Item = ($($T::Element),+,)>>(&mut self, iter: T) {
                     for element in iter {
-                        ($(self.t.$idx.push(element.$idx)),+);
+                        self.push(element);
                     }
                 }
             }
-            impl<'column, $($T),+> IntoIterator for &'column Col<($(Vec<$T>),+,)> {
-                type Item = ($(&'column $T),+,);
-                type IntoIter = Col<($(::std::slice::Iter<'column, $T>),+,)>;
+            impl<$($T: Storage),+> IntoIterator for Col<($($T),+,)> {
+                type Item = ($($T::Element),+,);
+                type IntoIter = ColIter<($($T::IntoIter),+,)>;
                 fn into_iter(self) -> Self::IntoIter {
-                    self.iter()
+                    ColIter { t: ($(self.t.$idx.into_iter()),+,) }
                 }
             }
-            impl<'column, $($T),+> Iterator for Col<($(::std::slice::Iter<'column, $T>),+,)> {
-                type Item = ($(&'column $T),+,);
-                fn next(&mut self) -> Option<Self::Item> {
-                    let t = ($(self.t.$idx.next()),+,);
-                    $(
-                    if t.$idx.is_none() {
-                        return None;
-                    }
-                    )+
-                    let t = ($(t.$idx.unwrap()),+,);
-                    Some(t)
+            impl<'column, $($T: Storage),+> IntoIterator for &'column Col<($($T),+,)> {
+                type Item = ($($T::Ref<'column>),+,);
+                type IntoIter = ColIter<($($T::Iter<'column>),+,)>;
+                fn into_iter(self) -> Self::IntoIter {
+                    self.iter()
                 }
             }
-            impl<'column, $($T),+> IntoIterator for &'column mut Col<($(Vec<$T>),+,)> {
-                type Item = ($(&'column mut $T),+,);
-                type IntoIter = Col<($(::std::slice::IterMut<'column, $T>),+,)>;
+            impl<'column, $($T: Storage),+> IntoIterator for &'column mut Col<($($T),+,)> {
+                type Item = ($($T::RefMut<'column>),+,);
+                type IntoIter = ColIter<($($T::IterMut<'column>),+,)>;
                 fn into_iter(self) -> Self::IntoIter {
                     self.iter_mut()
                 }
             }
-            impl<'column, $($T),+> Iterator for Col<($(::std::slice::IterMut<'column, $T>),+,)> {
-                type Item = ($(&'column mut $T),+,);
+            impl<$($T: Iterator),+> Iterator for ColIter<($($T),+,)> {
+                type Item = ($($T::Item),+,);
                 fn next(&mut self) -> Option<Self::Item> {
-                    let t = ($(self.t.$idx.next()),+,);
-                    $(
-                    if t.$idx.is_none() {
-                        return None;
-                    }
-                    )+
-                    let t = ($(t.$idx.unwrap()),+,);
-                    Some(t)
+                    Some(($(self.t.$idx.next()?),+,))
+                }
+                fn size_hint(&self) -> (usize, Option<usize>) {
+                    self.t.0.size_hint()
+                }
+                fn nth(&mut self, n: usize) -> Option<Self::Item> {
+                    Some(($(self.t.$idx.nth(n)?),+,))
                 }
             }
+            impl<$($T: DoubleEndedIterator),+> DoubleEndedIterator for ColIter<($($T),+,)> {
+                fn next_back(&mut self) -> Option<Self::Item> {
+                    Some(($(self.t.$idx.next_back()?),+,))
+                }
+            }
+            impl<$($T: ExactSizeIterator),+> ExactSizeIterator for ColIter<($($T),+,)> {}
+            impl<$($T: FusedIterator),+> FusedIterator for ColIter<($($T),+,)> {}
         )+
     }
 }
 
 tuple_impls! {
     Tuple1 {
-        (0) -> A
+        (0, col0, col0_mut) -> A
     }
     Tuple2 {
-        (0) -> A
-        (1) -> B
+        (0, col0, col0_mut) -> A
+        (1, col1, col1_mut) -> B
     }
     Tuple3 {
-        (0) -> A
-        (1) -> B
-        (2) -> C
+        (0, col0, col0_mut) -> A
+        (1, col1, col1_mut) -> B
+        (2, col2, col2_mut) -> C
     }
     Tuple4 {
-        (0) -> A
-        (1) -> B
-        (2) -> C
-        (3) -> D
+        (0, col0, col0_mut) -> A
+        (1, col1, col1_mut) -> B
+        (2, col2, col2_mut) -> C
+        (3, col3, col3_mut) -> D
     }
     Tuple5 {
-        (0) -> A
-        (1) -> B
-        (2) -> C
-        (3) -> D
-        (4) -> E
+        (0, col0, col0_mut) -> A
+        (1, col1, col1_mut) -> B
+        (2, col2, col2_mut) -> C
+        (3, col3, col3_mut) -> D
+        (4, col4, col4_mut) -> E
     }
     Tuple6 {
-        (0) -> A
-        (1) -> B
-        (2) -> C
-        (3) -> D
-        (4) -> E
-        (5) -> F
+        (0, col0, col0_mut) -> A
+        (1, col1, col1_mut) -> B
+        (2, col2, col2_mut) -> C
+        (3, col3, col3_mut) -> D
+        (4, col4, col4_mut) -> E
+        (5, col5, col5_mut) -> F
     }
     Tuple7 {
-        (0) -> A
-        (1) -> B
-        (2) -> C
-        (3) -> D
-        (4) -> E
-        (5) -> F
-        (6) -> G
+        (0, col0, col0_mut) -> A
+        (1, col1, col1_mut) -> B
+        (2, col2, col2_mut) -> C
+        (3, col3, col3_mut) -> D
+        (4, col4, col4_mut) -> E
+        (5, col5, col5_mut) -> F
+        (6, col6, col6_mut) -> G
     }
     Tuple8 {
-        (0) -> A
-        (1) -> B
-        (2) -> C
-        (3) -> D
-        (4) -> E
-        (5) -> F
-        (6) -> G
-        (7) -> H
+        (0, col0, col0_mut) -> A
+        (1, col1, col1_mut) -> B
+        (2, col2, col2_mut) -> C
+        (3, col3, col3_mut) -> D
+        (4, col4, col4_mut) -> E
+        (5, col5, col5_mut) -> F
+        (6, col6, col6_mut) -> G
+        (7, col7, col7_mut) -> H
     }
     Tuple9 {
-        (0) -> A
-        (1) -> B
-        (2) -> C
-        (3) -> D
-        (4) -> E
-        (5) -> F
-        (6) -> G
-        (7) -> H
-        (8) -> I
+        (0, col0, col0_mut) -> A
+        (1, col1, col1_mut) -> B
+        (2, col2, col2_mut) -> C
+        (3, col3, col3_mut) -> D
+        (4, col4, col4_mut) -> E
+        (5, col5, col5_mut) -> F
+        (6, col6, col6_mut) -> G
+        (7, col7, col7_mut) -> H
+        (8, col8, col8_mut) -> I
     }
     Tuple10 {
-        (0) -> A
-        (1) -> B
-        (2) -> C
-        (3) -> D
-        (4) -> E
-        (5) -> F
-        (6) -> G
-        (7) -> H
-        (8) -> I
-        (9) -> J
+        (0, col0, col0_mut) -> A
+        (1, col1, col1_mut) -> B
+        (2, col2, col2_mut) -> C
+        (3, col3, col3_mut) -> D
+        (4, col4, col4_mut) -> E
+        (5, col5, col5_mut) -> F
+        (6, col6, col6_mut) -> G
+        (7, col7, col7_mut) -> H
+        (8, col8, col8_mut) -> I
+        (9, col9, col9_mut) -> J
     }
     Tuple11 {
-        (0) -> A
-        (1) -> B
-        (2) -> C
-        (3) -> D
-        (4) -> E
-        (5) -> F
-        (6) -> G
-        (7) -> H
-        (8) -> I
-        (9) -> J
-        (10) -> K
+        (0, col0, col0_mut) -> A
+        (1, col1, col1_mut) -> B
+        (2, col2, col2_mut) -> C
+        (3, col3, col3_mut) -> D
+        (4, col4, col4_mut) -> E
+        (5, col5, col5_mut) -> F
+        (6, col6, col6_mut) -> G
+        (7, col7, col7_mut) -> H
+        (8, col8, col8_mut) -> I
+        (9, col9, col9_mut) -> J
+        (10, col10, col10_mut) -> K
     }
     Tuple12 {
-        (0) -> A
-        (1) -> B
-        (2) -> C
-        (3) -> D
-        (4) -> E
-        (5) -> F
-        (6) -> G
-        (7) -> H
-        (8) -> I
-        (9) -> J
-        (10) -> K
-        (11) -> L
-    }
-}
\ No newline at end of file
+        (0, col0, col0_mut) -> A
+        (1, col1, col1_mut) -> B
+        (2, col2, col2_mut) -> C
+        (3, col3, col3_mut) -> D
+        (4, col4, col4_mut) -> E
+        (5, col5, col5_mut) -> F
+        (6, col6, col6_mut) -> G
+        (7, col7, col7_mut) -> H
+        (8, col8, col8_mut) -> I
+        (9, col9, col9_mut) -> J
+        (10, col10, col10_mut) -> K
+        (11, col11, col11_mut) -> L
+    }
+    Tuple13 {
+        (0, col0, col0_mut) -> A
+        (1, col1, col1_mut) -> B
+        (2, col2, col2_mut) -> C
+        (3, col3, col3_mut) -> D
+        (4, col4, col4_mut) -> E
+        (5, col5, col5_mut) -> F
+        (6, col6, col6_mut) -> G
+        (7, col7, col7_mut) -> H
+        (8, col8, col8_mut) -> I
+        (9, col9, col9_mut) -> J
+        (10, col10, col10_mut) -> K
+        (11, col11, col11_mut) -> L
+        (12, col12, col12_mut) -> M
+    }
+    Tuple14 {
+        (0, col0, col0_mut) -> A
+        (1, col1, col1_mut) -> B
+        (2, col2, col2_mut) -> C
+        (3, col3, col3_mut) -> D
+        (4, col4, col4_mut) -> E
+        (5, col5, col5_mut) -> F
+        (6, col6, col6_mut) -> G
+        (7, col7, col7_mut) -> H
+        (8, col8, col8_mut) -> I
+        (9, col9, col9_mut) -> J
+        (10, col10, col10_mut) -> K
+        (11, col11, col11_mut) -> L
+        (12, col12, col12_mut) -> M
+        (13, col13, col13_mut) -> N
+    }
+    Tuple15 {
+        (0, col0, col0_mut) -> A
+        (1, col1, col1_mut) -> B
+        (2, col2, col2_mut) -> C
+        (3, col3, col3_mut) -> D
+        (4, col4, col4_mut) -> E
+        (5, col5, col5_mut) -> F
+        (6, col6, col6_mut) -> G
+        (7, col7, col7_mut) -> H
+        (8, col8, col8_mut) -> I
+        (9, col9, col9_mut) -> J
+        (10, col10, col10_mut) -> K
+        (11, col11, col11_mut) -> L
+        (12, col12, col12_mut) -> M
+        (13, col13, col13_mut) -> N
+        (14, col14, col14_mut) -> O
+    }
+    Tuple16 {
+        (0, col0, col0_mut) -> A
+        (1, col1, col1_mut) -> B
+        (2, col2, col2_mut) -> C
+        (3, col3, col3_mut) -> D
+        (4, col4, col4_mut) -> E
+        (5, col5, col5_mut) -> F
+        (6, col6, col6_mut) -> G
+        (7, col7, col7_mut) -> H
+        (8, col8, col8_mut) -> I
+        (9, col9, col9_mut) -> J
+        (10, col10, col10_mut) -> K
+        (11, col11, col11_mut) -> L
+        (12, col12, col12_mut) -> M
+        (13, col13, col13_mut) -> N
+        (14, col14, col14_mut) -> O
+        (15, col15, col15_mut) -> P
+    }
+}
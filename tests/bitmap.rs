@@ -7,8 +7,12 @@
 
 #[macro_use] extern crate column_derive;
 extern crate column;
-use column::bitmap::FilteredCollection;
-use column::Column;
+use column::bitmap::{ArcFilteredCollection, Bitmap, CompressedBitmap, FilteredCollection, FilteredCollectionMut};
+use column::bitmap::{LengthMismatch, Materialize, OwnedFilteredCollection, RcFilteredCollection, Selection, SelectionVector};
+use std::rc::Rc;
+use std::sync::Arc;
+use column::{Column, Len};
+use column::tuple::Col;
 
 #[derive(Eq, PartialEq, Debug, Clone, Column)]
 pub struct Useless {
@@ -22,7 +26,7 @@ fn test() {
     let original = u.clone();
     let mut column = <Useless as Column>::with_capacity(u.len());
     column.extend(u.into_iter());
-    let mut bitmap_container = FilteredCollection::new(&column, column.len());
+    let mut bitmap_container = FilteredCollection::new(&column);
     let result: Vec<_> = column.iter().map(|e| UselessRef::to_owned(&e)).collect();
     assert_eq!(original, result);
     assert_eq!(column.len(), original.len());
@@ -65,10 +69,648 @@ fn test_retain() {
     }
     let mut column = <Useless as Column>::with_capacity(u.len());
     column.extend(u.into_iter());
-    let mut bitmap_container = FilteredCollection::new(&column, column.len());
+    let mut bitmap_container = FilteredCollection::new(&column);
     bitmap_container.retain(|u| u.a.trailing_zeros() > 0);
     println!("bitmap_container: {:?}", bitmap_container);
     assert_eq!(bitmap_container.len(), size as usize / 2);
     let as_vec: Vec<Useless> = bitmap_container.iter().map(|x| x.to_owned()).collect();
     assert_eq!(as_vec.len(), bitmap_container.len());
 }
+
+#[test]
+fn test_bitmap() {
+    let mut bitmap = Bitmap::zeros(130);
+    assert_eq!(bitmap.len(), 130);
+    assert_eq!(bitmap.count_ones(), 0);
+    assert_eq!(bitmap.iter_ones().next(), None);
+    for &index in &[0, 63, 64, 129] {
+        bitmap.set(index, true);
+    }
+    assert!(bitmap.get(63) && bitmap.get(64) && !bitmap.get(65));
+    assert_eq!(bitmap.iter_ones().collect::<Vec<_>>(), vec![0, 63, 64, 129]);
+    bitmap.set(63, false);
+    assert_eq!(bitmap.count_ones(), 3);
+    assert_eq!(bitmap.count_zeros(), 127);
+
+    let ones = Bitmap::ones(70);
+    assert_eq!(ones.count_ones(), 70);
+    assert_eq!(ones.iter_ones().last(), Some(69));
+    assert_eq!(ones.iter().rev().filter(|&b| b).count(), 70);
+
+    let collected: Bitmap = (0..100).map(|i| i % 3 == 0).collect();
+    assert_eq!(collected.len(), 100);
+    assert_eq!(collected.iter_ones().collect::<Vec<_>>(), (0..100).filter(|i| i % 3 == 0).collect::<Vec<_>>());
+    assert_eq!(collected.iter().take(4).collect::<Vec<_>>(), vec![true, false, false, true]);
+}
+
+#[test]
+fn test_bitmap_mutation() {
+    let mut bits: Vec<bool> = (0..200).map(|i| i % 3 == 0 || i % 7 == 0).collect();
+    let mut bitmap: Bitmap = bits.iter().cloned().collect();
+    for &(index, value) in &[(0, true), (63, false), (64, true), (130, true), (203, false)] {
+        bitmap.insert(index, value);
+        bits.insert(index, value);
+        assert_eq!(bitmap.iter().collect::<Vec<_>>(), bits);
+    }
+    for &index in &[0, 62, 63, 64, 127, 199] {
+        assert_eq!(bitmap.remove(index), bits.remove(index));
+        assert_eq!(bitmap.iter().collect::<Vec<_>>(), bits);
+        assert_eq!(bitmap.count_ones(), bits.iter().filter(|&&b| b).count());
+    }
+    assert_eq!(bitmap.rank(100), bits[..100].iter().filter(|&&b| b).count());
+
+    let mut tail = bitmap.split_off(65);
+    let mut tail_bits = bits.split_off(65);
+    assert_eq!(bitmap.iter().collect::<Vec<_>>(), bits);
+    assert_eq!(tail.iter().collect::<Vec<_>>(), tail_bits);
+    bitmap.append(&mut tail);
+    bits.append(&mut tail_bits);
+    assert!(tail.is_empty());
+    assert_eq!(bitmap.clone().into_iter().rev().collect::<Vec<_>>(), bits.iter().rev().cloned().collect::<Vec<_>>());
+    while let Some(bit) = bits.pop() {
+        assert_eq!(bitmap.pop(), Some(bit));
+    }
+    assert_eq!(bitmap.pop(), None);
+    assert_eq!(bitmap, Bitmap::zeros(0));
+
+    let mut bitmap = Bitmap::with_capacity(100);
+    assert!(bitmap.capacity() >= 100);
+    bitmap.reserve(200);
+    assert!(bitmap.capacity() >= 200);
+}
+
+#[test]
+fn test_bitmap_rank_after_mutation() {
+    fn check(bitmap: &Bitmap, bits: &[bool]) {
+        let ones: Vec<_> = (0..bits.len()).filter(|&i| bits[i]).collect();
+        for index in (0..=bits.len()).step_by(97) {
+            assert_eq!(bitmap.rank(index), ones.iter().filter(|&&i| i < index).count());
+        }
+        for rank in (0..=ones.len()).step_by(31) {
+            assert_eq!(bitmap.select(rank), ones.get(rank).cloned());
+        }
+    }
+    let mut bitmap = Bitmap::zeros(0);
+    let mut bits = vec![];
+    for i in 0..1500 {
+        bitmap.push(i % 3 == 0);
+        bits.push(i % 3 == 0);
+        assert_eq!(bitmap.rank(i + 1), bits.iter().filter(|&&b| b).count());
+    }
+    check(&bitmap, &bits);
+    bitmap.set(10, true);
+    bits[10] = true;
+    check(&bitmap, &bits);
+    bitmap.insert(600, true);
+    bits.insert(600, true);
+    check(&bitmap, &bits);
+    bitmap.remove(5);
+    bits.remove(5);
+    check(&bitmap, &bits);
+    bitmap.truncate(1030);
+    bits.truncate(1030);
+    check(&bitmap, &bits);
+    let mut tail = bitmap.split_off(512);
+    let mut tail_bits = bits.split_off(512);
+    check(&bitmap, &bits);
+    check(&tail, &tail_bits);
+    bitmap.append(&mut tail);
+    bits.append(&mut tail_bits);
+    check(&bitmap, &bits);
+    check(&bitmap.clone(), &bits);
+}
+
+#[test]
+fn test_bitmap_split_off_append() {
+    let bits: Vec<bool> = (0..200).map(|i| i % 5 == 0 || i % 9 == 0).collect();
+    for &at in &[0, 1, 63, 64, 65, 128, 199, 200] {
+        let mut head: Bitmap = bits.iter().cloned().collect();
+        let mut tail = head.split_off(at);
+        assert_eq!(head.iter().collect::<Vec<_>>(), &bits[..at]);
+        assert_eq!(tail.iter().collect::<Vec<_>>(), &bits[at..]);
+        assert_eq!(tail, bits[at..].iter().cloned().collect());
+        assert_eq!(tail.rank(tail.len()), bits[at..].iter().filter(|&&b| b).count());
+        head.append(&mut tail);
+        assert_eq!(head, bits.iter().cloned().collect());
+        assert!(tail.is_empty());
+    }
+}
+
+#[test]
+#[should_panic]
+fn test_bitmap_out_of_bounds() {
+    Bitmap::zeros(64).set(64, true);
+}
+
+#[test]
+fn test_sparse() {
+    let collection: Vec<usize> = (0..10_000).collect();
+    let mut filtered = FilteredCollection::new(&collection);
+    filtered.retain(|&e| *e % 1000 == 999);
+    assert_eq!(filtered.len(), 10);
+    assert_eq!(filtered.bitmap().len(), 10_000);
+    assert_eq!(filtered.iter().cloned().collect::<Vec<_>>(), (0..10).map(|i| i * 1000 + 999).collect::<Vec<_>>());
+}
+
+#[test]
+fn test_from_bitmap_length() {
+    let mut collection = vec![1, 2, 3];
+    let mismatch = LengthMismatch { len: 2, collection_len: 3 };
+    assert_eq!(FilteredCollection::try_from_bitmap(&collection, Bitmap::ones(2)).err(), Some(mismatch));
+    assert_eq!(FilteredCollectionMut::try_from_bitmap(&mut collection, Bitmap::ones(2)).err().map(|e| e.len), Some(2));
+    assert!(RcFilteredCollection::try_from_bitmap(Rc::new(collection.clone()), Bitmap::ones(4)).is_err());
+    let filtered = FilteredCollection::try_from_bitmap(&collection, Bitmap::ones(3)).unwrap();
+    assert_eq!(filtered.iter().cloned().collect::<Vec<_>>(), vec![1, 2, 3]);
+}
+
+#[test]
+#[should_panic(expected = "Bitmap length 2 does not match the collection length 3")]
+fn test_from_bitmap_length_panics() {
+    let mut collection = vec![1, 2, 3];
+    FilteredCollectionMut::from_bitmap(&mut collection, Bitmap::ones(2));
+}
+
+#[test]
+fn test_set_algebra() {
+    let mut column = <Useless as Column>::new();
+    column.extend((0..200).map(|a| Useless { a, b: if a % 3 == 0 { None } else { Some(a as i64) } }));
+    let mut even = FilteredCollection::new(&column);
+    even.retain(|u| u.a % 2 == 0);
+    let mut none = FilteredCollection::new(&column);
+    none.retain(|u| u.b.is_none());
+
+    let ids = |filtered: FilteredCollection<UselessColumn>| filtered.iter().map(|u| *u.a).collect::<Vec<_>>();
+    assert_eq!(ids(even.and(&none)), (0..200).filter(|a| a % 6 == 0).collect::<Vec<_>>());
+    assert_eq!(ids(even.or(&none)), (0..200).filter(|a| a % 2 == 0 || a % 3 == 0).collect::<Vec<_>>());
+    assert_eq!(ids(even.xor(&none)), (0..200).filter(|a| (a % 2 == 0) != (a % 3 == 0)).collect::<Vec<_>>());
+    assert_eq!(ids(even.and_not(&none)), (0..200).filter(|a| a % 2 == 0 && a % 3 != 0).collect::<Vec<_>>());
+    assert_eq!(ids(even.not()), (0..200).filter(|a| a % 2 == 1).collect::<Vec<_>>());
+    assert_eq!(even.not().len(), 100);
+    assert_eq!(&(&!even.bitmap() | even.bitmap()) ^ none.bitmap(), none.bitmap().not());
+    assert_eq!(&(even.bitmap() & none.bitmap()) | &Bitmap::ones(200), Bitmap::ones(200));
+}
+
+#[test]
+#[should_panic(expected = "Bitmap length 2 does not match the collection length 3")]
+fn test_set_algebra_length() {
+    let collection = vec![1, 2, 3];
+    FilteredCollection::new(&collection).and(&FilteredCollection::from_bitmap(&collection, Bitmap::ones(2)));
+}
+
+#[test]
+#[should_panic(expected = "different collections")]
+fn test_set_algebra_collection() {
+    let (a, b) = (vec![1, 2, 3], vec![1, 2, 3]);
+    FilteredCollection::new(&a).or(&FilteredCollection::new(&b));
+}
+
+#[test]
+fn test_iter_mut() {
+    let mut column = <Useless as Column>::new();
+    column.extend((0..100).map(|a| Useless { a, b: None }));
+    let bitmap = {
+        let mut filtered = FilteredCollection::new(&column);
+        filtered.retain(|u| u.a % 10 == 0);
+        filtered.into_bitmap()
+    };
+    {
+        let mut filtered = FilteredCollectionMut::from_bitmap(&mut column, bitmap);
+        assert_eq!(filtered.len(), 10);
+        for u in filtered.iter_mut() {
+            *u.b = Some(*u.a as i64);
+        }
+        filtered.retain(|u| *u.a < 50);
+        for u in &mut filtered {
+            *u.a += 1;
+        }
+    }
+    let updated: Vec<_> = column.iter().filter(|u| u.b.is_some()).map(|u| (*u.a, *u.b)).collect();
+    assert_eq!(updated, (0..10).map(|i| (i * 10 + if i < 5 { 1 } else { 0 }, Some(i as i64 * 10))).collect::<Vec<_>>());
+    assert_eq!(column.iter().filter(|u| u.b.is_none()).count(), 90);
+}
+
+/// A pipeline stage holding its input
+struct Stage {
+    input: OwnedFilteredCollection<UselessColumn>,
+}
+
+fn useless(size: u64) -> UselessColumn {
+    let mut column = <Useless as Column>::new();
+    column.extend((0..size).map(|a| Useless { a, b: None }));
+    column
+}
+
+#[test]
+fn test_owned() {
+    let column = useless(10);
+    let mut stage = Stage { input: OwnedFilteredCollection::new(column) };
+    stage.input.retain(|u| u.a % 2 == 0);
+    assert_eq!(stage.input.len(), 5);
+    assert_eq!(stage.input.iter().map(|u| *u.a).collect::<Vec<_>>(), vec![0, 2, 4, 6, 8]);
+    let column = stage.input.into_inner();
+    assert_eq!(column.len(), 10);
+}
+
+#[test]
+fn test_shared() {
+    let column = Rc::new(useless(10));
+    let mut small = RcFilteredCollection::new(Rc::clone(&column));
+    small.retain(|u| *u.a < 3);
+    let mut odd = small.clone();
+    odd.retain(|u| u.a % 2 == 1);
+    assert_eq!(Rc::strong_count(&column), 3);
+    assert_eq!(small.iter().map(|u| *u.a).collect::<Vec<_>>(), vec![0, 1, 2]);
+    assert_eq!(odd.iter().map(|u| *u.a).collect::<Vec<_>>(), vec![1]);
+    let both = RcFilteredCollection::from_bitmap(Rc::clone(&column), small.bitmap().and_not(odd.bitmap()));
+    assert_eq!(both.iter().map(|u| *u.a).collect::<Vec<_>>(), vec![0, 2]);
+}
+
+#[test]
+fn test_threads() {
+    let column = Arc::new(useless(1000));
+    let handles: Vec<_> = (0..4).map(|i| {
+        let mut filtered = ArcFilteredCollection::new(Arc::clone(&column));
+        ::std::thread::spawn(move || {
+            filtered.retain(|u| u.a % 4 == i);
+            filtered.iter().map(|u| *u.a).sum::<u64>()
+        })
+    }).collect();
+    let sum: u64 = handles.into_iter().map(|h| h.join().unwrap()).sum();
+    assert_eq!(sum, (0..1000).sum::<u64>());
+}
+
+#[derive(PartialEq, Debug, Clone, Column)]
+pub enum Value {
+    Missing,
+    Int(i64),
+    Text(String),
+}
+
+#[test]
+fn test_selection_vector() {
+    let mut selection = SelectionVector::new(100);
+    for index in (0..100).filter(|i| i % 40 == 1) {
+        selection.push(index);
+    }
+    assert_eq!(selection.as_slice(), &[1, 41, 81]);
+    assert_eq!(selection.len(), 3);
+    assert_eq!(selection.rows(), 100);
+    assert!(selection.contains(41) && !selection.contains(40));
+    let bitmap = Bitmap::from(&selection);
+    assert_eq!(bitmap.len(), 100);
+    assert_eq!(bitmap.iter_ones().collect::<Vec<_>>(), vec![1, 41, 81]);
+    assert_eq!(SelectionVector::from(&bitmap), selection);
+}
+
+#[test]
+#[should_panic(expected = "strictly ascending")]
+fn test_selection_vector_unsorted() {
+    SelectionVector::from_indices(vec![3, 1], 4);
+}
+
+#[test]
+fn test_selection() {
+    let sparse: Bitmap = (0..1000).map(|i| i % 100 == 0).collect();
+    let selection = Selection::from_bitmap(sparse.clone());
+    assert!(selection.is_vector());
+    assert_eq!(selection.len(), 10);
+    assert_eq!(selection.rows(), 1000);
+    assert!(selection.contains(300) && !selection.contains(301));
+    assert_eq!(selection.clone().into_bitmap(), sparse);
+
+    let dense: Bitmap = (0..1000).map(|i| i % 2 == 0).collect();
+    let selection = Selection::from_selection_vector(SelectionVector::from(&dense));
+    assert!(!selection.is_vector());
+    assert_eq!(selection.iter().collect::<Vec<_>>(), dense.iter_ones().collect::<Vec<_>>());
+    assert_eq!(selection.into_selection_vector().len(), 500);
+}
+
+#[test]
+fn test_gather() {
+    let column = useless(1000);
+    let mut filtered = FilteredCollection::new(&column);
+    filtered.retain(|u| u.a % 250 == 3);
+    let selection = Selection::from_bitmap(filtered.into_bitmap());
+    assert!(selection.is_vector());
+    let gathered = column.gather(&selection);
+    assert_eq!(gathered.a, vec![3, 253, 503, 753]);
+    assert_eq!(column.gather(&SelectionVector::from_indices(vec![999], 1000)).a, vec![999]);
+    assert!(column.gather(vec![]).is_empty());
+
+    let mut values = <Value as Column>::new();
+    values.extend(vec![Value::Int(1), Value::Missing, Value::Text("a".to_owned()), Value::Int(2)]);
+    let gathered = values.gather(&SelectionVector::from_indices(vec![0, 2, 3], 4));
+    assert_eq!(gathered.iter().map(|v| v.to_owned()).collect::<Vec<_>>(), vec![Value::Int(1), Value::Text("a".to_owned()), Value::Int(2)]);
+}
+
+#[derive(PartialEq, Debug, Clone, Column)]
+#[column(no_slices)]
+pub struct Tagged {
+    #[column(nested)]
+    value: Value,
+    #[column(skip)]
+    cache: Option<u8>,
+    tag: u32,
+}
+
+fn tagged() -> TaggedColumn {
+    let values = vec![Value::Int(1), Value::Missing, Value::Text("a".to_owned()), Value::Int(2), Value::Text("b".to_owned())];
+    values.into_iter().enumerate().map(|(tag, value)| Tagged { value, cache: Some(1), tag: tag as u32 }).collect()
+}
+
+#[test]
+fn test_materialize() {
+    let column = useless(100);
+    let mut filtered = FilteredCollection::new(&column);
+    filtered.retain(|u| u.a % 30 == 0);
+    let dense: UselessColumn = filtered.materialize();
+    assert_eq!(dense.a, vec![0, 30, 60, 90]);
+    assert_eq!(dense.b, vec![None; 4]);
+
+    let column = tagged();
+    let bitmap: Bitmap = (0..5).map(|tag| tag != 1 && tag != 4).collect();
+    let filtered = FilteredCollection::from_bitmap(&column, bitmap);
+    let dense = filtered.materialize();
+    assert_eq!(dense.tag, vec![0, 2, 3]);
+    assert_eq!(dense.iter().map(|t| t.to_owned()).collect::<Vec<_>>(),
+               vec![Tagged { value: Value::Int(1), cache: None, tag: 0 },
+                    Tagged { value: Value::Text("a".to_owned()), cache: None, tag: 2 },
+                    Tagged { value: Value::Int(2), cache: None, tag: 3 }]);
+
+    let mut values: ValueColumn = tagged().iter().map(|t| t.value.to_owned()).collect();
+    values.push(Value::Int(3));
+    let mut dense = values.materialize(&(0..6).map(|i| i != 0 && i != 4).collect::<Bitmap>());
+    assert_eq!(dense.v1_0, vec![2, 3]);
+    assert_eq!(dense.v2_0, vec!["a".to_owned()]);
+    dense.push(Value::Text("c".to_owned()));
+    assert_eq!(dense.iter().map(|v| v.to_owned()).collect::<Vec<_>>(),
+               vec![Value::Missing, Value::Text("a".to_owned()), Value::Int(2), Value::Int(3), Value::Text("c".to_owned())]);
+
+    let mut tuples = <Col<(u8, String)> as Column>::new();
+    tuples.extend(vec![(1, "a".to_owned()), (2, "b".to_owned()), (3, "c".to_owned())]);
+    let bitmap: Bitmap = vec![true, false, true].into_iter().collect();
+    let dense = tuples.materialize(&bitmap);
+    assert_eq!(dense.iter().map(|(a, b)| (*a, b.clone())).collect::<Vec<_>>(), vec![(1, "a".to_owned()), (3, "c".to_owned())]);
+
+    let shared = Rc::new(useless(10));
+    let mut filtered = RcFilteredCollection::new(shared);
+    filtered.retain(|u| *u.a > 7);
+    assert_eq!(filtered.materialize().a, vec![8, 9]);
+}
+
+#[test]
+fn test_retain_by_bitmap() {
+    let mut column = useless(100);
+    let bitmap: Bitmap = (0..100).map(|i| i % 7 == 3).collect();
+    column.retain_by_bitmap(&bitmap);
+    assert_eq!(column.a, (0..100).filter(|i| i % 7 == 3).collect::<Vec<_>>());
+    assert_eq!(column.b.len(), column.a.len());
+
+    let mut column = tagged();
+    column.retain_by_bitmap(&vec![false, true, true, false, true].into_iter().collect::<Bitmap>());
+    assert_eq!(column.tag, vec![1, 2, 4]);
+    assert_eq!(column.iter().map(|t| t.value.to_owned()).collect::<Vec<_>>(),
+               vec![Value::Missing, Value::Text("a".to_owned()), Value::Text("b".to_owned())]);
+
+    let mut column = useless(100);
+    let compressed: CompressedBitmap = (0..100).map(|i| i >= 90 || i % 20 == 5).collect();
+    column.retain_by_bitmap(&compressed);
+    assert_eq!(column.a, vec![5, 25, 45, 65, 85, 90, 91, 92, 93, 94, 95, 96, 97, 98, 99]);
+
+    let mut column: ValueColumn = tagged().iter().map(|t| t.value.to_owned()).collect();
+    column.retain_by_bitmap(&vec![true, false, false, true, true].into_iter().collect::<CompressedBitmap>());
+    assert_eq!(column.iter().map(|v| v.to_owned()).collect::<Vec<_>>(),
+               vec![Value::Int(1), Value::Int(2), Value::Text("b".to_owned())]);
+}
+
+#[test]
+#[should_panic(expected = "must match the column length")]
+fn test_retain_by_bitmap_length() {
+    useless(10).retain_by_bitmap(&Bitmap::ones(9));
+}
+
+#[test]
+#[should_panic(expected = "must match the column length")]
+fn test_materialize_length() {
+    useless(10).materialize(&Bitmap::ones(9));
+}
+
+/// Bitmaps of different densities and lengths around word and block bounds
+fn rank_select_bitmaps() -> Vec<Vec<bool>> {
+    let mut bitmaps = vec![vec![], vec![false; 100], vec![true; 512], vec![true; 1025]];
+    for &len in &[63, 64, 65, 511, 512, 513, 2000] {
+        bitmaps.push((0..len).map(|i| i % 3 == 1).collect());
+        bitmaps.push((0..len).map(|i| (i * 7919) % 97 < 2).collect());
+    }
+    bitmaps
+}
+
+#[test]
+fn test_rank_select() {
+    for bits in rank_select_bitmaps() {
+        let bitmap: Bitmap = bits.iter().cloned().collect();
+        let ones: Vec<usize> = (0..bits.len()).filter(|&i| bits[i]).collect();
+        for index in 0..bits.len() + 1 {
+            assert_eq!(bitmap.rank(index), bits[..index].iter().filter(|&&b| b).count());
+        }
+        for (rank, &index) in ones.iter().enumerate() {
+            assert_eq!(bitmap.select(rank), Some(index));
+        }
+        assert_eq!(bitmap.select(ones.len()), None);
+        assert_eq!(bitmap.first_set(), ones.first().cloned());
+        assert_eq!(bitmap.last_set(), ones.last().cloned());
+        assert_eq!(bitmap.iter_indices().collect::<Vec<_>>(), ones);
+        assert_eq!(bitmap.iter_indices().rev().collect::<Vec<_>>(), ones.iter().rev().cloned().collect::<Vec<_>>());
+        for n in 0..ones.len() + 2 {
+            let mut iter = bitmap.iter_indices();
+            assert_eq!(iter.nth(n), ones.get(n).cloned());
+            assert_eq!(iter.len(), ones.len().saturating_sub(n + 1));
+            assert_eq!(bitmap.iter_ones().nth(n), ones.get(n).cloned());
+        }
+    }
+}
+
+#[test]
+fn test_rank_select_updates() {
+    let mut bitmap = Bitmap::zeros(1000);
+    assert_eq!(bitmap.select(0), None);
+    bitmap.set(700, true);
+    assert_eq!(bitmap.rank(1000), 1);
+    assert_eq!(bitmap.select(0), Some(700));
+    bitmap.set(10, true);
+    bitmap.push(true);
+    assert_eq!(bitmap.select(0), Some(10));
+    assert_eq!(bitmap.select(2), Some(1000));
+    assert_eq!(bitmap.rank(701), 2);
+
+    let mut iter = bitmap.iter_indices();
+    assert_eq!(iter.next_back(), Some(1000));
+    assert_eq!(iter.nth(1), Some(700));
+    assert_eq!(iter.next(), None);
+    assert_eq!(iter.next_back(), None);
+}
+
+#[test]
+fn test_filtered_positions() {
+    let column = useless(1000);
+    let mut filtered = FilteredCollection::new(&column);
+    filtered.retain(|u| u.a % 7 == 0);
+    assert_eq!(filtered.get(10).map(|u| *u.a), Some(70));
+    assert_eq!(filtered.get(143).map(|u| *u.a), None);
+    // The third page of ten visible elements
+    let page: Vec<_> = filtered.iter().skip(20).take(10).map(|u| *u.a).collect();
+    assert_eq!(page, (20..30).map(|i| i * 7).collect::<Vec<_>>());
+    assert_eq!(filtered.iter().nth(142).map(|u| *u.a), Some(994));
+    assert_eq!(filtered.bitmap().rank(100), 15);
+}
+
+/// Bitmaps spanning several chunks with sparse, dense, fragmented and empty
+/// regions, so that every kind of container is used
+fn compressed_patterns() -> Vec<Bitmap> {
+    let len = 4 * 65536 + 1000;
+    let mut state = 7u64;
+    let mut random = move || {
+        state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        state >> 33
+    };
+    let mut patterns = Vec::new();
+    for seed in 0..3 {
+        let bitmap: Bitmap = (0..len).map(|i| match (i + seed * 20000) / 65536 {
+            0 => random() % 1000 == 0,
+            1 => random() % 5 != 0,
+            2 => i % 1000 < 600,
+            3 => false,
+            _ => random() % 2 == 0,
+        }).collect();
+        patterns.push(bitmap);
+    }
+    patterns.push(Bitmap::ones(len));
+    patterns.push(Bitmap::zeros(len));
+    patterns
+}
+
+#[test]
+fn test_compressed() {
+    let patterns = compressed_patterns();
+    for bitmap in &patterns {
+        let compressed = CompressedBitmap::from(bitmap);
+        assert_eq!(compressed.len(), bitmap.len());
+        assert_eq!(compressed.count_ones(), bitmap.count_ones());
+        assert_eq!(compressed.iter_ones().collect::<Vec<_>>(), bitmap.iter_ones().collect::<Vec<_>>());
+        assert!(compressed.iter().eq(bitmap.iter()));
+        assert_eq!(Bitmap::from(&compressed), *bitmap);
+        assert_eq!(compressed.first_set(), bitmap.first_set());
+        assert_eq!(compressed.last_set(), bitmap.last_set());
+        for index in (0..bitmap.len()).step_by(997).chain(Some(bitmap.len())) {
+            assert_eq!(compressed.rank(index), bitmap.rank(index));
+        }
+        for rank in (0..bitmap.count_ones() + 2).step_by(991) {
+            assert_eq!(compressed.select(rank), bitmap.select(rank));
+            assert_eq!(compressed.iter_ones().nth(rank), bitmap.iter_ones().nth(rank));
+        }
+        let mut iter = compressed.iter_ones();
+        let mut expected = bitmap.iter_ones();
+        for n in (0..50).map(|i| i * 37) {
+            assert_eq!(iter.nth(n), expected.nth(n));
+        }
+        assert_eq!(Bitmap::from(&!&compressed), !bitmap);
+    }
+    for left in &patterns {
+        for right in &patterns {
+            let (a, b) = (CompressedBitmap::from(left), CompressedBitmap::from(right));
+            assert_eq!(Bitmap::from(&(&a & &b)), left & right);
+            assert_eq!(Bitmap::from(&(&a | &b)), left | right);
+            assert_eq!(Bitmap::from(&(&a ^ &b)), left ^ right);
+            assert_eq!(Bitmap::from(&a.and_not(&b)), left.and_not(right));
+            assert_eq!(&a & &b, CompressedBitmap::from(&(left & right)));
+        }
+    }
+}
+
+#[test]
+fn test_compressed_updates() {
+    let mut compressed = CompressedBitmap::zeros(200_000);
+    let mut bitmap = Bitmap::zeros(200_000);
+    // Grow an array beyond its capacity, fragment it and shrink it again
+    for index in (0..70_000).step_by(3).chain((100_000..140_000).step_by(2)) {
+        compressed.set(index, true);
+        bitmap.set(index, true);
+    }
+    for index in (0..70_000).step_by(5).chain(100_000..139_000) {
+        compressed.set(index, false);
+        bitmap.set(index, false);
+    }
+    assert_eq!(Bitmap::from(&compressed), bitmap);
+    assert_eq!(compressed, CompressedBitmap::from(&bitmap));
+
+    // Split and merge runs
+    let mut compressed = CompressedBitmap::ones(70_000);
+    let mut bitmap = Bitmap::ones(70_000);
+    for index in (0..70_000).step_by(2) {
+        compressed.set(index, false);
+        bitmap.set(index, false);
+    }
+    for index in (0..70_000).step_by(4).chain(Some(65_535)) {
+        compressed.set(index, true);
+        bitmap.set(index, true);
+    }
+    assert_eq!(Bitmap::from(&compressed), bitmap);
+    assert!(compressed.get(69_999));
+    assert!(!compressed.get(69_998));
+
+    let pushed: CompressedBitmap = bitmap.iter().collect();
+    assert_eq!(pushed, compressed);
+}
+
+#[test]
+#[should_panic(expected = "Bitmaps of different lengths")]
+fn test_compressed_lengths() {
+    let _ = &CompressedBitmap::ones(10) & &CompressedBitmap::ones(11);
+}
+
+#[test]
+fn test_compressed_filtered() {
+    let column = useless(200_000);
+    let mut filtered = FilteredCollection::from_bitmap(&column, CompressedBitmap::ones(column.len()));
+    filtered.retain(|u| u.a % 1000 == 0);
+    assert_eq!(filtered.len(), 200);
+    assert_eq!(filtered.iter().map(|u| *u.a).nth(150), Some(150_000));
+    assert_eq!(filtered.get(3).map(|u| *u.a), Some(3000));
+    assert_eq!(filtered.bitmap().rank(100_000), 100);
+
+    let mut odd = FilteredCollection::from_bitmap(&column, CompressedBitmap::ones(column.len()));
+    odd.retain(|u| u.a % 2 == 1);
+    assert_eq!(filtered.and(&odd).len(), 0);
+    assert_eq!(filtered.or(&odd).len(), 100_200);
+    assert_eq!(filtered.not().len(), 199_800);
+    assert_eq!(filtered.materialize().a, (0..200).map(|i| i * 1000).collect::<Vec<_>>());
+
+    let bitmap = filtered.into_bitmap();
+    let owned: RcFilteredCollection<_, _> = OwnedFilteredCollection::from_bitmap(Rc::new(column), bitmap);
+    assert_eq!(owned.iter().map(|u| *u.a).last(), Some(199_000));
+}
+
+#[test]
+fn test_try_new() {
+    let column = useless(10);
+    assert_eq!(Len::len(&column), 10);
+    assert_eq!(FilteredCollection::try_new(&column, 10).map(|f| f.len()), Ok(10));
+    let error = FilteredCollection::try_new(&column, 12).err();
+    assert_eq!(error, Some(LengthMismatch { len: 12, collection_len: 10 }));
+    assert_eq!(error.unwrap().to_string(), "length 12 does not match the collection length 10");
+
+    let mut values = vec![1, 2, 3];
+    assert!(FilteredCollectionMut::try_new(&mut values, 2).is_err());
+    let mut filtered = FilteredCollectionMut::try_new(&mut values, 3).unwrap();
+    assert_eq!(filtered.iter_mut().count(), 3);
+    assert!(ArcFilteredCollection::try_new(Arc::new(tagged()), 5).is_ok());
+    assert!(RcFilteredCollection::try_new(Rc::new(vec![1]), 0).is_err());
+
+    let mut tuples = <Col<(u8, u16)> as Column>::new();
+    tuples.extend(vec![(1, 2), (3, 4)]);
+    assert_eq!(FilteredCollection::new(&tuples).len(), 2);
+    assert!(!Len::is_empty(&tuples));
+    let values: ValueColumn = vec![Value::Missing, Value::Int(1)].into_iter().collect();
+    assert_eq!(FilteredCollection::new(&values).len(), 2);
+    assert!(Len::is_empty(&[0u8; 0][..]));
+}
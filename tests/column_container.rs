@@ -0,0 +1,114 @@
+// Copyright 2017 columnar-rs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+#[macro_use] extern crate column_derive;
+extern crate column;
+use column::{Column, ColumnContainer};
+use column::tuple::Nested;
+
+#[derive(PartialEq, Debug, Clone, Column)]
+pub struct Data {
+    id: usize,
+    val: f64,
+}
+
+#[derive(PartialEq, Debug, Clone, Column)]
+#[column(no_slices)]
+pub struct Outer {
+    #[column(nested)]
+    data: Data,
+    flag: bool,
+}
+
+#[derive(Eq, PartialEq, Debug, Clone, Copy, Column)]
+pub enum Event {
+    Click { x: u32, y: u32 },
+    Key(char),
+    Quit,
+}
+
+mod other {
+    #[derive(Column)]
+    pub struct Sample {
+        pub value: f64,
+        weight: f64,
+    }
+
+    pub fn sample(value: f64) -> Sample {
+        Sample { value, weight: 1. }
+    }
+}
+
+/// Fill a new column of `T` with `elements`
+fn fill<T: Column, I: IntoIterator<Item = <T::Output as ColumnContainer>::Element>>(elements: I) -> T::Output {
+    let mut column = T::with_capacity(0);
+    for element in elements {
+        column.push(element);
+    }
+    column
+}
+
+/// The positions of the elements of `column` accepted by `f`
+fn positions<C: ColumnContainer, F: FnMut(C::Ref<'_>) -> bool>(column: &C, mut f: F) -> Vec<usize> {
+    (0..column.len()).filter(|&index| f(column.get(index).unwrap())).collect()
+}
+
+#[test]
+fn test_struct() {
+    let mut column = fill::<Data, _>((0..5).map(|id| Data { id, val: id as f64 }));
+    assert_eq!(positions(&column, |data| *data.id % 2 == 0), vec![0, 2, 4]);
+    assert_eq!(ColumnContainer::get(&column, 1).map(|data| *data.val), Some(1.));
+    assert!(ColumnContainer::get(&column, 5).is_none());
+    *ColumnContainer::index_mut(&mut column, 1).val = 3.;
+    assert_eq!(ColumnContainer::iter(&column).rev().map(|data| *data.val).collect::<Vec<_>>(), vec![4., 3., 2., 3., 0.]);
+    ColumnContainer::truncate(&mut column, 2);
+    assert_eq!(column.len(), 2);
+    ColumnContainer::clear(&mut column);
+    assert!(column.is_empty());
+}
+
+#[test]
+fn test_nested() {
+    let column = fill::<Outer, _>((0..4).map(|id| Outer { data: Data { id, val: 0. }, flag: id > 1 }));
+    assert_eq!(positions(&column, |outer| *outer.flag), vec![2, 3]);
+    let outer = ColumnContainer::get(&column, 3).unwrap();
+    assert_eq!(*outer.data.id, 3);
+    assert!(ColumnContainer::get(&column, 4).is_none());
+}
+
+#[test]
+fn test_enum() {
+    let events = vec![Event::Key('a'), Event::Click { x: 1, y: 2 }, Event::Quit, Event::Key('b')];
+    let column = fill::<Event, _>(events);
+    assert_eq!(positions(&column, |event| matches!(event, EventRef::Key(_))), vec![0, 3]);
+    match ColumnContainer::get(&column, 1) {
+        Some(EventRef::Click { x, y }) => assert_eq!((*x, *y), (1, 2)),
+        _ => panic!("Expected a click"),
+    }
+    assert!(ColumnContainer::get(&column, 4).is_none());
+}
+
+#[test]
+fn test_private() {
+    let column = fill::<other::Sample, _>((0..3).map(|value| other::sample(value as f64)));
+    assert_eq!(positions(&column, |sample| *sample.value > 0.), vec![1, 2]);
+}
+
+#[test]
+fn test_tuple() {
+    let column = fill::<Nested<(u64, (bool, char))>, _>(vec![(1, (true, 'a')), (2, (false, 'b'))]);
+    assert_eq!(positions(&column, |(_, (flag, _))| *flag), vec![0]);
+    assert_eq!(ColumnContainer::get(&column, 1), Some((&2, (&false, &'b'))));
+}
+
+#[test]
+fn test_vec() {
+    let mut column: Vec<u64> = ColumnContainer::with_capacity(2);
+    ColumnContainer::push(&mut column, 3);
+    ColumnContainer::push(&mut column, 4);
+    assert_eq!(positions(&column, |value| *value > 3), vec![1]);
+}
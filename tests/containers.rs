@@ -0,0 +1,277 @@
+// Copyright 2017 columnar-rs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+#[macro_use] extern crate column_derive;
+extern crate column;
+use column::bitmap::{Bitmap, Materialize};
+use column::containers::{ArrayColumn, BoxColumn, OptionColumn, ResultColumn, StringColumn, VecColumn};
+use column::{CloneContainer, Column, ColumnContainer, Len};
+use std::iter::FromIterator;
+
+#[derive(PartialEq, Debug, Clone, Column)]
+pub struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[derive(PartialEq, Debug, Clone, Column)]
+#[column(no_slices)]
+pub struct Record {
+    id: u64,
+    #[column(nested)]
+    name: String,
+    #[column(nested)]
+    score: Option<u64>,
+    #[column(nested)]
+    tags: Vec<u32>,
+    #[column(nested)]
+    origin: Box<Point>,
+    #[column(nested)]
+    position: [f64; 3],
+    #[column(nested)]
+    status: Result<u64, String>,
+}
+
+fn record(id: u64) -> Record {
+    Record {
+        id,
+        name: format!("record {}", id),
+        score: if id.is_multiple_of(2) { Some(id * 10) } else { None },
+        tags: (0..id as u32 % 4).collect(),
+        origin: Box::new(Point { x: id as i32, y: -(id as i32) }),
+        position: [id as f64, 0.5, -1.],
+        status: if id.is_multiple_of(3) { Err(format!("error {}", id)) } else { Ok(id) },
+    }
+}
+
+fn to_vec<C: CloneContainer>(column: &C) -> Vec<C::Element> {
+    column.iter().map(|element| C::to_owned(&element)).collect()
+}
+
+/// Apply the same operations to `column` and a `Vec`, and compare the results
+fn check<C>(mut column: C, mut elements: Vec<C::Element>)
+    where C: CloneContainer + Default + Extend<<C as ColumnContainer>::Element>, C::Element: Clone + PartialEq + ::std::fmt::Debug
+{
+    assert!(elements.len() >= 5);
+    column.extend(elements.clone());
+    assert_eq!(column.len(), elements.len());
+    assert_eq!(to_vec(&column), elements);
+    for (index, element) in elements.iter().enumerate() {
+        assert_eq!(&column.index(index), element);
+    }
+    assert!(column.get(elements.len()).is_none());
+    assert_eq!(column.iter().len(), elements.len());
+    assert_eq!(column.iter().rev().map(|element| C::to_owned(&element)).collect::<Vec<_>>(),
+               elements.iter().rev().cloned().collect::<Vec<_>>());
+    assert_eq!(C::to_owned_mut(&column.index_mut(1)), elements[1]);
+
+    let element = column.remove(1);
+    assert_eq!(element, elements.remove(1));
+    column.insert(3, element.clone());
+    elements.insert(3, element);
+    column.insert(0, elements[2].clone());
+    elements.insert(0, elements[2].clone());
+    column.insert(column.len(), elements[1].clone());
+    elements.push(elements[1].clone());
+    assert_eq!(to_vec(&column), elements);
+
+    column.swap(0, 4);
+    elements.swap(0, 4);
+    column.swap(3, 1);
+    elements.swap(3, 1);
+    assert_eq!(to_vec(&column), elements);
+    assert_eq!(column.swap_remove(1), elements.swap_remove(1));
+    assert_eq!(column.swap_remove(column.len() - 1), elements.swap_remove(elements.len() - 1));
+    assert_eq!(to_vec(&column), elements);
+
+    let mut tail = column.split_off(2);
+    let mut tail_elements = elements.split_off(2);
+    assert_eq!(to_vec(&column), elements);
+    assert_eq!(to_vec(&tail), tail_elements);
+    tail.append(&mut column);
+    tail_elements.append(&mut elements);
+    assert!(column.is_empty());
+    assert_eq!(to_vec(&tail), tail_elements);
+    let mut column = tail;
+    let mut elements = tail_elements;
+
+    assert_eq!(column.pop(), elements.pop());
+    column.truncate(3);
+    elements.truncate(3);
+    assert_eq!(to_vec(&column), elements);
+    assert_eq!(column.into_iter().rev().collect::<Vec<_>>(), elements.into_iter().rev().collect::<Vec<_>>());
+}
+
+/// Swap all pairs of elements and remove each element with `swap_remove`,
+/// and compare the results to a `Vec`
+fn check_swaps<C>(elements: Vec<C::Element>)
+    where C: CloneContainer + FromIterator<<C as ColumnContainer>::Element>, C::Element: Clone + PartialEq + ::std::fmt::Debug
+{
+    for a in 0..elements.len() {
+        for b in 0..elements.len() {
+            let mut column: C = elements.iter().cloned().collect();
+            let mut expected = elements.clone();
+            column.swap(a, b);
+            expected.swap(a, b);
+            assert_eq!(to_vec(&column), expected, "swap({}, {})", a, b);
+        }
+        let mut column: C = elements.iter().cloned().collect();
+        let mut expected = elements.clone();
+        assert_eq!(column.swap_remove(a), expected.swap_remove(a));
+        assert_eq!(to_vec(&column), expected, "swap_remove({})", a);
+    }
+}
+
+#[test]
+fn test_swap() {
+    check_swaps::<OptionColumn<Vec<u8>>>(vec![Some(1), None, Some(2), Some(3), None, None, Some(4), None]);
+    check_swaps::<ResultColumn<Vec<u8>, Vec<char>>>(vec![Ok(1), Err('a'), Ok(2), Ok(3), Err('b'), Err('c'), Ok(4), Err('d')]);
+    let strings = vec!["", "a", "bc", "", "dé", "ghij", "k", "ü"];
+    check_swaps::<StringColumn>(strings.into_iter().map(String::from).collect());
+}
+
+#[test]
+fn test_primitives() {
+    check(<u64 as Column>::new(), vec![1, 2, 3, 4, 5, 6]);
+    check(<f64 as Column>::new(), vec![0.5, 1.5, -2., 3., 4., 5.]);
+    check(<bool as Column>::new(), vec![true, false, false, true, true, false]);
+    check(<char as Column>::new(), vec!['a', 'b', 'c', 'd', 'e', 'f']);
+}
+
+#[test]
+fn test_string() {
+    let strings: Vec<String> = vec!["", "a", "bc", "", "def", "ghij", "k"].into_iter().map(String::from).collect();
+    check(<String as Column>::new(), strings.clone());
+
+    let mut column: StringColumn = strings.iter().cloned().collect();
+    assert_eq!(column.values(), "abcdefghijk");
+    assert_eq!(column.get(2), Some("bc"));
+    column.index_mut(4).make_ascii_uppercase();
+    assert_eq!(column.iter().collect::<Vec<_>>(), vec!["", "a", "bc", "", "DEF", "ghij", "k"]);
+}
+
+#[test]
+fn test_vec() {
+    let vecs = vec![vec![], vec![1u32], vec![2, 3], vec![], vec![4, 5, 6], vec![7], vec![8, 9]];
+    check(<Vec<u32> as Column>::new(), vecs.clone());
+
+    let mut column: VecColumn<u32> = vecs.into_iter().collect();
+    assert_eq!(column.values(), &vec![1, 2, 3, 4, 5, 6, 7, 8, 9]);
+    for values in column.iter_mut() {
+        values.reverse();
+    }
+    assert_eq!(column.get(4), Some(&[6, 5, 4][..]));
+    assert_eq!(column.iter().next_back(), Some(&[9, 8][..]));
+}
+
+#[test]
+fn test_option() {
+    let options = vec![Some(1u64), None, Some(2), Some(3), None, None, Some(4)];
+    check(<Option<u64> as Column>::new(), options.clone());
+
+    let mut column: OptionColumn<Vec<u64>> = options.into_iter().collect();
+    assert_eq!(column.values(), &vec![1, 2, 3, 4]);
+    assert_eq!(column.get(1), Some(None));
+    assert_eq!(column.get(2), Some(Some(&2)));
+    *column.index_mut(3).unwrap() = 30;
+    assert!(column.index_mut(4).is_none());
+    assert_eq!(column.iter().map(|value| value.cloned()).collect::<Vec<_>>(),
+               vec![Some(1), None, Some(2), Some(30), None, None, Some(4)]);
+
+    check(<Option<String> as Column>::new(), vec![None, Some("a".to_owned()), None, Some("bc".to_owned()), Some("".to_owned())]);
+}
+
+#[test]
+fn test_result() {
+    let results: Vec<Result<u64, String>> = vec![Ok(1), Err("a".to_owned()), Ok(2), Err("b".to_owned()), Err("c".to_owned()), Ok(3)];
+    check(<Result<u64, String> as Column>::new(), results.clone());
+
+    let mut column: ResultColumn<Vec<u64>, StringColumn> = results.into_iter().collect();
+    assert_eq!(column.oks(), &vec![1, 2, 3]);
+    assert_eq!(column.errs().values(), "abc");
+    assert_eq!(column.get(3), Some(Err("b")));
+    if let Ok(value) = column.index_mut(2) {
+        *value = 20;
+    }
+    assert_eq!(column.iter().filter_map(Result::ok).cloned().collect::<Vec<_>>(), vec![1, 20, 3]);
+}
+
+#[test]
+fn test_box() {
+    let points: Vec<Box<Point>> = (0..6).map(|i| Box::new(Point { x: i, y: i * i })).collect();
+    check(<Box<Point> as Column>::new(), points.clone());
+
+    let column: BoxColumn<PointColumn> = points.into_iter().collect();
+    assert_eq!(column.values().y, vec![0, 1, 4, 9, 16, 25]);
+    assert_eq!(*column.get(2).unwrap().x, 2);
+    check(<Box<u64> as Column>::new(), (0..5).map(Box::new).collect());
+}
+
+#[test]
+fn test_array() {
+    let arrays: Vec<[f64; 3]> = (0..6).map(|i| [i as f64, 0.5 * i as f64, -1.]).collect();
+    check(<[f64; 3] as Column>::new(), arrays.clone());
+
+    let mut column: ArrayColumn<Vec<f64>, 3> = arrays.into_iter().collect();
+    assert_eq!(column.columns()[1], vec![0., 0.5, 1., 1.5, 2., 2.5]);
+    for [x, _, z] in column.iter_mut() {
+        *z = *x * 2.;
+    }
+    assert_eq!(column.get(3), Some([&3., &1.5, &6.]));
+
+    let mut empty = <[u8; 0] as Column>::new();
+    empty.extend(vec![[], []]);
+    assert_eq!(empty.len(), 2);
+    assert_eq!(empty.iter().count(), 2);
+    check(<[String; 2] as Column>::new(), (0..5).map(|i| [i.to_string(), "x".repeat(i)]).collect());
+}
+
+#[test]
+fn test_materialize() {
+    let bitmap: Bitmap = vec![true, false, true, true, false].into_iter().collect();
+
+    let strings: StringColumn = vec!["a", "b", "c", "d", "e"].into_iter().map(String::from).collect();
+    assert_eq!(strings.materialize(&bitmap).iter().collect::<Vec<_>>(), vec!["a", "c", "d"]);
+
+    let options: OptionColumn<Vec<u8>> = vec![Some(1), None, None, Some(4), Some(5)].into_iter().collect();
+    assert_eq!(to_vec(&options.materialize(&bitmap)), vec![Some(1), None, Some(4)]);
+
+    let results: ResultColumn<Vec<u8>, Vec<char>> = vec![Ok(1), Err('b'), Err('c'), Ok(4), Ok(5)].into_iter().collect();
+    assert_eq!(to_vec(&results.materialize(&bitmap)), vec![Ok(1), Err('c'), Ok(4)]);
+
+    let arrays: ArrayColumn<Vec<u8>, 2> = (0..5).map(|i| [i, i + 1]).collect();
+    assert_eq!(to_vec(&arrays.materialize(&bitmap)), vec![[0, 1], [2, 3], [3, 4]]);
+}
+
+#[test]
+fn test_nested_fields() {
+    let records: Vec<Record> = (0..10).map(record).collect();
+    let mut column = <Record as Column>::with_capacity(records.len());
+    column.extend(records.clone());
+    assert_eq!(column.len(), 10);
+    assert_eq!(column.iter().map(|r| r.to_owned()).collect::<Vec<_>>(), records);
+
+    let fourth = column.get(4).unwrap();
+    assert_eq!(fourth.name, "record 4");
+    assert_eq!(fourth.score, Some(&40));
+    assert!(fourth.tags.is_empty());
+    assert_eq!(*fourth.origin.y, -4);
+    assert_eq!(fourth.position, [&4., &0.5, &-1.]);
+    assert_eq!(fourth.status, Ok(&4));
+    assert_eq!(column.get(3).unwrap().status, Err("error 3"));
+    assert_eq!(column.get(7).unwrap().tags, &[0, 1, 2][..]);
+
+    let mut expected = records.clone();
+    assert_eq!(column.remove(3), expected.remove(3));
+    column.retain(|r| r.score.is_some() || r.status.is_err());
+    expected.retain(|r| r.score.is_some() || r.status.is_err());
+    assert_eq!(column.iter().map(|r| r.to_owned()).collect::<Vec<_>>(), expected);
+
+    column.push(record(11));
+    expected.push(record(11));
+    assert_eq!(column.into_iter().collect::<Vec<_>>(), expected);
+}
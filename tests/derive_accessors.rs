@@ -0,0 +1,93 @@
+// Copyright 2017 columnar-rs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+#[macro_use] extern crate column_derive;
+extern crate column;
+use column::Column;
+
+#[derive(PartialEq, Debug, Clone, Column)]
+pub struct Data {
+    id: usize,
+    val: f64,
+}
+
+#[derive(PartialEq, Debug, Clone, Column)]
+pub struct Point(f32, #[column(skip)] u8, f32);
+
+#[derive(PartialEq, Debug, Clone, Column)]
+#[column(no_slices)]
+pub struct Outer {
+    #[column(nested)]
+    data: Data,
+    flag: bool,
+}
+
+mod other {
+    #[derive(Column)]
+    pub struct Sample {
+        pub value: f64,
+        weight: f64,
+    }
+
+    pub fn sample(value: f64) -> Sample {
+        Sample { value, weight: 1. }
+    }
+}
+
+#[test]
+fn test_accessors() {
+    let mut column = <Data as Column>::new();
+    column.extend((0..4).map(|id| Data { id, val: id as f64 }));
+    assert_eq!(column.id(), &[0, 1, 2, 3]);
+    for val in column.val_mut() {
+        *val *= 2.;
+    }
+    assert_eq!(column.val().iter().sum::<f64>(), 12.);
+}
+
+#[test]
+fn test_fields_mut() {
+    let mut column = <Data as Column>::new();
+    column.extend((0..4).map(|id| Data { id, val: 0. }));
+    {
+        let (ids, vals) = column.fields_mut();
+        for (val, id) in vals.iter_mut().zip(ids.iter()) {
+            *val = *id as f64 * 1.5;
+        }
+    }
+    assert_eq!(column.val(), &[0., 1.5, 3., 4.5]);
+}
+
+#[test]
+fn test_tuple() {
+    let mut column = <Point as Column>::new();
+    column.extend(vec![Point(1., 9, 2.), Point(3., 9, 4.)]);
+    assert_eq!(column.col0(), &[1., 3.]);
+    column.col2_mut()[1] = 5.;
+    assert_eq!(column.index(1), Point(3., 0, 5.));
+}
+
+#[test]
+fn test_nested() {
+    let mut column = <Outer as Column>::new();
+    column.extend(vec![Outer { data: Data { id: 1, val: 2. }, flag: true }]);
+    assert_eq!(column.data().id(), &[1]);
+    column.data_mut().val_mut()[0] = 3.;
+    let (data, flags) = column.fields_mut();
+    flags[0] = false;
+    assert_eq!(data.val(), &[3.]);
+    assert_eq!(column.index(0), Outer { data: Data { id: 1, val: 3. }, flag: false });
+}
+
+#[test]
+fn test_visibility() {
+    let mut column = <other::Sample as Column>::new();
+    column.extend(vec![other::sample(1.)]);
+    assert_eq!(column.value(), &[1.]);
+    column.value_mut()[0] = 2.;
+    assert_eq!(column.value, vec![2.]);
+}
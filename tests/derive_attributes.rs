@@ -0,0 +1,119 @@
+// Copyright 2017 columnar-rs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+#[macro_use] extern crate column_derive;
+extern crate column;
+use column::Column;
+use column::bitmap::{Bitmap, Materialize};
+
+/// A minimal `Vec`-like container to test custom storage
+#[derive(Debug, Default)]
+pub struct Storage<T> {
+    inner: Vec<T>,
+}
+
+impl<T> Storage<T> {
+    pub fn new() -> Self { Self { inner: Vec::new() } }
+    pub fn with_capacity(capacity: usize) -> Self { Self { inner: Vec::with_capacity(capacity) } }
+    pub fn push(&mut self, value: T) { self.inner.push(value) }
+    pub fn pop(&mut self) -> Option<T> { self.inner.pop() }
+    pub fn insert(&mut self, index: usize, value: T) { self.inner.insert(index, value) }
+    pub fn remove(&mut self, index: usize) -> T { self.inner.remove(index) }
+    pub fn swap_remove(&mut self, index: usize) -> T { self.inner.swap_remove(index) }
+    pub fn truncate(&mut self, len: usize) { self.inner.truncate(len) }
+    pub fn split_off(&mut self, at: usize) -> Self { Self { inner: self.inner.split_off(at) } }
+    pub fn append(&mut self, other: &mut Self) { self.inner.append(&mut other.inner) }
+    pub fn clear(&mut self) { self.inner.clear() }
+    pub fn reserve(&mut self, additional: usize) { self.inner.reserve(additional) }
+    pub fn capacity(&self) -> usize { self.inner.capacity() }
+    pub fn shrink_to_fit(&mut self) { self.inner.shrink_to_fit() }
+}
+
+impl<T> ::std::ops::Deref for Storage<T> {
+    type Target = [T];
+    fn deref(&self) -> &[T] { &self.inner }
+}
+
+impl<T> ::std::ops::DerefMut for Storage<T> {
+    fn deref_mut(&mut self) -> &mut [T] { &mut self.inner }
+}
+
+impl<T> IntoIterator for Storage<T> {
+    type Item = T;
+    type IntoIter = ::std::vec::IntoIter<T>;
+    fn into_iter(self) -> Self::IntoIter { self.inner.into_iter() }
+}
+
+#[derive(Eq, PartialEq, Debug, Clone, Column)]
+pub struct Data {
+    id: u64,
+    #[column(skip)]
+    cache: Option<u64>,
+    #[column(storage = "Storage")]
+    val: i32,
+}
+
+#[derive(Eq, PartialEq, Debug, Clone, Column)]
+pub struct Pair(#[column(skip)] u8, u16);
+
+mod other {
+    #[derive(Eq, PartialEq, Debug, Clone, Column)]
+    #[column(name = "OtherData")]
+    pub struct Data {
+        pub id: u64,
+    }
+}
+
+#[test]
+fn test_skip() {
+    let u = vec![Data { id: 1, cache: Some(3), val: -1 }, Data { id: 2, cache: None, val: 1 }];
+    let mut column = <Data as Column>::with_capacity(u.len());
+    column.extend(u);
+    assert_eq!(column.index(0), Data { id: 1, cache: None, val: -1 });
+    let result: Vec<_> = column.iter().map(|e| e.to_owned()).collect();
+    assert_eq!(result, vec![Data { id: 1, cache: None, val: -1 }, Data { id: 2, cache: None, val: 1 }]);
+}
+
+#[test]
+fn test_skip_tuple() {
+    let mut column = <Pair as Column>::new();
+    column.extend(vec![Pair(1, 2), Pair(3, 4)]);
+    assert_eq!(column.0, vec![2, 4]);
+    for e in &mut column {
+        *e.0 += 1;
+    }
+    assert_eq!(column.index(1), Pair(0, 5));
+}
+
+#[test]
+fn test_storage() {
+    let mut column = <Data as Column>::new();
+    column.extend(vec![Data { id: 1, cache: None, val: -1 }, Data { id: 2, cache: None, val: 1 }]);
+    assert_eq!(&*column.val, &[-1, 1]);
+    for e in column.iter_mut() {
+        *e.val *= 3;
+    }
+    *column.index_mut(0).val += 1;
+    assert_eq!(&*column.val, &[-2, 3]);
+    column.push(Data { id: 3, cache: None, val: 5 });
+    column.retain(|e| *e.val > 0);
+    assert_eq!(&*column.val, &[3, 5]);
+    let bitmap: Bitmap = vec![false, true].into_iter().collect();
+    assert_eq!(&*column.materialize(&bitmap).val, &[5]);
+    column.retain_by_bitmap(&bitmap);
+    assert_eq!(&*column.val, &[5]);
+    column.clear();
+    assert!(column.val.is_empty());
+}
+
+#[test]
+fn test_name() {
+    let mut column: other::OtherDataColumn = <other::Data as Column>::new();
+    column.extend(vec![other::Data { id: 7 }]);
+    let refs: Vec<other::OtherDataRef> = (&column).into_iter().collect();
+    assert_eq!(*refs[0].id, 7);
+}
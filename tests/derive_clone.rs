@@ -0,0 +1,127 @@
+// Copyright 2017 columnar-rs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+#[macro_use] extern crate column_derive;
+extern crate column;
+use column::Column;
+
+#[derive(PartialEq, Debug, Clone, Column)]
+pub struct Record {
+    name: String,
+    payload: Vec<u8>,
+}
+
+#[derive(PartialEq, Debug, Clone, Column)]
+pub enum Message {
+    Text(String),
+    Binary { data: Vec<u8>, id: u32 },
+    Close,
+}
+
+#[derive(PartialEq, Debug, Clone, Column)]
+#[column(no_slices)]
+pub struct Envelope {
+    #[column(nested)]
+    message: Message,
+    sender: String,
+}
+
+#[derive(PartialEq, Debug)]
+pub struct Token(u32);
+
+#[derive(PartialEq, Debug, Column)]
+pub struct Handle(Box<u32>, Token);
+
+#[derive(PartialEq, Debug, Column)]
+pub enum Lease {
+    Held(Token),
+    Free,
+}
+
+fn records() -> Vec<Record> {
+    (0..4).map(|i| Record { name: format!("r{}", i), payload: vec![i; i as usize] }).collect()
+}
+
+fn messages() -> Vec<Message> {
+    vec![
+        Message::Text("a".to_owned()),
+        Message::Binary { data: vec![1], id: 1 },
+        Message::Close,
+        Message::Text("b".to_owned()),
+        Message::Binary { data: vec![2, 3], id: 2 },
+    ]
+}
+
+#[test]
+fn test_clone() {
+    let u = records();
+    let original = u.clone();
+    let mut column = <Record as Column>::new();
+    column.extend(u);
+    let result: Vec<_> = column.iter().map(|e| e.to_owned()).collect();
+    assert_eq!(original, result);
+    for (index, e) in original.iter().enumerate() {
+        assert_eq!(*e, column.index(index));
+    }
+}
+
+#[test]
+fn test_remove() {
+    let mut original = records();
+    let mut column = <Record as Column>::new();
+    column.extend(original.clone());
+    assert_eq!(column.remove(1), original.remove(1));
+    assert_eq!(column.swap_remove(0), original.swap_remove(0));
+    assert_eq!(column.len(), 2);
+    let result: Vec<_> = column.iter().map(|e| e.to_owned()).collect();
+    assert_eq!(original, result);
+}
+
+#[test]
+fn test_remove_without_clone() {
+    let mut column = <Handle as Column>::new();
+    column.extend((0..3).map(|i| Handle(Box::new(i), Token(i))));
+    assert_eq!(column.swap_remove(0), Handle(Box::new(0), Token(0)));
+    assert_eq!(column.remove(1), Handle(Box::new(1), Token(1)));
+    assert_eq!(*column.0, vec![Box::new(2)]);
+    assert_eq!(*column.1, vec![Token(2)]);
+
+    let mut column = <Lease as Column>::new();
+    column.extend(vec![Lease::Held(Token(0)), Lease::Free, Lease::Held(Token(1))]);
+    assert_eq!(column.remove(0), Lease::Held(Token(0)));
+    assert_eq!(column.swap_remove(0), Lease::Free);
+    assert_eq!(column.pop(), Some(Lease::Held(Token(1))));
+}
+
+#[test]
+fn test_enum() {
+    let mut original = messages();
+    let mut column = <Message as Column>::new();
+    column.extend(original.clone());
+    assert_eq!(column.iter().map(|e| e.to_owned()).collect::<Vec<_>>(), original);
+    assert_eq!(column.remove(1), original.remove(1));
+    assert_eq!(column.swap_remove(0), original.swap_remove(0));
+    column.insert(1, Message::Binary { data: vec![4], id: 3 });
+    original.insert(1, Message::Binary { data: vec![4], id: 3 });
+    assert_eq!(column.iter().map(|e| e.to_owned()).collect::<Vec<_>>(), original);
+    for (index, e) in original.iter().enumerate() {
+        assert_eq!(*e, column.index(index));
+    }
+}
+
+#[test]
+fn test_nested() {
+    let mut original: Vec<_> = messages().into_iter().enumerate()
+        .map(|(i, message)| Envelope { message, sender: format!("s{}", i) })
+        .collect();
+    let mut column = <Envelope as Column>::new();
+    column.extend(original.clone());
+    assert_eq!(column.swap_remove(1), original.swap_remove(1));
+    assert_eq!(column.remove(2), original.remove(2));
+    let result: Vec<_> = column.iter().map(|e| e.to_owned()).collect();
+    assert_eq!(original, result);
+}
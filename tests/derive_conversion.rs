@@ -0,0 +1,68 @@
+// Copyright 2017 columnar-rs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+#[macro_use] extern crate column_derive;
+extern crate column;
+use column::Column;
+use column::tuple::Col;
+
+#[derive(PartialEq, Debug, Clone, Column)]
+pub struct Data {
+    id: u64,
+    #[column(skip)]
+    cache: Option<u8>,
+    val: f64,
+}
+
+#[derive(PartialEq, Debug, Clone, Column)]
+pub struct Point(f32, f32);
+
+#[derive(PartialEq, Debug, Clone, Column)]
+#[column(no_slices)]
+pub struct Pair<A: Copy> {
+    key: A,
+    #[column(nested)]
+    point: Point,
+}
+
+/// A generic operator over tuple columns
+fn scale(column: &mut Col<(Vec<u64>, Vec<f64>)>, factor: f64) {
+    for (_, val) in column {
+        *val *= factor;
+    }
+}
+
+#[test]
+fn test_struct() {
+    let mut column = <Data as Column>::new();
+    column.extend((0..3).map(|id| Data { id, cache: Some(1), val: id as f64 }));
+    let capacity = column.id.capacity();
+    let mut tuples = Col::from(column);
+    scale(&mut tuples, 2.);
+    assert_eq!(tuples.col0().len(), 3);
+    let column = DataColumn::from(tuples);
+    assert_eq!(column.id.capacity(), capacity);
+    assert_eq!(column.val, vec![0., 2., 4.]);
+}
+
+#[test]
+fn test_tuple_struct() {
+    let tuples = Col::from_inner((vec![1f32, 2.], vec![3f32, 4.]));
+    let column: PointColumn = tuples.into();
+    assert_eq!(column.iter().map(|p| p.to_owned()).collect::<Vec<_>>(), vec![Point(1., 3.), Point(2., 4.)]);
+    let (x, y) = Col::from(column).into_inner();
+    assert_eq!((x, y), (vec![1., 2.], vec![3., 4.]));
+}
+
+#[test]
+fn test_nested() {
+    let mut column = <Pair<u8> as Column>::new();
+    column.extend(vec![Pair { key: 1, point: Point(0., 1.) }]);
+    let (keys, points) = Col::from(column).into_inner();
+    assert_eq!(keys, vec![1]);
+    assert_eq!(points.1, vec![1.]);
+}
@@ -0,0 +1,190 @@
+// Copyright 2017 columnar-rs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+#[macro_use] extern crate column_derive;
+extern crate column;
+use column::Column;
+
+#[derive(Eq, PartialEq, Debug, Clone, Copy, Column)]
+pub enum Event {
+    Click { x: u32, y: u32 },
+    Key(char),
+    Quit,
+}
+
+#[derive(Eq, PartialEq, Debug, Clone, Copy, Column)]
+pub enum EventGen<A: Copy> {
+    Value(A),
+    Pair { a: A, b: u8 },
+}
+
+fn events() -> Vec<Event> {
+    vec![Event::Key('a'), Event::Click { x: 1, y: 2 }, Event::Quit, Event::Key('b'), Event::Click { x: 3, y: 4 }]
+}
+
+#[test]
+fn test() {
+    let u = events();
+    let original = u.clone();
+    let mut column = <Event as Column>::with_capacity(u.len());
+    column.extend(u);
+    assert_eq!(column.len(), original.len());
+    for (index, e) in original.iter().enumerate() {
+        assert_eq!(*e, column.index(index));
+    }
+}
+
+#[test]
+fn test_iter() {
+    let mut column = <Event as Column>::new();
+    column.extend(events());
+    let mut keys = vec![];
+    let mut clicks = 0;
+    let mut quits = 0;
+    for e in &column {
+        match e {
+            EventRef::Click { x, y } => {
+                assert_eq!(*x + 1, *y);
+                clicks += 1;
+            },
+            EventRef::Key(c) => keys.push(*c),
+            EventRef::Quit => quits += 1,
+        }
+    }
+    assert_eq!(keys, vec!['a', 'b']);
+    assert_eq!(clicks, 2);
+    assert_eq!(quits, 1);
+}
+
+#[test]
+fn test_clear() {
+    let mut column = <Event as Column>::new();
+    assert!(column.is_empty());
+    column.push(Event::Quit);
+    column.extend(events());
+    assert_eq!(column.len(), 6);
+    assert_eq!(column.index(0), Event::Quit);
+    column.clear();
+    assert!(column.is_empty());
+    assert_eq!(column.iter().count(), 0);
+}
+
+#[test]
+fn test_generic() {
+    let mut column = <EventGen<i64> as Column>::new();
+    column.extend(vec![EventGen::Pair { a: -1, b: 2 }, EventGen::Value(3)]);
+    assert_eq!(column.index(0), EventGen::Pair { a: -1, b: 2 });
+    assert_eq!(column.index(1), EventGen::Value(3));
+    let values: Vec<_> = column.iter().filter_map(|e| match e {
+        EventGenRef::Value(v) => Some(*v),
+        _ => None,
+    }).collect();
+    assert_eq!(values, vec![3]);
+}
+
+#[derive(Eq, PartialEq, Debug, Clone, Copy, Column)]
+pub enum Color {
+    Red,
+    Green,
+    Blue,
+}
+
+#[test]
+fn test_fieldless() {
+    let colors = vec![Color::Green, Color::Red, Color::Blue, Color::Green];
+    let mut column = <Color as Column>::new();
+    column.extend(colors.clone());
+    assert_eq!(column.len(), 4);
+    assert_eq!(column.iter().map(|c| c.to_owned()).collect::<Vec<_>>(), colors);
+    assert!(matches!(column.iter().nth(2), Some(ColorRef::Blue)));
+    column.insert(0, Color::Blue);
+    assert_eq!(column.remove(2), Color::Red);
+    column.retain(|c| !matches!(c, ColorRef::Blue));
+    assert_eq!(column.into_iter().collect::<Vec<_>>(), vec![Color::Green, Color::Green]);
+}
+
+#[derive(Eq, PartialEq, Debug, Clone, Column)]
+pub enum Similar {
+    A { b_c: u8 },
+    AB { c: String },
+}
+
+#[test]
+fn test_similar_names() {
+    let values = vec![Similar::AB { c: "x".to_owned() }, Similar::A { b_c: 1 }, Similar::AB { c: "y".to_owned() }];
+    let mut column = <Similar as Column>::new();
+    column.extend(values.clone());
+    assert_eq!(column.index(1), Similar::A { b_c: 1 });
+    assert_eq!(column.into_iter().collect::<Vec<_>>(), values);
+}
+
+#[derive(Eq, PartialEq, Debug, Clone, Column)]
+pub enum Shape {
+    Circle(u32),
+    Rect { w: u32, h: String },
+    Empty,
+}
+
+fn shapes(n: u32) -> Vec<Shape> {
+    (0..n).map(|i| match i % 5 {
+        0 | 3 => Shape::Circle(i),
+        1 => Shape::Rect { w: i, h: i.to_string() },
+        2 => Shape::Empty,
+        _ => Shape::Rect { w: i * 2, h: "r".to_owned() },
+    }).collect()
+}
+
+#[test]
+fn test_swap() {
+    for a in 0..9 {
+        for b in 0..9 {
+            let mut original = shapes(9);
+            let mut column: ShapeColumn = original.iter().cloned().collect();
+            column.swap(a, b);
+            original.swap(a, b);
+            assert_eq!(column.iter().map(|s| s.to_owned()).collect::<Vec<_>>(), original, "swap({}, {})", a, b);
+            column.push(Shape::Circle(100));
+            original.push(Shape::Circle(100));
+            assert_eq!(column.into_iter().collect::<Vec<_>>(), original);
+        }
+    }
+}
+
+#[test]
+fn test_swap_remove() {
+    for n in 1..9 {
+        for index in 0..n {
+            let mut original = shapes(n as u32);
+            let mut column: ShapeColumn = original.iter().cloned().collect();
+            assert_eq!(column.swap_remove(index), original.swap_remove(index));
+            assert_eq!(column.iter().map(|s| s.to_owned()).collect::<Vec<_>>(), original, "swap_remove({}) of {}", index, n);
+            column.insert(0, Shape::Empty);
+            original.insert(0, Shape::Empty);
+            assert_eq!(column.into_iter().rev().collect::<Vec<_>>(), original.into_iter().rev().collect::<Vec<_>>());
+        }
+    }
+}
+
+#[test]
+fn test_truncate_capacity() {
+    let mut column = <Shape as Column>::with_capacity(10);
+    assert!(column.capacity() >= 10);
+    assert_eq!(column.v1_h.capacity(), 0);
+    column.extend(shapes(10));
+    assert!(column.capacity() >= 10);
+    column.reserve(5);
+    assert!(column.capacity() >= 15);
+    for len in (0..12).rev() {
+        let mut original = shapes(10);
+        let mut truncated: ShapeColumn = original.iter().cloned().collect();
+        truncated.truncate(len);
+        original.truncate(len);
+        truncated.push(Shape::Rect { w: 0, h: "new".to_owned() });
+        original.push(Shape::Rect { w: 0, h: "new".to_owned() });
+        assert_eq!(truncated.into_iter().collect::<Vec<_>>(), original);
+    }
+}
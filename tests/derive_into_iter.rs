@@ -0,0 +1,96 @@
+// Copyright 2017 columnar-rs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+#[macro_use] extern crate column_derive;
+extern crate column;
+
+#[derive(PartialEq, Debug, Column)]
+pub struct Record {
+    id: u32,
+    name: String,
+    tags: Vec<String>,
+}
+
+#[derive(PartialEq, Debug, Column)]
+pub struct Cached(u8, #[column(skip)] Option<u8>);
+
+#[derive(PartialEq, Debug, Clone, Column)]
+pub enum Event {
+    Start,
+    Message(String),
+    Move { x: i32, y: i32 },
+}
+
+#[derive(PartialEq, Debug, Clone, Column)]
+#[column(no_slices)]
+pub struct Logged {
+    #[column(nested)]
+    event: Event,
+    time: u64,
+}
+
+fn records() -> Vec<Record> {
+    (0..4).map(|id| Record { id, name: format!("r{}", id), tags: vec![id.to_string()] }).collect()
+}
+
+fn events() -> Vec<Event> {
+    vec![
+        Event::Message("a".to_owned()),
+        Event::Start,
+        Event::Move { x: 1, y: 2 },
+        Event::Message("b".to_owned()),
+        Event::Start,
+    ]
+}
+
+#[test]
+fn test_struct() {
+    let column: RecordColumn = records().into_iter().collect();
+    assert_eq!(column.len(), 4);
+    assert_eq!(column.name, vec!["r0", "r1", "r2", "r3"]);
+    let mut iter = column.into_iter();
+    assert_eq!(iter.len(), 4);
+    assert_eq!(iter.next_back().map(|r| r.name), Some("r3".to_owned()));
+    assert_eq!(iter.nth(1).map(|r| r.tags), Some(vec!["1".to_owned()]));
+    assert_eq!(iter.collect::<Vec<_>>(), records().into_iter().skip(2).take(1).collect::<Vec<_>>());
+
+    let column: RecordColumn = records().into_iter().collect();
+    assert_eq!(column.into_iter().rev().map(|r| r.id).collect::<Vec<_>>(), vec![3, 2, 1, 0]);
+}
+
+#[test]
+fn test_skip() {
+    let column: CachedColumn = vec![Cached(1, Some(1)), Cached(2, None)].into_iter().collect();
+    assert_eq!(column.into_iter().collect::<Vec<_>>(), vec![Cached(1, None), Cached(2, None)]);
+}
+
+#[test]
+fn test_enum() {
+    let column: EventColumn = events().into_iter().collect();
+    assert_eq!(column.len(), 5);
+    assert_eq!(column.into_iter().collect::<Vec<_>>(), events());
+
+    let column: EventColumn = events().into_iter().collect();
+    let mut expected = events();
+    expected.reverse();
+    assert_eq!(column.into_iter().rev().collect::<Vec<_>>(), expected);
+
+    for n in 0..6 {
+        let column: EventColumn = events().into_iter().collect();
+        let mut iter = column.into_iter();
+        assert_eq!(iter.nth(n), events().into_iter().nth(n));
+        assert_eq!(iter.collect::<Vec<_>>(), events().into_iter().skip(n + 1).collect::<Vec<_>>());
+    }
+}
+
+#[test]
+fn test_nested() {
+    let logged: Vec<_> = events().into_iter().enumerate().map(|(time, event)| Logged { event, time: time as u64 }).collect();
+    let column: LoggedColumn = logged.clone().into_iter().collect();
+    assert_eq!(column.event.len(), 5);
+    assert_eq!(column.into_iter().collect::<Vec<_>>(), logged);
+}
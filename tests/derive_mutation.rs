@@ -0,0 +1,171 @@
+// Copyright 2017 columnar-rs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+#[macro_use] extern crate column_derive;
+extern crate column;
+use column::Column;
+
+#[derive(PartialEq, Debug, Clone, Column)]
+pub struct Data {
+    id: usize,
+    name: String,
+}
+
+#[derive(PartialEq, Debug, Clone, Column)]
+pub enum Value {
+    Int(i64),
+    Text { text: String },
+    Null,
+}
+
+#[derive(PartialEq, Debug, Clone, Column)]
+#[column(no_slices)]
+pub struct Cell {
+    row: usize,
+    #[column(nested)]
+    value: Value,
+}
+
+#[derive(PartialEq, Debug, Clone, Column)]
+pub struct Gen<A: Clone>(A);
+
+fn data(n: usize) -> Vec<Data> {
+    (0..n).map(|id| Data { id, name: id.to_string() }).collect()
+}
+
+fn cells(n: usize) -> Vec<Cell> {
+    (0..n).map(|row| {
+        let value = match row % 3 {
+            0 => Value::Int(row as i64),
+            1 => Value::Text { text: row.to_string() },
+            _ => Value::Null,
+        };
+        Cell { row, value }
+    }).collect()
+}
+
+fn to_vec(column: &DataColumn) -> Vec<Data> {
+    column.iter().map(|e| e.to_owned()).collect()
+}
+
+fn cells_to_vec(column: &CellColumn) -> Vec<Cell> {
+    column.iter().map(|e| e.to_owned()).collect()
+}
+
+#[test]
+fn test_push_pop_insert() {
+    let mut original = data(3);
+    let mut column = <Data as Column>::new();
+    for e in original.clone() {
+        column.push(e);
+    }
+    assert_eq!(column.pop(), original.pop());
+    column.insert(1, Data { id: 7, name: "7".to_owned() });
+    original.insert(1, Data { id: 7, name: "7".to_owned() });
+    assert_eq!(to_vec(&column), original);
+    column.clear();
+    assert_eq!(column.pop(), None);
+}
+
+#[test]
+fn test_truncate_resize() {
+    let mut column = <Data as Column>::new();
+    column.extend(data(5));
+    column.truncate(2);
+    assert_eq!(to_vec(&column), data(2));
+    let mut next = 2;
+    column.resize_with(4, || {
+        next += 1;
+        Data { id: next - 1, name: (next - 1).to_string() }
+    });
+    assert_eq!(to_vec(&column), data(4));
+    column.resize_with(1, || unreachable!());
+    assert_eq!(to_vec(&column), data(1));
+    column.shrink_to_fit();
+    assert_eq!(column.capacity(), 1);
+}
+
+#[test]
+fn test_split_off_append_drain() {
+    let mut column = <Data as Column>::new();
+    column.extend(data(6));
+    let mut tail = column.split_off(4);
+    assert_eq!(to_vec(&column), data(4));
+    assert_eq!(to_vec(&tail), data(6)[4..].to_vec());
+    column.append(&mut tail);
+    assert!(tail.is_empty());
+    assert_eq!(to_vec(&column), data(6));
+
+    let range = column.split_range(1..=2);
+    assert_eq!(to_vec(&range), data(3)[1..].to_vec());
+    let mut expected = data(6);
+    expected.drain(1..3);
+    assert_eq!(to_vec(&column), expected);
+    let drained: Vec<_> = column.drain(2..).collect();
+    assert_eq!(drained, expected.drain(2..).collect::<Vec<_>>());
+    assert_eq!(to_vec(&column), expected);
+    assert_eq!(column.split_range(..).len(), 2);
+    assert!(column.is_empty());
+}
+
+#[test]
+fn test_retain_swap() {
+    let mut column = <Data as Column>::new();
+    column.extend(data(6));
+    column.retain(|e| *e.id % 2 == 1);
+    assert_eq!(column.id, vec![1, 3, 5]);
+    assert_eq!(column.name, vec!["1", "3", "5"]);
+    column.swap(0, 2);
+    assert_eq!(column.id, vec![5, 3, 1]);
+    assert_eq!(column.name, vec!["5", "3", "1"]);
+}
+
+#[test]
+fn test_enum() {
+    let mut original = cells(9);
+    let mut column = <Cell as Column>::new();
+    column.extend(original.clone());
+
+    column.swap(0, 4);
+    original.swap(0, 4);
+    column.swap(1, 4);
+    original.swap(1, 4);
+    assert_eq!(cells_to_vec(&column), original);
+
+    let mut tail = column.split_off(5);
+    let tail_original = original.split_off(5);
+    assert_eq!(cells_to_vec(&tail), tail_original);
+    assert_eq!(cells_to_vec(&column), original);
+    column.append(&mut tail);
+    original.extend(tail_original);
+    assert_eq!(cells_to_vec(&column), original);
+
+    column.retain(|e| *e.row % 2 == 0);
+    original.retain(|e| e.row % 2 == 0);
+    assert_eq!(cells_to_vec(&column), original);
+
+    let range = column.split_range(1..3);
+    let range_original: Vec<_> = original.drain(1..3).collect();
+    assert_eq!(cells_to_vec(&range), range_original);
+    assert_eq!(cells_to_vec(&column), original);
+    let drained: Vec<_> = column.drain(..1).collect();
+    assert_eq!(drained, original.drain(..1).collect::<Vec<_>>());
+    assert_eq!(cells_to_vec(&column), original);
+
+    column.truncate(1);
+    original.truncate(1);
+    assert_eq!(column.pop(), original.pop());
+    assert!(column.is_empty());
+}
+
+#[test]
+fn test_generic() {
+    let mut column = <Gen<String> as Column>::new();
+    column.extend(vec![Gen("a".to_owned()), Gen("b".to_owned())]);
+    column.retain(|e| e.0 != "a");
+    assert_eq!(column.pop(), Some(Gen("b".to_owned())));
+}
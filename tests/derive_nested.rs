@@ -0,0 +1,91 @@
+// Copyright 2017 columnar-rs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+#[macro_use] extern crate column_derive;
+extern crate column;
+use column::Column;
+
+#[derive(PartialEq, Debug, Clone, Copy, Column)]
+pub struct Position(f64, f64);
+
+#[derive(PartialEq, Debug, Clone, Copy, Column)]
+pub enum Kind {
+    Point,
+    Circle { radius: f64 },
+}
+
+#[derive(PartialEq, Debug, Clone, Copy, Column)]
+#[column(no_slices)]
+pub struct Shape {
+    id: u64,
+    #[column(nested)]
+    position: Position,
+    #[column(nested)]
+    kind: Kind,
+}
+
+#[derive(PartialEq, Debug, Clone, Copy, Column)]
+#[column(no_slices)]
+pub struct Scene {
+    #[column(nested)]
+    shape: Shape,
+    visible: bool,
+}
+
+fn scenes() -> Vec<Scene> {
+    vec![
+        Scene { shape: Shape { id: 0, position: Position(0., 1.), kind: Kind::Point }, visible: true },
+        Scene { shape: Shape { id: 1, position: Position(2., 3.), kind: Kind::Circle { radius: 4. } }, visible: false },
+    ]
+}
+
+#[test]
+fn test() {
+    let u = scenes();
+    let original = u.clone();
+    let mut column = <Scene as Column>::with_capacity(u.len());
+    column.extend(u);
+    assert_eq!(column.len(), original.len());
+    let result: Vec<_> = column.iter().map(|e| e.to_owned()).collect();
+    assert_eq!(original, result);
+    for (index, e) in original.iter().enumerate() {
+        assert_eq!(*e, column.index(index));
+    }
+}
+
+#[test]
+fn test_iter() {
+    let mut column = <Scene as Column>::new();
+    column.extend(scenes());
+    let ids: Vec<_> = column.iter().map(|e| *e.shape.id).collect();
+    assert_eq!(ids, vec![0, 1]);
+    let xs: Vec<_> = column.iter().map(|e| *e.shape.position.0).collect();
+    assert_eq!(xs, vec![0., 2.]);
+    let radii: Vec<_> = column.iter().filter_map(|e| match e.shape.kind {
+        KindRef::Circle { radius } => Some(*radius),
+        KindRef::Point => None,
+    }).collect();
+    assert_eq!(radii, vec![4.]);
+}
+
+#[test]
+fn test_iter_mut() {
+    let mut column = <Scene as Column>::new();
+    column.extend(scenes());
+    for e in &mut column {
+        *e.shape.position.1 *= 2.;
+        if let KindRefMut::Circle { radius } = e.shape.kind {
+            *radius += 1.;
+        }
+    }
+    *column.index_mut(0).shape.id += 10;
+    assert_eq!(column.index(0).shape.id, 10);
+    assert_eq!(column.index(0).shape.position, Position(0., 2.));
+    assert_eq!(column.index(1).shape.kind, Kind::Circle { radius: 5. });
+    column.clear();
+    assert!(column.is_empty());
+}
@@ -0,0 +1,74 @@
+// Copyright 2017 columnar-rs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+#[macro_use] extern crate column_derive;
+extern crate column;
+use column::Column;
+
+#[derive(PartialEq, Debug, Clone, Copy, Column)]
+pub struct Position(f64, f64);
+
+#[derive(PartialEq, Debug, Clone, Column)]
+#[column(project = "id, val")]
+#[column(project = "position", project = "val, name")]
+#[column(no_slices)]
+pub struct Data {
+    id: usize,
+    val: f64,
+    name: String,
+    #[column(nested)]
+    position: Position,
+}
+
+#[derive(PartialEq, Debug, Clone, Column)]
+#[column(project = "2, 0")]
+pub struct Triple<A: Clone>(A, #[column(skip)] u8, f32);
+
+fn column() -> DataColumn {
+    let mut column = <Data as Column>::new();
+    column.extend((0..3).map(|id| Data {
+        id,
+        val: id as f64,
+        name: id.to_string(),
+        position: Position(0., id as f64),
+    }));
+    column
+}
+
+#[test]
+fn test_project() {
+    let column = column();
+    let pairs: Vec<_> = column.iter_id_val().map(|e: DataIdValRef| (*e.id, *e.val)).collect();
+    assert_eq!(pairs, vec![(0, 0.), (1, 1.), (2, 2.)]);
+    let names: Vec<_> = column.iter_val_name().map(|e| e.name.clone()).collect();
+    assert_eq!(names, vec!["0", "1", "2"]);
+    let ys: Vec<_> = column.iter_position().map(|e| *e.position.1).collect();
+    assert_eq!(ys, vec![0., 1., 2.]);
+}
+
+#[test]
+fn test_project_mut() {
+    let mut column = column();
+    for e in column.iter_id_val_mut() {
+        *e.val += *e.id as f64;
+    }
+    for e in column.iter_position_mut() {
+        *e.position.0 = 1.;
+    }
+    assert_eq!(column.index(2), Data { id: 2, val: 4., name: "2".to_owned(), position: Position(1., 2.) });
+}
+
+#[test]
+fn test_project_tuple() {
+    let mut column = <Triple<i32> as Column>::new();
+    column.extend(vec![Triple(1, 0, 2.), Triple(3, 0, 4.)]);
+    for e in column.iter_col2_col0_mut() {
+        *e.0 += *e.1 as f32;
+    }
+    let pairs: Vec<_> = column.iter_col2_col0().map(|e| (*e.0, *e.1)).collect();
+    assert_eq!(pairs, vec![(3., 1), (7., 3)]);
+}
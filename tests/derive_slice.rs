@@ -0,0 +1,112 @@
+// Copyright 2017 columnar-rs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+#[macro_use] extern crate column_derive;
+extern crate column;
+use column::Column;
+
+#[derive(PartialEq, Debug, Clone, Column)]
+pub struct Data {
+    id: usize,
+    val: f64,
+}
+
+#[derive(PartialEq, Debug, Clone, Column)]
+pub struct Pair<A: Clone>(A, String);
+
+fn column(n: usize) -> DataColumn {
+    let mut column = <Data as Column>::new();
+    column.extend((0..n).map(|id| Data { id, val: id as f64 / 2. }));
+    column
+}
+
+fn ids<'a, I: IntoIterator<Item=DataRef<'a>>>(iter: I) -> Vec<usize> {
+    iter.into_iter().map(|e| *e.id).collect()
+}
+
+#[test]
+fn test_slice() {
+    let column = column(6);
+    let slice = column.slice(1..4);
+    assert_eq!(slice.len(), 3);
+    assert_eq!(ids(slice), vec![1, 2, 3]);
+    assert_eq!(slice.index(1), Data { id: 2, val: 1. });
+    let inner = slice.slice(1..);
+    assert_eq!(ids(inner.iter()), vec![2, 3]);
+    assert!(slice.slice(..0).is_empty());
+    assert_eq!(column.as_slice().len(), 6);
+
+    let (left, right) = column.split_at(2);
+    assert_eq!(ids(left), vec![0, 1]);
+    assert_eq!(ids(right), vec![2, 3, 4, 5]);
+}
+
+#[test]
+fn test_chunks_windows() {
+    let column = column(5);
+    let chunks: Vec<_> = column.chunks(2).map(ids).collect();
+    assert_eq!(chunks, vec![vec![0, 1], vec![2, 3], vec![4]]);
+    let chunks: Vec<_> = column.chunks_exact(2).map(ids).collect();
+    assert_eq!(chunks, vec![vec![0, 1], vec![2, 3]]);
+    let windows: Vec<_> = column.slice(1..).windows(3).map(ids).collect();
+    assert_eq!(windows, vec![vec![1, 2, 3], vec![2, 3, 4]]);
+    assert_eq!(column.windows(6).count(), 0);
+}
+
+#[test]
+fn test_slice_mut() {
+    let mut column = column(6);
+    {
+        let mut slice = column.slice_mut(2..);
+        for e in slice.iter_mut() {
+            *e.id += 10;
+        }
+        *slice.index_mut(0).val = -1.;
+        let (mut left, right) = slice.split_at_mut(1);
+        *left.index_mut(0).id += 1;
+        assert_eq!(ids(right.iter()), vec![13, 14, 15]);
+        assert_eq!(ids(slice.slice(..2)), vec![13, 13]);
+    }
+    assert_eq!(column.id, vec![0, 1, 13, 13, 14, 15]);
+    assert_eq!(column.index(2).val, -1.);
+
+    for (index, mut chunk) in column.chunks_exact_mut(4).enumerate() {
+        for e in chunk.iter_mut() {
+            *e.id = index;
+        }
+    }
+    assert_eq!(column.id, vec![0, 0, 0, 0, 14, 15]);
+}
+
+#[test]
+fn test_threads() {
+    let mut column = column(100);
+    ::std::thread::scope(|scope| {
+        for slice in column.chunks_mut(30) {
+            scope.spawn(move || {
+                for e in slice {
+                    *e.val *= 2.;
+                }
+            });
+        }
+    });
+    let vals: Vec<_> = column.iter().map(|e| *e.val).collect();
+    assert_eq!(vals, (0..100).map(|id| id as f64).collect::<Vec<_>>());
+}
+
+#[test]
+fn test_generic() {
+    let mut column = <Pair<u8> as Column>::new();
+    column.extend(vec![Pair(1, "a".to_owned()), Pair(2, "b".to_owned()), Pair(3, "c".to_owned())]);
+    let (left, right) = column.split_at(1);
+    assert_eq!(left.index(0), Pair(1, "a".to_owned()));
+    assert_eq!(right.iter().map(|e| e.1.clone()).collect::<Vec<_>>(), vec!["b", "c"]);
+    for mut chunk in column.chunks_mut(2) {
+        *chunk.index_mut(0).0 *= 10;
+    }
+    assert_eq!(column.0, vec![10, 2, 30]);
+}
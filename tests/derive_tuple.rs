@@ -0,0 +1,64 @@
+// Copyright 2017 columnar-rs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+#[macro_use] extern crate column_derive;
+extern crate column;
+use column::Column;
+
+#[derive(PartialEq, Debug, Clone, Column)]
+pub struct Point(f32, f32);
+
+#[derive(Eq, PartialEq, Debug, Clone, Column)]
+pub struct UserId(u64);
+
+#[derive(PartialEq, Debug, Clone, Column)]
+pub struct Pair<A: Copy>(A, pub f64);
+
+#[test]
+fn test() {
+    let u = vec![Point(1., 2.), Point(3., 4.)];
+    let original = u.clone();
+    let mut column = <Point as Column>::with_capacity(u.len());
+    column.extend(u);
+    let result: Vec<_> = column.iter().map(|e| PointRef::to_owned(&e)).collect();
+    assert_eq!(original, result);
+    assert_eq!(column.0, vec![1., 3.]);
+    assert_eq!(column.1, vec![2., 4.]);
+}
+
+#[test]
+fn test_mul_2() {
+    let mut column = <Point as Column>::new();
+    column.extend(vec![Point(1., 2.), Point(3., 4.)]);
+    for e in &mut column {
+        *e.0 *= 2.;
+    }
+    for (index, e) in column.iter().enumerate() {
+        assert_eq!(*e.0, (1 + 2 * index) as f32 * 2.);
+        assert_eq!(*e.1, (2 + 2 * index) as f32);
+    }
+}
+
+#[test]
+fn test_newtype() {
+    let mut column = <UserId as Column>::new();
+    assert!(column.is_empty());
+    column.extend((0..4).map(UserId));
+    assert_eq!(column.len(), 4);
+    *column.index_mut(2).0 += 10;
+    assert_eq!(column.index(2), UserId(12));
+    column.clear();
+    assert!(column.is_empty());
+}
+
+#[test]
+fn test_generic() {
+    let mut column = <Pair<&str> as Column>::new();
+    column.extend(vec![Pair("a", 1.), Pair("b", 2.)]);
+    assert_eq!(column.index(1), Pair("b", 2.));
+    assert_eq!(column.iter().map(|e| *e.0).collect::<Vec<_>>(), vec!["a", "b"]);
+}
@@ -0,0 +1,141 @@
+// Copyright 2017 columnar-rs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+#[macro_use] extern crate column_derive;
+extern crate column;
+use column::Column;
+use column::tuple::Col;
+
+#[derive(PartialEq, Debug, Clone, Column)]
+#[column(project = "val")]
+pub struct Data {
+    id: usize,
+    val: f64,
+}
+
+#[derive(PartialEq, Debug, Clone, Column)]
+pub enum Shape {
+    Point,
+    Circle(f64),
+    Rect { w: f64, h: f64 },
+}
+
+#[derive(PartialEq, Debug, Clone, Column)]
+#[column(no_slices)]
+pub struct Tagged {
+    #[column(nested)]
+    shape: Shape,
+    tag: u8,
+}
+
+fn data(n: usize) -> DataColumn {
+    let mut column = <Data as Column>::new();
+    column.extend((0..n).map(|id| Data { id, val: id as f64 }));
+    column
+}
+
+fn shapes() -> Vec<Shape> {
+    vec![
+        Shape::Circle(1.),
+        Shape::Point,
+        Shape::Rect { w: 2., h: 3. },
+        Shape::Circle(4.),
+        Shape::Rect { w: 5., h: 6. },
+        Shape::Point,
+        Shape::Circle(7.),
+    ]
+}
+
+#[test]
+fn test_struct() {
+    let mut column = data(5);
+    let mut iter = column.iter();
+    assert_eq!(iter.len(), 5);
+    assert_eq!(*iter.next_back().unwrap().id, 4);
+    assert_eq!(*iter.nth(1).unwrap().id, 1);
+    assert_eq!(iter.size_hint(), (2, Some(2)));
+    assert_eq!(iter.rev().map(|e| *e.id).collect::<Vec<_>>(), vec![3, 2]);
+
+    for e in column.iter_mut().rev().take(2) {
+        *e.val = -1.;
+    }
+    assert_eq!(column.iter_val().rev().map(|e| *e.val).collect::<Vec<_>>(), vec![-1., -1., 2., 1., 0.]);
+    let mut iter = column.iter_val_mut();
+    assert_eq!(*iter.nth(4).unwrap().val, -1.);
+    assert!(iter.next().is_none());
+    assert!(iter.next().is_none());
+}
+
+#[test]
+fn test_enum() {
+    let mut column = <Shape as Column>::new();
+    column.extend(shapes());
+    let reversed: Vec<_> = column.iter().rev().map(|e| e.to_owned()).collect();
+    let mut expected = shapes();
+    expected.reverse();
+    assert_eq!(reversed, expected);
+    for n in 0..8 {
+        let mut iter = column.iter();
+        assert_eq!(iter.nth(n).map(|e| e.to_owned()), shapes().into_iter().nth(n));
+        assert_eq!(iter.len(), 7usize.saturating_sub(n + 1));
+        assert_eq!(iter.map(|e| e.to_owned()).collect::<Vec<_>>(), shapes().into_iter().skip(n + 1).collect::<Vec<_>>());
+    }
+    let mut iter = column.iter_mut();
+    iter.nth(2);
+    if let Some(ShapeRefMut::Circle(r)) = iter.next_back() {
+        *r = 0.;
+    }
+    assert_eq!(column.index(6), Shape::Circle(0.));
+}
+
+#[test]
+fn test_nested() {
+    let mut column = <Tagged as Column>::new();
+    column.extend(shapes().into_iter().enumerate().map(|(i, shape)| Tagged { shape, tag: i as u8 }));
+    let tags: Vec<_> = column.iter().rev().skip(1).step_by(2).map(|e| *e.tag).collect();
+    assert_eq!(tags, vec![5, 3, 1]);
+    assert_eq!(column.iter().nth(4).unwrap().to_owned(), Tagged { shape: Shape::Rect { w: 5., h: 6. }, tag: 4 });
+}
+
+#[test]
+fn test_tuple() {
+    let mut column = <Col<(u8, char)> as Column>::new();
+    column.extend(vec![(1, 'a'), (2, 'b'), (3, 'c')]);
+    let mut iter = column.iter();
+    assert_eq!(iter.len(), 3);
+    assert_eq!(iter.next_back(), Some((&3, &'c')));
+    assert_eq!(iter.nth(1), Some((&2, &'b')));
+    assert_eq!(iter.next(), None);
+    for e in column.iter_mut().rev().take(1) {
+        *e.0 = 30;
+    }
+    assert_eq!(column.iter().map(|e| *e.0).collect::<Vec<_>>(), vec![1, 2, 30]);
+}
+
+#[test]
+fn test_chunks_windows() {
+    let mut column = data(7);
+    let ids = |slice: DataSlice| slice.iter().map(|e| *e.id).collect::<Vec<_>>();
+    assert_eq!(column.chunks(3).len(), 3);
+    assert_eq!(column.chunks_exact(3).len(), 2);
+    assert_eq!(column.windows(3).len(), 5);
+    assert_eq!(column.chunks(3).rev().map(ids).collect::<Vec<_>>(), vec![vec![6], vec![3, 4, 5], vec![0, 1, 2]]);
+    assert_eq!(column.chunks_exact(3).rev().map(ids).collect::<Vec<_>>(), vec![vec![3, 4, 5], vec![0, 1, 2]]);
+    assert_eq!(column.chunks(2).nth(2).map(ids), Some(vec![4, 5]));
+    assert_eq!(column.windows(3).rev().nth(1).map(ids), Some(vec![3, 4, 5]));
+    assert_eq!(column.windows(3).nth(4).map(ids), Some(vec![4, 5, 6]));
+    assert!(column.windows(3).nth(5).is_none());
+
+    let mut chunks = column.chunks_exact_mut(2);
+    assert_eq!(chunks.len(), 3);
+    let mut last = chunks.next_back().unwrap();
+    *last.index_mut(1).id = 0;
+    let mut second = chunks.nth(1).unwrap();
+    *second.index_mut(0).id = 0;
+    assert!(chunks.next().is_none());
+    assert_eq!(column.id, vec![0, 1, 0, 3, 4, 0, 6]);
+}
@@ -8,7 +8,7 @@
 
 extern crate column;
 use ::column::Column;
-use ::column::tuple::Col;
+use ::column::tuple::{Col, Nested};
 
 #[test]
 fn test() {
@@ -20,3 +20,83 @@ fn test() {
     assert_eq!(original, result);
 }
 
+
+#[test]
+fn test_mutation() {
+    let mut column = <Col<(u64, String, bool)> as Column>::new();
+    column.reserve(4);
+    assert!(column.capacity() >= 4);
+    column.push((1, "a".to_owned(), true));
+    column.push((2, "b".to_owned(), false));
+    column.push((3, "c".to_owned(), true));
+    assert_eq!(column.len(), 3);
+    assert_eq!(column.index(1), (2, "b".to_owned(), false));
+    assert_eq!(column.get(2), Some((&3, &"c".to_owned(), &true)));
+    assert_eq!(column.get(3), None);
+
+    {
+        let (id, name, _) = column.index_mut(0);
+        *id = 10;
+        name.push('!');
+    }
+    column.col2_mut()[1] = true;
+    assert_eq!(column.col0(), &[10, 2, 3]);
+    assert_eq!(column.col1(), &["a!".to_owned(), "b".to_owned(), "c".to_owned()]);
+    assert_eq!(column.col2(), &[true, true, true]);
+
+    column.truncate(2);
+    assert_eq!(column.len(), 2);
+    let (ids, names, flags) = column.into_inner();
+    assert_eq!(ids, vec![10, 2]);
+    assert_eq!(names, vec!["a!".to_owned(), "b".to_owned()]);
+    assert_eq!(flags, vec![true, true]);
+
+    let mut column = <Col<(u8,)> as Column>::new();
+    column.extend(vec![(1,), (2,)]);
+    column.clear();
+    assert!(column.is_empty());
+}
+
+#[test]
+fn test_nested() {
+    let joined = vec![((1u64, "a".to_owned()), (1u64, 0.5f64)), ((2, "b".to_owned()), (2, 1.5))];
+    let mut column = <Nested<((u64, String), (u64, f64))> as Column>::new();
+    column.extend(joined.clone());
+    assert_eq!(column.len(), 2);
+    assert_eq!(column.iter().map(|((k, v1), (_, v2))| (*k, v1.clone(), *v2)).collect::<Vec<_>>(),
+               vec![(1, "a".to_owned(), 0.5), (2, "b".to_owned(), 1.5)]);
+    assert_eq!(column.index(1), joined[1]);
+    assert_eq!(column.get(0), Some(((&1, &"a".to_owned()), (&1, &0.5))));
+
+    for ((_, name), (_, value)) in &mut column {
+        name.push('!');
+        *value *= 2.;
+    }
+    let (left, right) = column.into_inner();
+    assert_eq!(left.col1(), &["a!".to_owned(), "b!".to_owned()]);
+    assert_eq!(right.into_inner(), (vec![1, 2], vec![1., 3.]));
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Point {
+    x: f32,
+    y: f32,
+}
+
+#[test]
+fn test_any_type() {
+    let mut column = <Col<(Point, (u8, u8))> as Column>::new();
+    column.push((Point { x: 1., y: 2. }, (3, 4)));
+    assert_eq!(column.col0(), &[Point { x: 1., y: 2. }]);
+    assert_eq!(column.col1(), &[(3, 4)]);
+    assert_eq!(column.index(0), (Point { x: 1., y: 2. }, (3, 4)));
+}
+
+#[test]
+fn test_wide() {
+    type Wide = (u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, (u16, u32));
+    let mut column = <Nested<Wide> as Column>::with_capacity(2);
+    column.push((0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, (15, 16)));
+    assert_eq!(column.col15().col1(), &[16]);
+    assert_eq!(column.iter().next().map(|e| (*e.14, *(e.15).0)), Some((14, 15)));
+}